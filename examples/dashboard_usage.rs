@@ -1,25 +1,25 @@
-/// Example demonstrating how to use the MeCP monitoring dashboard with MySQL integration
-/// 
-/// This example shows how to:
-/// 1. Create a metrics collector with MySQL backend
-/// 2. Start the HTTP server with dashboard
-/// 3. Access metrics and logs programmatically
-/// 
-/// Prerequisites:
-/// - MySQL server running
-/// - Database initialized with `./scripts/init-mysql-db.sh`
-/// 
-/// Run with:
-/// ```
-/// cargo run --example dashboard_usage
-/// ```
-/// 
-/// Then open http://127.0.0.1:3000/dashboard in your browser
+//! Example demonstrating how to use the MeCP monitoring dashboard with MySQL integration
+//! 
+//! This example shows how to:
+//! 1. Create a metrics collector with MySQL backend
+//! 2. Start the HTTP server with dashboard
+//! 3. Access metrics and logs programmatically
+//! 
+//! Prerequisites:
+//! - MySQL server running
+//! - Database initialized with `./scripts/init-mysql-db.sh`
+//! 
+//! Run with:
+//! ```
+//! cargo run --example dashboard_usage
+//! ```
+//! 
+//! Then open http://127.0.0.1:3000/dashboard in your browser
 
 use mecp::core::{
     http_server::HttpServer,
     server::McpServer,
-    metrics::{MetricsCollector, MySqlMetricsWriter},
+    metrics::MetricsCollector,
 };
 use std::sync::Arc;
 
@@ -45,7 +45,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Option 2: Create metrics collector with MySQL backend (uncomment to use)
     // println!("2. Creating metrics collector with MySQL backend...");
-    // let mysql_writer = Arc::new(MySqlMetricsWriter::new(
+    // let mysql_writer = Arc::new(mecp::core::metrics::MySqlMetricsWriter::new(
     //     "localhost",
     //     3306,
     //     "mecp_db",
@@ -61,7 +61,7 @@ async fn main() -> anyhow::Result<()> {
         .and_then(|p| p.parse().ok())
         .unwrap_or(3000);
 
-    let server = HttpServer::with_metrics(mcp_server, metrics, port);
+    let server = HttpServer::with_metrics(mcp_server, metrics, "127.0.0.1".to_string(), port);
 
     println!();
     println!("✓ Server starting on http://127.0.0.1:{}", port);
@@ -1,22 +1,22 @@
-/// MeCP Test Client
-/// 
-/// Simulates realistic client traffic to test the entire monitoring flow:
-/// Client -> MeCP Server -> Database -> Dashboard
-/// 
-/// This client sends various MCP requests including both successful calls
-/// and intentional errors to verify error tracking.
-/// 
-/// Usage:
-/// ```bash
-/// # Send 50 requests (default)
-/// cargo run --example test_client
-/// 
-/// # Send custom number of requests
-/// cargo run --example test_client -- 100
-/// 
-/// # Use custom server URL
-/// MCP_URL=http://localhost:3000 cargo run --example test_client
-/// ```
+//! MeCP Test Client
+//! 
+//! Simulates realistic client traffic to test the entire monitoring flow:
+//! Client -> MeCP Server -> Database -> Dashboard
+//! 
+//! This client sends various MCP requests including both successful calls
+//! and intentional errors to verify error tracking.
+//! 
+//! Usage:
+//! ```bash
+//! # Send 50 requests (default)
+//! cargo run --example test_client
+//! 
+//! # Send custom number of requests
+//! cargo run --example test_client -- 100
+//! 
+//! # Use custom server URL
+//! MCP_URL=http://localhost:3000 cargo run --example test_client
+//! ```
 
 use serde_json::json;
 use std::time::Duration;
@@ -306,8 +306,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let requests_per_type = num_requests / 8;
 
     // Test data
-    let names = vec!["Alice", "Bob", "Charlie", "Diana", "Eve", "Frank", "Grace", "Henry"];
-    let topics = vec![
+    let names = ["Alice", "Bob", "Charlie", "Diana", "Eve", "Frank", "Grace", "Henry"];
+    let topics = [
         "Rust programming",
         "AI development",
         "Database design",
@@ -317,7 +317,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         "Performance optimization",
         "Security best practices",
     ];
-    let fake_tools = vec![
+    let fake_tools = [
         "nonexistent_tool",
         "invalid_tool",
         "unknown_tool",
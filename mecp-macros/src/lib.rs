@@ -0,0 +1,606 @@
+//! Procedural macros for MeCP
+//!
+//! Use them through their re-exports in `mecp::tools`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, ExprLit, Fields, FnArg, ItemFn, Lit, LitStr,
+    Meta, Pat, ReturnType, Token, Type,
+};
+
+/// Turn an `async fn` into a tool
+///
+/// Generates a unit struct named after the function in CamelCase with a
+/// `Tool` suffix, implementing `mecp::tools::Tool`. Each argument becomes a
+/// property of the input schema, built from its type's `InputSchema`; an
+/// `Option` argument is optional. A `&RequestContext` argument receives the
+/// calling request. The function's doc comment is the tool description.
+///
+/// ```ignore
+/// /// Greets a user
+/// #[tool]
+/// async fn greet(#[arg(description = "Name to greet")] name: Option<String>) -> anyhow::Result<String> {
+///     Ok(format!("Hello, {}!", name.as_deref().unwrap_or("World")))
+/// }
+///
+/// server.register_tool(Box::new(GreetTool)).await?;
+/// ```
+///
+/// `#[tool(name = "...", description = "...")]` overrides the defaults, and
+/// `#[tool(cache_ttl_secs = 60)]` lets a server's result cache reuse results
+/// for that long. Results are shared by every call of the same user with the
+/// same arguments, so only set it on tools whose output doesn't depend on
+/// anything else about the session, such as its roots. `title = "..."`,
+/// `read_only_hint`, `destructive_hint`, `idempotent_hint` and
+/// `open_world_hint` (with `= true` or `= false`) set the tool's annotations.
+#[proc_macro_attribute]
+pub fn tool(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let options = parse_macro_input!(attr with Punctuated::<Meta, Token![,]>::parse_terminated);
+    let function = parse_macro_input!(item as ItemFn);
+    expand_tool(options, function)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Derive `mecp::tools::InputSchema` for a struct or a unit-only enum
+///
+/// Struct fields become object properties, described by their doc comments.
+/// `Option` fields are optional and also accept `null`. The serde attributes
+/// that change what is deserialized are honoured: `rename` and `rename_all`
+/// set property names, `default` on a field or the whole struct makes
+/// properties optional, `skip` and `skip_deserializing` leave fields out,
+/// `flatten` merges a field's properties into the struct's, and
+/// `deny_unknown_fields` forbids other properties.
+/// Enums become a string schema listing their variant names.
+#[proc_macro_derive(InputSchema)]
+pub fn derive_input_schema(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    expand_input_schema(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand_tool(options: Punctuated<Meta, Token![,]>, mut function: ItemFn) -> syn::Result<TokenStream2> {
+    let signature = &function.sig;
+    if signature.asyncness.is_none() {
+        return Err(Error::new(signature.fn_token.span, "#[tool] functions must be async"));
+    }
+    if !signature.generics.params.is_empty() {
+        return Err(Error::new(signature.generics.span(), "#[tool] functions cannot be generic"));
+    }
+    if let ReturnType::Default = signature.output {
+        return Err(Error::new(signature.span(), "#[tool] functions must return a Result"));
+    }
+
+    let mut name = signature.ident.to_string();
+    let mut description = doc_comment(&function.attrs);
+    let mut cache_ttl = quote!(::std::option::Option::None);
+    let mut annotations = Vec::new();
+    for option in &options {
+        // A bare hint flag means `= true`
+        if let Meta::Path(path) = option {
+            if let Some(hint) = HINTS.iter().find(|hint| path.is_ident(hint)) {
+                let field = format_ident!("{}", hint);
+                annotations.push(quote!(#field: ::std::option::Option::Some(true)));
+                continue;
+            }
+        }
+        let Meta::NameValue(pair) = option else {
+            return Err(Error::new(option.span(), TOOL_OPTIONS));
+        };
+        if let Some(hint) = HINTS.iter().find(|hint| pair.path.is_ident(hint)) {
+            let field = format_ident!("{}", hint);
+            let value = bool_value(&pair.value)?;
+            annotations.push(quote!(#field: ::std::option::Option::Some(#value)));
+            continue;
+        }
+        if pair.path.is_ident("name") {
+            name = string_value(&pair.value)?;
+        } else if pair.path.is_ident("description") {
+            description = Some(string_value(&pair.value)?);
+        } else if pair.path.is_ident("title") {
+            let title = string_value(&pair.value)?;
+            annotations.push(quote!(title: ::std::option::Option::Some(#title.to_string())));
+        } else if pair.path.is_ident("cache_ttl_secs") {
+            let secs = int_value(&pair.value)?;
+            cache_ttl = quote!(::std::option::Option::Some(::std::time::Duration::from_secs(#secs)));
+        } else {
+            return Err(Error::new(pair.path.span(), format!("unknown #[tool] option; {}", TOOL_OPTIONS)));
+        }
+    }
+    let description = description.unwrap_or_default();
+    let annotations = if annotations.is_empty() {
+        quote!(::std::option::Option::None)
+    } else {
+        quote!(::std::option::Option::Some(::mecp::core::protocol::ToolAnnotations {
+            #(#annotations,)*
+            ..::std::default::Default::default()
+        }))
+    };
+
+    let mut properties = Vec::new();
+    let mut call_args = Vec::new();
+    for input in function.sig.inputs.iter_mut() {
+        let FnArg::Typed(argument) = input else {
+            return Err(Error::new(input.span(), "#[tool] functions cannot take self"));
+        };
+
+        if is_request_context(&argument.ty) {
+            call_args.push(quote!(ctx));
+            continue;
+        }
+
+        let Pat::Ident(ref pattern) = *argument.pat else {
+            return Err(Error::new(argument.pat.span(), "#[tool] arguments must be plain identifiers"));
+        };
+        let ident = &pattern.ident;
+        let key = ident.to_string();
+        let key = key.strip_prefix("r#").unwrap_or(&key).to_string();
+        let ty = &argument.ty;
+        let description = take_arg_description(&mut argument.attrs)?;
+        let description = match description {
+            Some(text) => quote!(::std::option::Option::Some(#text)),
+            None => quote!(::std::option::Option::None),
+        };
+
+        properties.push(quote! {
+            properties.insert(
+                #key.to_string(),
+                ::mecp::tools::input::describe(<#ty as ::mecp::tools::InputSchema>::schema(), #description),
+            );
+            if <#ty as ::mecp::tools::InputSchema>::required() {
+                required.push(::mecp::__private::serde_json::Value::from(#key));
+            }
+        });
+        call_args.push(quote!(::mecp::tools::input::argument::<#ty>(&params, #key)?));
+    }
+
+    let function_name = &function.sig.ident;
+    let visibility = &function.vis;
+    let struct_name = format_ident!("{}Tool", camel_case(&function_name.to_string()));
+    let struct_doc = format!("Tool generated from [`{}`]", function_name);
+
+    Ok(quote! {
+        #function
+
+        #[doc = #struct_doc]
+        #[derive(Debug, Clone, Copy, Default)]
+        #visibility struct #struct_name;
+
+        #[::mecp::__private::async_trait]
+        impl ::mecp::tools::Tool for #struct_name {
+            async fn metadata(&self) -> ::mecp::__private::anyhow::Result<::mecp::tools::ToolMetadata> {
+                let mut properties = ::mecp::__private::serde_json::Map::new();
+                let mut required: ::std::vec::Vec<::mecp::__private::serde_json::Value> = ::std::vec::Vec::new();
+                #(#properties)*
+
+                ::std::result::Result::Ok(::mecp::tools::ToolMetadata {
+                    name: #name.to_string(),
+                    description: #description.to_string(),
+                    input_schema: ::std::option::Option::Some(::mecp::__private::serde_json::json!({
+                        "type": "object",
+                        "properties": properties,
+                        "required": required,
+                    })),
+                    cache_ttl: #cache_ttl,
+                    annotations: #annotations,
+                    ..::std::default::Default::default()
+                })
+            }
+
+            async fn execute(
+                &self,
+                params: ::mecp::core::types::JsonValue,
+            ) -> ::mecp::__private::anyhow::Result<::mecp::core::types::ToolResult> {
+                self.execute_with_context(params, &::mecp::core::context::RequestContext::default()).await
+            }
+
+            async fn execute_with_context(
+                &self,
+                params: ::mecp::core::types::JsonValue,
+                ctx: &::mecp::core::context::RequestContext,
+            ) -> ::mecp::__private::anyhow::Result<::mecp::core::types::ToolResult> {
+                let _ = ctx;
+                let output = #function_name(#(#call_args),*).await?;
+                ::std::result::Result::Ok(::mecp::tools::IntoToolResult::into_tool_result(output))
+            }
+        }
+    })
+}
+
+fn expand_input_schema(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let description = match doc_comment(&input.attrs) {
+        Some(text) => quote!(::std::option::Option::Some(#text)),
+        None => quote!(::std::option::Option::None),
+    };
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let Fields::Named(fields) = &data.fields else {
+                return Err(Error::new(input.span(), "InputSchema requires named fields"));
+            };
+
+            let container = SerdeOptions::parse(&input.attrs)?;
+            let mut properties = Vec::new();
+            for field in &fields.named {
+                let serde = SerdeOptions::parse(&field.attrs)?;
+                // Skipped fields are never read from the arguments
+                if serde.skip_deserializing {
+                    continue;
+                }
+                let ty = &field.ty;
+                let is_required = if serde.default || container.default {
+                    quote!(false)
+                } else {
+                    quote!(<#ty as ::mecp::tools::InputSchema>::required())
+                };
+
+                // A flattened struct's properties sit next to this struct's own
+                if serde.flatten {
+                    if container.deny_unknown_fields {
+                        return Err(Error::new(
+                            field.span(),
+                            "InputSchema does not support flatten with deny_unknown_fields",
+                        ));
+                    }
+                    properties.push(quote! {
+                        let flattened = <#ty as ::mecp::tools::InputSchema>::schema();
+                        if let ::std::option::Option::Some(inner) =
+                            flattened.get("properties").and_then(|p| p.as_object())
+                        {
+                            properties.extend(inner.clone());
+                        }
+                        if #is_required {
+                            if let ::std::option::Option::Some(inner) =
+                                flattened.get("required").and_then(|r| r.as_array())
+                            {
+                                required.extend(inner.iter().cloned());
+                            }
+                        }
+                    });
+                    continue;
+                }
+
+                let key = serde.rename.unwrap_or_else(|| {
+                    let ident = field.ident.as_ref().map(ToString::to_string).unwrap_or_default();
+                    rename_field(ident.strip_prefix("r#").unwrap_or(&ident), container.rename_all.as_deref())
+                });
+                let field_description = match doc_comment(&field.attrs) {
+                    Some(text) => quote!(::std::option::Option::Some(#text)),
+                    None => quote!(::std::option::Option::None),
+                };
+
+                properties.push(quote! {
+                    properties.insert(
+                        #key.to_string(),
+                        ::mecp::tools::input::describe(
+                            <#ty as ::mecp::tools::InputSchema>::schema(),
+                            #field_description,
+                        ),
+                    );
+                    if #is_required {
+                        required.push(::mecp::__private::serde_json::Value::from(#key));
+                    }
+                });
+            }
+
+            let closed = container.deny_unknown_fields;
+            quote! {
+                let mut properties = ::mecp::__private::serde_json::Map::new();
+                let mut required: ::std::vec::Vec<::mecp::__private::serde_json::Value> = ::std::vec::Vec::new();
+                #(#properties)*
+
+                let mut schema = ::mecp::__private::serde_json::json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                });
+                if #closed {
+                    schema["additionalProperties"] = ::mecp::__private::serde_json::Value::Bool(false);
+                }
+                schema
+            }
+        }
+        Data::Enum(data) => {
+            let container = SerdeOptions::parse(&input.attrs)?;
+            let mut variants = Vec::new();
+            for variant in &data.variants {
+                if !matches!(variant.fields, Fields::Unit) {
+                    return Err(Error::new(variant.span(), "InputSchema only supports enums with unit variants"));
+                }
+                let serde = SerdeOptions::parse(&variant.attrs)?;
+                if serde.skip_deserializing {
+                    continue;
+                }
+                variants.push(
+                    serde
+                        .rename
+                        .unwrap_or_else(|| rename_variant(&variant.ident.to_string(), container.rename_all.as_deref())),
+                );
+            }
+            quote! {
+                ::mecp::__private::serde_json::json!({
+                    "type": "string",
+                    "enum": [#(#variants),*],
+                })
+            }
+        }
+        Data::Union(_) => return Err(Error::new(input.span(), "InputSchema cannot be derived for unions")),
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::mecp::tools::InputSchema for #name #type_generics #where_clause {
+            fn schema() -> ::mecp::__private::serde_json::Value {
+                let schema = { #body };
+                ::mecp::tools::input::describe(schema, #description)
+            }
+        }
+    })
+}
+
+/// The serde attributes that change a type's schema
+#[derive(Default)]
+struct SerdeOptions {
+    rename: Option<String>,
+    rename_all: Option<String>,
+    default: bool,
+    deny_unknown_fields: bool,
+    /// `skip` or `skip_deserializing`: the field is never read
+    skip_deserializing: bool,
+    flatten: bool,
+}
+
+impl SerdeOptions {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    options.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("rename_all") {
+                    let rule = meta.value()?.parse::<LitStr>()?;
+                    if !RENAME_RULES.contains(&rule.value().as_str()) {
+                        return Err(Error::new(rule.span(), "unsupported rename_all rule"));
+                    }
+                    options.rename_all = Some(rule.value());
+                } else if meta.path.is_ident("default") {
+                    options.default = true;
+                    // `default = "path"` names a function; the value doesn't matter here
+                    if meta.input.peek(Token![=]) {
+                        meta.value()?.parse::<LitStr>()?;
+                    }
+                } else if meta.path.is_ident("deny_unknown_fields") {
+                    options.deny_unknown_fields = true;
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                    options.skip_deserializing = true;
+                } else if meta.path.is_ident("flatten") {
+                    options.flatten = true;
+                } else if meta.input.peek(Token![=]) {
+                    meta.value()?.parse::<Expr>()?;
+                } else if meta.input.peek(syn::token::Paren) {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    content.parse::<TokenStream2>()?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(options)
+    }
+}
+
+const RENAME_RULES: &[&str] = &[
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
+
+/// Apply a serde `rename_all` rule to a field name, which is in snake_case
+///
+/// Follows serde: `lowercase` leaves the name alone and `UPPERCASE` keeps
+/// its underscores.
+fn rename_field(field: &str, rule: Option<&str>) -> String {
+    match rule {
+        None | Some("lowercase") | Some("snake_case") => field.to_string(),
+        Some("UPPERCASE") | Some("SCREAMING_SNAKE_CASE") => field.to_ascii_uppercase(),
+        Some("PascalCase") => {
+            let mut pascal = String::new();
+            let mut capitalize = true;
+            for c in field.chars() {
+                if c == '_' {
+                    capitalize = true;
+                } else if capitalize {
+                    pascal.push(c.to_ascii_uppercase());
+                    capitalize = false;
+                } else {
+                    pascal.push(c);
+                }
+            }
+            pascal
+        }
+        Some("camelCase") => lower_first(&rename_field(field, Some("PascalCase"))),
+        Some("kebab-case") => field.replace('_', "-"),
+        Some(_) => field.to_ascii_uppercase().replace('_', "-"),
+    }
+}
+
+/// Apply a serde `rename_all` rule to a variant name, which is in PascalCase
+fn rename_variant(variant: &str, rule: Option<&str>) -> String {
+    let snake = || {
+        let mut snake = String::new();
+        for (i, c) in variant.char_indices() {
+            if i > 0 && c.is_uppercase() {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        }
+        snake
+    };
+
+    match rule {
+        None | Some("PascalCase") => variant.to_string(),
+        Some("lowercase") => variant.to_ascii_lowercase(),
+        Some("UPPERCASE") => variant.to_ascii_uppercase(),
+        Some("camelCase") => lower_first(variant),
+        Some("snake_case") => snake(),
+        Some("SCREAMING_SNAKE_CASE") => snake().to_ascii_uppercase(),
+        Some("kebab-case") => snake().replace('_', "-"),
+        Some(_) => snake().to_ascii_uppercase().replace('_', "-"),
+    }
+}
+
+fn lower_first(name: &str) -> String {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// Remove an argument's `#[arg(description = "...")]`, returning the description
+fn take_arg_description(attrs: &mut Vec<Attribute>) -> syn::Result<Option<String>> {
+    let mut description = None;
+    let mut error = None;
+    attrs.retain(|attr| {
+        if !attr.path().is_ident("arg") {
+            return true;
+        }
+        let result = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("description") {
+                description = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unknown #[arg] option"))
+            }
+        });
+        if let Err(e) = result {
+            error = Some(e);
+        }
+        false
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(description),
+    }
+}
+
+/// Join an item's doc comment lines
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(pair) => string_value(&pair.value).ok(),
+            _ => None,
+        })
+        .map(|line| line.trim().to_string())
+        .collect();
+
+    let text = lines.join(" ").trim().to_string();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+fn string_value(expr: &Expr) -> syn::Result<String> {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Str(text), .. }) => Ok(text.value()),
+        _ => Err(Error::new(expr.span(), "expected a string literal")),
+    }
+}
+
+/// `#[tool]` options setting the `ToolAnnotations` hint of the same name
+const HINTS: &[&str] = &["read_only_hint", "destructive_hint", "idempotent_hint", "open_world_hint"];
+
+/// What a malformed `#[tool]` option is told to be instead
+const TOOL_OPTIONS: &str = "expected `name = \"...\"`, `description = \"...\"`, `title = \"...\"`, \
+    `cache_ttl_secs = ...`, or a hint (`read_only_hint`, `destructive_hint`, `idempotent_hint`, \
+    `open_world_hint`) optionally set `= true` or `= false`";
+
+fn bool_value(expr: &Expr) -> syn::Result<bool> {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Bool(value), .. }) => Ok(value.value),
+        _ => Err(Error::new(expr.span(), "expected `true` or `false`")),
+    }
+}
+
+fn int_value(expr: &Expr) -> syn::Result<u64> {
+    match expr {
+        Expr::Lit(ExprLit { lit: Lit::Int(number), .. }) => number.base10_parse(),
+        _ => Err(Error::new(expr.span(), "expected an integer literal")),
+    }
+}
+
+fn is_request_context(ty: &Type) -> bool {
+    let Type::Reference(reference) = ty else {
+        return false;
+    };
+    match reference.elem.as_ref() {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "RequestContext"),
+        _ => false,
+    }
+}
+
+fn camel_case(name: &str) -> String {
+    name.trim_start_matches("r#")
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_fields() {
+        assert_eq!(rename_field("min_score", None), "min_score");
+        assert_eq!(rename_field("min_score", Some("lowercase")), "min_score");
+        assert_eq!(rename_field("min_score", Some("UPPERCASE")), "MIN_SCORE");
+        assert_eq!(rename_field("min_score", Some("camelCase")), "minScore");
+        assert_eq!(rename_field("min_score", Some("PascalCase")), "MinScore");
+        assert_eq!(rename_field("min_score", Some("kebab-case")), "min-score");
+        assert_eq!(rename_field("min_score", Some("SCREAMING_SNAKE_CASE")), "MIN_SCORE");
+        assert_eq!(rename_field("min_score", Some("SCREAMING-KEBAB-CASE")), "MIN-SCORE");
+    }
+
+    #[test]
+    fn test_rename_variants() {
+        assert_eq!(rename_variant("HttpGet", None), "HttpGet");
+        assert_eq!(rename_variant("HttpGet", Some("lowercase")), "httpget");
+        assert_eq!(rename_variant("HttpGet", Some("UPPERCASE")), "HTTPGET");
+        assert_eq!(rename_variant("HttpGet", Some("snake_case")), "http_get");
+        assert_eq!(rename_variant("HttpGet", Some("camelCase")), "httpGet");
+        assert_eq!(rename_variant("HttpGet", Some("SCREAMING-KEBAB-CASE")), "HTTP-GET");
+    }
+
+    #[test]
+    fn test_camel_case_struct_names() {
+        assert_eq!(camel_case("catalogue_search"), "CatalogueSearch");
+        assert_eq!(camel_case("r#type"), "Type");
+    }
+}
@@ -0,0 +1,353 @@
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::error::McpError;
+use super::metrics::MetricsCollector;
+use super::middleware::{Call, CallResult, Middleware};
+use super::protocol::methods;
+use super::schema;
+use crate::core::types::{JsonValue, ToolResult};
+
+/// Default number of results a cache holds
+pub const DEFAULT_CACHE_CAPACITY: usize = 1000;
+
+/// `_meta` flag a client sets on `tools/call` to skip cached results
+pub const NO_CACHE_META: &str = "mecp/noCache";
+
+/// A cached call: the tool name, the user it was made for and its
+/// canonicalized arguments
+type CacheKey = (String, Option<String>, String);
+
+struct CacheEntry {
+    result: ToolResult,
+    expires_at: Instant,
+    /// Position in the recency order
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+    /// Keys by last use, least recent first
+    recency: BTreeMap<u64, CacheKey>,
+    clock: u64,
+}
+
+impl CacheState {
+    fn touch(&mut self, key: &CacheKey) {
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = self.clock;
+            self.recency.insert(self.clock, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.last_used);
+        }
+    }
+}
+
+/// Size-bounded LRU cache of tool results
+///
+/// Only tools with a TTL are cached: the `cache_ttl` they declare in their
+/// metadata, or one set here with `with_ttl`. Entries are keyed by tool name,
+/// the calling user and arguments, so one user's results are never served
+/// to another, while calls whose arguments differ only in key order or
+/// omitted defaults share an entry.
+///
+/// The cache is a middleware: it answers calls it has a result for and
+/// stores the results of the ones that reach the tool.
+pub struct ToolCache {
+    capacity: usize,
+    ttls: HashMap<String, Duration>,
+    state: Mutex<CacheState>,
+    metrics: Option<Arc<MetricsCollector>>,
+}
+
+impl ToolCache {
+    /// Create a cache holding at most `capacity` results
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            ttls: HashMap::new(),
+            state: Mutex::new(CacheState::default()),
+            metrics: None,
+        }
+    }
+
+    /// Cache a tool's results for `ttl`, overriding the TTL it declares
+    ///
+    /// A zero TTL turns caching off for the tool.
+    pub fn with_ttl(mut self, tool: &str, ttl: Duration) -> Self {
+        self.ttls.insert(tool.to_string(), ttl);
+        self
+    }
+
+    /// Count hits and misses in `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<MetricsCollector>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Get how long a tool's results are cached, if at all
+    pub fn ttl(&self, tool: &str, declared: Option<Duration>) -> Option<Duration> {
+        self.ttls.get(tool).copied().or(declared).filter(|ttl| !ttl.is_zero())
+    }
+
+    /// Look up the cached result of a call made for `user`
+    pub fn get(&self, tool: &str, user: Option<&str>, arguments: &JsonValue) -> Option<ToolResult> {
+        let key = cache_key(tool, user, arguments);
+        let mut state = self.state.lock().unwrap();
+
+        let fresh = match state.entries.get(&key) {
+            Some(entry) if entry.expires_at > Instant::now() => true,
+            Some(_) => {
+                state.remove(&key);
+                false
+            }
+            None => false,
+        };
+        let result = fresh.then(|| {
+            state.touch(&key);
+            state.entries[&key].result.clone()
+        });
+        drop(state);
+
+        if let Some(metrics) = &self.metrics {
+            metrics.record_cache_lookup(result.is_some());
+        }
+        result
+    }
+
+    /// Cache the result of a call made for `user` for `ttl`, evicting the
+    /// least recently used result if the cache is full
+    pub fn insert(&self, tool: &str, user: Option<&str>, arguments: &JsonValue, result: ToolResult, ttl: Duration) {
+        let key = cache_key(tool, user, arguments);
+        let mut state = self.state.lock().unwrap();
+
+        state.remove(&key);
+        while state.entries.len() >= self.capacity {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+
+        state.entries.insert(
+            key.clone(),
+            CacheEntry {
+                result,
+                expires_at: Instant::now() + ttl,
+                last_used: 0,
+            },
+        );
+        state.touch(&key);
+    }
+
+    /// Check if a fresh result of a call made for `user` is cached, without
+    /// counting a lookup
+    fn contains(&self, tool: &str, user: Option<&str>, arguments: &JsonValue) -> bool {
+        let key = cache_key(tool, user, arguments);
+        let state = self.state.lock().unwrap();
+        state.entries.get(&key).is_some_and(|entry| entry.expires_at > Instant::now())
+    }
+
+    /// Get how long a call's result is cached and the arguments it is cached
+    /// under, with defaults applied, if it is cacheable
+    fn cacheable(&self, call: &Call) -> Option<(Duration, JsonValue)> {
+        let tool = call.tool.as_ref().filter(|_| call.method == methods::CALL_TOOL)?;
+        let ttl = self.ttl(&call.target, tool.cache_ttl)?;
+        let mut arguments = call.arguments.clone();
+        schema::apply_defaults(&tool.schema(), &mut arguments);
+        Some((ttl, arguments))
+    }
+
+    /// Drop the cached results of one call, for every user
+    pub fn invalidate(&self, tool: &str, arguments: &JsonValue) {
+        let arguments = canonicalize(arguments).to_string();
+        let mut state = self.state.lock().unwrap();
+        let keys: Vec<CacheKey> = state
+            .entries
+            .keys()
+            .filter(|(name, _, args)| name == tool && *args == arguments)
+            .cloned()
+            .collect();
+        for key in keys {
+            state.remove(&key);
+        }
+    }
+
+    /// Drop every cached result of a tool
+    pub fn invalidate_tool(&self, tool: &str) {
+        let mut state = self.state.lock().unwrap();
+        let keys: Vec<CacheKey> = state.entries.keys().filter(|(name, _, _)| name == tool).cloned().collect();
+        for key in keys {
+            state.remove(&key);
+        }
+    }
+
+    /// Drop every cached result
+    pub fn clear(&self) {
+        *self.state.lock().unwrap() = CacheState::default();
+    }
+
+    /// Get the number of cached results, including expired ones not yet dropped
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Check if a call asks for cached results to be skipped
+fn is_refresh(call: &Call) -> bool {
+    call.meta(NO_CACHE_META).and_then(|flag| flag.as_bool()).unwrap_or(false)
+}
+
+#[async_trait]
+impl Middleware for ToolCache {
+    async fn before(&self, call: &mut Call) -> Result<Option<CallResult>, McpError> {
+        if is_refresh(call) {
+            return Ok(None);
+        }
+        let Some((_, arguments)) = self.cacheable(call) else {
+            return Ok(None);
+        };
+        Ok(self.get(&call.target, call.caller.user.as_deref(), &arguments).map(CallResult::Tool))
+    }
+
+    async fn after(&self, call: &Call, result: Result<CallResult, McpError>) -> Result<CallResult, McpError> {
+        let Ok(CallResult::Tool(ref tool_result)) = result else {
+            return result;
+        };
+        let Some((ttl, arguments)) = self.cacheable(call).filter(|_| tool_result.success) else {
+            return result;
+        };
+
+        // A fresh entry means the call was answered from the cache, which
+        // must not extend the result's life
+        let user = call.caller.user.as_deref();
+        if is_refresh(call) || !self.contains(&call.target, user, &arguments) {
+            self.insert(&call.target, user, &arguments, tool_result.clone(), ttl);
+        }
+        result
+    }
+}
+
+fn cache_key(tool: &str, user: Option<&str>, arguments: &JsonValue) -> CacheKey {
+    (tool.to_string(), user.map(str::to_string), canonicalize(arguments).to_string())
+}
+
+/// Sort object keys recursively, so equal arguments serialize the same
+fn canonicalize(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(map) => {
+            let sorted: BTreeMap<&String, JsonValue> =
+                map.iter().map(|(key, value)| (key, canonicalize(value))).collect();
+            JsonValue::Object(sorted.into_iter().map(|(key, value)| (key.clone(), value)).collect())
+        }
+        JsonValue::Array(items) => JsonValue::Array(items.iter().map(canonicalize).collect()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result(output: &str) -> ToolResult {
+        ToolResult {
+            success: true,
+            output: json!(output),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_key_ignores_argument_order() {
+        let cache = ToolCache::new(10);
+        cache.insert("search", None, &json!({"query": "rust", "max_results": 5}), result("hit"), Duration::from_secs(60));
+
+        let hit = cache.get("search", None, &json!({"max_results": 5, "query": "rust"}));
+        assert_eq!(hit.unwrap().output, json!("hit"));
+        assert!(cache.get("search", None, &json!({"query": "rust"})).is_none());
+        assert!(cache.get("fetch", None, &json!({"max_results": 5, "query": "rust"})).is_none());
+    }
+
+    #[test]
+    fn test_results_are_kept_per_user() {
+        let cache = ToolCache::new(10);
+        let ttl = Duration::from_secs(60);
+        cache.insert("search", Some("alice"), &json!({"query": "notes"}), result("alice's"), ttl);
+
+        assert_eq!(cache.get("search", Some("alice"), &json!({"query": "notes"})).unwrap().output, json!("alice's"));
+        assert!(cache.get("search", Some("bob"), &json!({"query": "notes"})).is_none());
+        assert!(cache.get("search", None, &json!({"query": "notes"})).is_none());
+
+        cache.insert("search", Some("bob"), &json!({"query": "notes"}), result("bob's"), ttl);
+        cache.invalidate("search", &json!({"query": "notes"}));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = ToolCache::new(2);
+        let ttl = Duration::from_secs(60);
+        cache.insert("search", None, &json!({"query": "a"}), result("a"), ttl);
+        cache.insert("search", None, &json!({"query": "b"}), result("b"), ttl);
+
+        // Using "a" makes "b" the least recently used
+        assert!(cache.get("search", None, &json!({"query": "a"})).is_some());
+        cache.insert("search", None, &json!({"query": "c"}), result("c"), ttl);
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("search", None, &json!({"query": "a"})).is_some());
+        assert!(cache.get("search", None, &json!({"query": "b"})).is_none());
+        assert!(cache.get("search", None, &json!({"query": "c"})).is_some());
+    }
+
+    #[test]
+    fn test_expired_entries_miss() {
+        let cache = ToolCache::new(10);
+        cache.insert("search", None, &json!({}), result("stale"), Duration::ZERO);
+
+        assert!(cache.get("search", None, &json!({})).is_none());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let cache = ToolCache::new(10);
+        let ttl = Duration::from_secs(60);
+        cache.insert("search", None, &json!({"query": "a"}), result("a"), ttl);
+        cache.insert("search", None, &json!({"query": "b"}), result("b"), ttl);
+        cache.insert("fetch", None, &json!({"url": "a"}), result("a"), ttl);
+
+        cache.invalidate("search", &json!({"query": "a"}));
+        assert!(cache.get("search", None, &json!({"query": "a"})).is_none());
+        assert_eq!(cache.len(), 2);
+
+        cache.invalidate_tool("search");
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_ttl_override() {
+        let cache = ToolCache::new(10).with_ttl("fetch", Duration::from_secs(5));
+
+        assert_eq!(cache.ttl("fetch", Some(Duration::from_secs(60))), Some(Duration::from_secs(5)));
+        assert_eq!(cache.ttl("search", Some(Duration::from_secs(60))), Some(Duration::from_secs(60)));
+        assert_eq!(cache.ttl("echo", None), None);
+    }
+}
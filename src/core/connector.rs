@@ -75,6 +75,11 @@ pub trait McpConnector: Send + Sync {
     
     /// Read a resource
     async fn read_resource(&self, username: &str, uri: &str) -> Result<ResourceContent>;
+
+    /// Release the connector's runtimes when the server shuts down
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
 }
 
 /// Cursor MCP Connector implementation
@@ -548,6 +553,14 @@ impl McpConnector for CursorMcpConnector {
         
         bail!("Resource '{}' not found for user '{}'", uri, username)
     }
+
+    async fn shutdown(&self) -> Result<()> {
+        self.user_sessions.write().await.clear();
+        if let Some(runtime) = &self.wassette_runtime {
+            runtime.shutdown().await?;
+        }
+        Ok(())
+    }
 }
 
 /// Connector registry - holds registered connectors
@@ -582,6 +595,15 @@ impl ConnectorRegistry {
     pub fn list_connector_ids(&self) -> Vec<String> {
         self.connectors.keys().cloned().collect()
     }
+
+    /// Shut down every registered connector
+    pub async fn shutdown_all(&self) {
+        for (id, connector) in &self.connectors {
+            if let Err(e) = connector.shutdown().await {
+                error!("Failed to shut down connector {}: {}", id, e);
+            }
+        }
+    }
 }
 
 impl Default for ConnectorRegistry {
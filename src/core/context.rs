@@ -0,0 +1,290 @@
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use super::error::McpError;
+use super::logging::ClientLogger;
+use super::protocol::{
+    file_uri_path, methods, CreateMessageParams, CreateMessageResult, JsonRpcNotification, JsonRpcRequest,
+    ListRootsResult, Root,
+};
+use super::session::Session;
+use crate::core::types::JsonValue;
+
+/// How many times `roots/list` is asked before giving up on roots that keep changing
+const MAX_ROOTS_ATTEMPTS: usize = 3;
+
+/// Per-request state handed to tools while they execute
+///
+/// Carries the caller's session, its `progressToken` and a cancellation
+/// token that fires when the client sends `notifications/cancelled`. Tools
+/// use it to log to the client, to send it requests such as sampling, and to
+/// stay within the roots it has exposed.
+/// Notifications sent through the context reach the client over the stream
+/// the request arrived on, or the session's stream otherwise.
+#[derive(Clone, Default)]
+pub struct RequestContext {
+    request_id: Option<JsonValue>,
+    progress_token: Option<JsonValue>,
+    /// The request's `_meta` object
+    meta: Option<JsonValue>,
+    session: Option<Arc<Session>>,
+    /// Request-scoped stream, e.g. the SSE response of a Streamable HTTP POST
+    stream: Option<mpsc::UnboundedSender<JsonValue>>,
+    cancellation: CancellationToken,
+}
+
+impl RequestContext {
+    pub fn new(
+        request_id: Option<JsonValue>,
+        params: Option<&JsonValue>,
+        session: Option<Arc<Session>>,
+        stream: Option<mpsc::UnboundedSender<JsonValue>>,
+        cancellation: CancellationToken,
+    ) -> Self {
+        let meta = params.and_then(|p| p.get("_meta")).cloned();
+        let progress_token = meta
+            .as_ref()
+            .and_then(|meta| meta.get("progressToken"))
+            .cloned()
+            .filter(|token| !token.is_null());
+
+        Self {
+            request_id,
+            progress_token,
+            meta,
+            session,
+            stream,
+            cancellation,
+        }
+    }
+
+    /// Get the ID of the request being handled
+    pub fn request_id(&self) -> Option<&JsonValue> {
+        self.request_id.as_ref()
+    }
+
+    /// Get the progress token the client asked progress to be reported against
+    pub fn progress_token(&self) -> Option<&JsonValue> {
+        self.progress_token.as_ref()
+    }
+
+    /// Get a field of the request's `_meta` object
+    pub fn meta(&self, key: &str) -> Option<&JsonValue> {
+        self.meta.as_ref().and_then(|meta| meta.get(key))
+    }
+
+    /// Get the request's `_meta` object
+    pub(crate) fn meta_object(&self) -> Option<&JsonValue> {
+        self.meta.as_ref()
+    }
+
+    /// Get the session the request belongs to
+    pub fn session(&self) -> Option<&Arc<Session>> {
+        self.session.as_ref()
+    }
+
+    /// Check if the client has cancelled the request
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Wait until the client cancels the request
+    pub async fn cancelled(&self) {
+        self.cancellation.cancelled().await
+    }
+
+    /// Report progress on the request
+    ///
+    /// Does nothing unless the client supplied a `progressToken`. Returns
+    /// false if the notification could not be delivered.
+    pub async fn report_progress(&self, progress: f64, total: Option<f64>, message: Option<&str>) -> bool {
+        let Some(token) = self.progress_token.clone() else {
+            return false;
+        };
+
+        let mut params = json!({
+            "progressToken": token,
+            "progress": progress,
+        });
+        if let Some(total) = total {
+            params["total"] = json!(total);
+        }
+        if let Some(message) = message {
+            params["message"] = json!(message);
+        }
+
+        self.notify(JsonRpcNotification::new(methods::PROGRESS, Some(params))).await
+    }
+
+    /// Get a logger that sends messages to the client, under `name`
+    pub fn logger(&self, name: &str) -> ClientLogger {
+        ClientLogger::new(self.clone(), Some(name))
+    }
+
+    /// Ask the client's model for a message with `sampling/createMessage`
+    ///
+    /// Fails if the client did not announce the `sampling` capability.
+    pub async fn create_message(&self, params: CreateMessageParams) -> Result<CreateMessageResult, McpError> {
+        let supported = match &self.session {
+            Some(session) => session.client_capabilities().await.sampling.is_some(),
+            None => false,
+        };
+        if !supported {
+            return Err(McpError::tool_execution("The client does not support sampling"));
+        }
+
+        let result = self
+            .request(methods::CREATE_MESSAGE, Some(serde_json::to_value(params).unwrap()))
+            .await?;
+        serde_json::from_value(result).map_err(|e| {
+            McpError::tool_execution(format!("Invalid {} result from the client: {}", methods::CREATE_MESSAGE, e))
+        })
+    }
+
+    /// Get the roots the client has exposed, asking it with `roots/list`
+    ///
+    /// The roots are kept with the session until the client sends
+    /// `notifications/roots/list_changed`. Fails if the client did not
+    /// announce the `roots` capability.
+    pub async fn roots(&self) -> Result<Vec<Root>, McpError> {
+        let Some(session) = &self.session else {
+            return Err(McpError::tool_execution("The client does not expose roots"));
+        };
+        if session.client_capabilities().await.roots.is_none() {
+            return Err(McpError::tool_execution("The client does not expose roots"));
+        }
+
+        // A reply that crossed a change announcement is stale, so ask again
+        for _ in 0..MAX_ROOTS_ATTEMPTS {
+            if let Some(roots) = session.roots().await {
+                return Ok(roots);
+            }
+
+            let generation = session.roots_generation();
+            let result = self.request(methods::LIST_ROOTS, None).await?;
+            let roots = serde_json::from_value::<ListRootsResult>(result)
+                .map_err(|e| {
+                    McpError::tool_execution(format!("Invalid {} result from the client: {}", methods::LIST_ROOTS, e))
+                })?
+                .roots;
+            if session.set_roots(roots.clone(), generation).await {
+                return Ok(roots);
+            }
+        }
+        Err(McpError::tool_execution("The client's roots kept changing while they were listed"))
+    }
+
+    /// Check that a URI lies within one of the roots the client has exposed
+    ///
+    /// `file://` URIs are checked with `resolve_within_roots`, following
+    /// symlinks; other URIs are compared lexically.
+    pub async fn ensure_within_roots(&self, uri: &str) -> Result<(), McpError> {
+        if uri.starts_with("file://") {
+            return self.resolve_within_roots(uri).await.map(|_| ());
+        }
+        if self.roots().await?.iter().any(|root| root.contains(uri)) {
+            Ok(())
+        } else {
+            Err(outside_roots(uri))
+        }
+    }
+
+    /// Resolve a `file://` URI to a path within one of the roots the client
+    /// has exposed
+    ///
+    /// The path and the roots are canonicalized, so a symlink inside a root
+    /// can't lead out of it. Open the returned path rather than the URI's,
+    /// so the file checked is the file used.
+    pub async fn resolve_within_roots(&self, uri: &str) -> Result<PathBuf, McpError> {
+        let path = file_uri_path(uri)
+            .ok_or_else(|| McpError::tool_execution(format!("{} is not a local file:// URI", uri)))?;
+        let roots = self.roots().await?;
+        // Only look at the file system for paths the roots could contain
+        if !roots.iter().any(|root| root.contains(uri)) {
+            return Err(outside_roots(uri));
+        }
+
+        let path = tokio::fs::canonicalize(&path)
+            .await
+            .map_err(|e| McpError::tool_execution(format!("Cannot resolve {}: {}", uri, e)))?;
+        for root in roots.iter().filter_map(Root::path) {
+            if let Ok(root) = tokio::fs::canonicalize(&root).await {
+                if path.starts_with(&root) {
+                    return Ok(path);
+                }
+            }
+        }
+        Err(outside_roots(uri))
+    }
+
+    /// Send a request to the client and wait for its result
+    ///
+    /// The request goes out like a notification, and the client's response
+    /// is matched to it by ID. Fails if the client has no stream, answers
+    /// with an error, or goes away first, and stops waiting if this request
+    /// is cancelled.
+    pub async fn request(&self, method: &str, params: Option<JsonValue>) -> Result<JsonValue, McpError> {
+        let Some(session) = &self.session else {
+            return Err(McpError::tool_execution(format!("Cannot send {} without a session", method)));
+        };
+
+        let (id, response) = session.expect_response().await;
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            id: Some(id.clone()),
+            method: method.to_string(),
+            params,
+        };
+        if !self.send(serde_json::to_value(request).unwrap()).await {
+            session.forget_response(&id).await;
+            return Err(McpError::tool_execution(format!("The client has no stream to receive {}", method)));
+        }
+
+        let response = tokio::select! {
+            response = response => response
+                .map_err(|_| McpError::tool_execution(format!("The session closed before the client answered {}", method)))?,
+            _ = self.cancelled() => {
+                session.forget_response(&id).await;
+                return Err(McpError::tool_execution(format!("Cancelled while waiting for {}", method)));
+            }
+        };
+
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(McpError::tool_execution(format!(
+                "The client failed {}: {}",
+                method, error.message
+            ))
+            .with_data(json!({ "code": error.code }))),
+            (Some(result), None) => Ok(result),
+            (None, None) => Ok(JsonValue::Null),
+        }
+    }
+
+    /// Send a notification to the client
+    ///
+    /// Returns false if the client has no stream to deliver it on.
+    pub async fn notify(&self, notification: JsonRpcNotification) -> bool {
+        self.send(serde_json::to_value(notification).unwrap()).await
+    }
+
+    /// Send a message over the request's stream, or the session's otherwise
+    async fn send(&self, message: JsonValue) -> bool {
+        if let Some(stream) = &self.stream {
+            if stream.send(message.clone()).is_ok() {
+                return true;
+            }
+        }
+
+        match &self.session {
+            Some(session) => session.send(message).await,
+            None => false,
+        }
+    }
+}
+
+fn outside_roots(uri: &str) -> McpError {
+    McpError::tool_execution(format!("{} is outside the roots the client has exposed", uri))
+}
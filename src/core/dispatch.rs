@@ -0,0 +1,719 @@
+use chrono::Utc;
+use futures::future::join_all;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, error};
+
+use super::context::RequestContext;
+use super::error::McpError;
+use super::pagination::Page;
+use super::metrics::{MetricsCollector, ApiCallLog};
+use super::protocol::*;
+use super::server::McpServer;
+use super::session::{ClientDetails, Session};
+use crate::core::types::JsonValue;
+
+/// Transport-independent JSON-RPC method dispatch
+///
+/// Shared by the HTTP, SSE and stdio transports so every client sees the
+/// same methods and every call is recorded in the metrics.
+#[derive(Clone)]
+pub struct McpDispatcher {
+    mcp_server: Arc<McpServer>,
+    metrics: Arc<MetricsCollector>,
+}
+
+impl McpDispatcher {
+    pub fn new(mcp_server: Arc<McpServer>, metrics: Arc<MetricsCollector>) -> Self {
+        Self {
+            mcp_server,
+            metrics,
+        }
+    }
+
+    /// Handle a raw JSON-RPC message: a single request, a notification, a
+    /// response to a server request or a batch
+    ///
+    /// Batch entries are processed concurrently. Returns `None` when nothing
+    /// should be sent back, i.e. the message only contained notifications and
+    /// responses or the client cancelled its requests.
+    pub async fn handle_message(
+        &self,
+        message: JsonValue,
+        session: Option<Arc<Session>>,
+        endpoint: &str,
+    ) -> Option<JsonValue> {
+        self.dispatch(message, session, None, endpoint).await
+    }
+
+    /// Handle a message whose response is delivered over its own stream
+    ///
+    /// Notifications emitted while handling the message, such as progress,
+    /// are sent over `stream` ahead of the response.
+    pub async fn handle_streamed_message(
+        &self,
+        message: JsonValue,
+        session: Option<Arc<Session>>,
+        stream: mpsc::UnboundedSender<JsonValue>,
+        endpoint: &str,
+    ) -> Option<JsonValue> {
+        self.dispatch(message, session, Some(stream), endpoint).await
+    }
+
+    async fn dispatch(
+        &self,
+        message: JsonValue,
+        session: Option<Arc<Session>>,
+        stream: Option<mpsc::UnboundedSender<JsonValue>>,
+        endpoint: &str,
+    ) -> Option<JsonValue> {
+        match message {
+            JsonValue::Array(entries) if entries.is_empty() => Some(invalid_request(None, "empty batch")),
+            JsonValue::Array(entries) => {
+                let responses: Vec<JsonValue> = join_all(
+                    entries
+                        .into_iter()
+                        .map(|entry| self.handle_single(entry, session.clone(), stream.clone(), endpoint)),
+                )
+                .await
+                .into_iter()
+                .flatten()
+                .collect();
+
+                if responses.is_empty() {
+                    None
+                } else {
+                    Some(JsonValue::Array(responses))
+                }
+            }
+            message => self.handle_single(message, session, stream, endpoint).await,
+        }
+    }
+
+    /// Handle a single (non-batch) JSON-RPC message
+    async fn handle_single(
+        &self,
+        message: JsonValue,
+        session: Option<Arc<Session>>,
+        stream: Option<mpsc::UnboundedSender<JsonValue>>,
+        endpoint: &str,
+    ) -> Option<JsonValue> {
+        let id = message.get("id").cloned().filter(|id| !id.is_null());
+
+        // Answers to requests the server sent the client, such as sampling
+        let is_response = message.get("method").is_none()
+            && (message.get("result").is_some() || message.get("error").is_some());
+        if is_response {
+            self.handle_response(message, session.as_ref()).await;
+            return None;
+        }
+
+        let request: JsonRpcRequest = match serde_json::from_value(message) {
+            Ok(request) => request,
+            Err(e) => return Some(invalid_request(id, &e.to_string())),
+        };
+        if request.jsonrpc != "2.0" {
+            return Some(invalid_request(id, "jsonrpc must be \"2.0\""));
+        }
+
+        let Some(id) = request.id.clone() else {
+            self.handle_notification(request, session.as_ref(), endpoint).await;
+            return None;
+        };
+
+        // Only requests within a session can be cancelled by the client
+        let cancellation = match &session {
+            Some(session) => match session.begin_request(&id).await {
+                Some(token) => token,
+                None => {
+                    return Some(invalid_request(
+                        Some(id.clone()),
+                        &format!("request {} is already in progress", id),
+                    ))
+                }
+            },
+            None => CancellationToken::new(),
+        };
+        let ctx = RequestContext::new(
+            Some(id.clone()),
+            request.params.as_ref(),
+            session.clone(),
+            stream,
+            cancellation.clone(),
+        );
+
+        // Dropping the handler future aborts whatever it was awaiting
+        let response = tokio::select! {
+            response = self.handle_request(request, &ctx, endpoint) => Some(response),
+            _ = cancellation.cancelled() => {
+                info!("Request {} cancelled by client", id);
+                None
+            }
+        };
+
+        if let Some(session) = &session {
+            session.end_request(&id, &cancellation).await;
+        }
+
+        response.map(|response| serde_json::to_value(response).unwrap())
+    }
+
+    /// Dispatch a JSON-RPC request and record it in the metrics
+    ///
+    /// `endpoint` identifies the transport the request arrived on in the logs.
+    pub async fn handle_request(
+        &self,
+        request: JsonRpcRequest,
+        ctx: &RequestContext,
+        endpoint: &str,
+    ) -> JsonRpcResponse {
+        let start_time = Instant::now();
+        info!("Received MCP request: method={}", request.method);
+
+        let response = match request.method.as_str() {
+            methods::INITIALIZE => handle_initialize(&self.mcp_server, &request, ctx.session()).await,
+            methods::PING => JsonRpcResponse::success(request.id.clone(), json!({})),
+            methods::LIST_RESOURCES => handle_list_resources(&self.mcp_server, &request).await,
+            methods::READ_RESOURCE => handle_read_resource(&self.mcp_server, &request, ctx).await,
+            methods::LIST_RESOURCE_TEMPLATES => handle_list_resource_templates(&self.mcp_server, &request).await,
+            methods::SUBSCRIBE => handle_subscribe(&self.mcp_server, &request, ctx.session()).await,
+            methods::UNSUBSCRIBE => handle_unsubscribe(&self.mcp_server, &request, ctx.session()).await,
+            methods::LIST_TOOLS => handle_list_tools(&self.mcp_server, &request).await,
+            methods::CALL_TOOL => handle_call_tool(&self.mcp_server, &request, ctx).await,
+            methods::LIST_PROMPTS => handle_list_prompts(&self.mcp_server, &request).await,
+            methods::GET_PROMPT => handle_get_prompt(&self.mcp_server, &request, ctx).await,
+            methods::COMPLETE => handle_complete(&self.mcp_server, &request).await,
+            methods::SET_LOG_LEVEL => handle_set_log_level(&request, ctx.session()).await,
+            _ => McpError::MethodNotFound(request.method.clone()).to_response(request.id.clone()),
+        };
+
+        self.record_call(&request, Some(&response), start_time, endpoint);
+        response
+    }
+
+    /// Handle a JSON-RPC notification - notifications never get a response
+    async fn handle_notification(
+        &self,
+        notification: JsonRpcRequest,
+        session: Option<&Arc<Session>>,
+        endpoint: &str,
+    ) {
+        let start_time = Instant::now();
+        info!("Received MCP notification: method={}", notification.method);
+
+        match notification.method.as_str() {
+            methods::INITIALIZED => {
+                if let Some(session) = session {
+                    session.mark_initialized();
+                    info!("Session {} initialized", session.id());
+                }
+            }
+            methods::CANCELLED => {
+                let request_id = notification
+                    .params
+                    .as_ref()
+                    .and_then(|p| p.get("requestId"))
+                    .cloned()
+                    .unwrap_or(JsonValue::Null);
+                let cancelled = match session {
+                    Some(session) => session.cancel_request(&request_id).await,
+                    None => false,
+                };
+                if cancelled {
+                    info!("Client cancelled request {}", request_id);
+                } else {
+                    debug!("Cancellation for unknown or finished request {}", request_id);
+                }
+            }
+            methods::ROOTS_LIST_CHANGED => {
+                if let Some(session) = session {
+                    session.invalidate_roots().await;
+                    info!("Session {} changed its roots", session.id());
+                }
+            }
+            _ => debug!("Ignoring unknown notification: {}", notification.method),
+        }
+
+        self.record_call(&notification, None, start_time, endpoint);
+    }
+
+    /// Hand a client's response to the server request awaiting it
+    async fn handle_response(&self, message: JsonValue, session: Option<&Arc<Session>>) {
+        let response: JsonRpcResponse = match serde_json::from_value(message) {
+            Ok(response) => response,
+            Err(e) => {
+                debug!("Ignoring malformed response: {}", e);
+                return;
+            }
+        };
+        let id = response.id.clone().unwrap_or(JsonValue::Null);
+
+        let resolved = match session {
+            Some(session) => session.resolve_response(response).await,
+            None => false,
+        };
+        if !resolved {
+            debug!("Response to unknown or abandoned request {}", id);
+        }
+    }
+
+    /// Record a handled message in the metrics without blocking the caller
+    fn record_call(
+        &self,
+        request: &JsonRpcRequest,
+        response: Option<&JsonRpcResponse>,
+        start_time: Instant,
+        endpoint: &str,
+    ) {
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let error = response.and_then(|r| r.error.as_ref());
+        let (status, error_msg) = match error {
+            Some(e) => ("error".to_string(), Some(e.message.clone())),
+            None => ("success".to_string(), None),
+        };
+
+        let log = ApiCallLog {
+            id: None,
+            method: request.method.clone(),
+            endpoint: endpoint.to_string(),
+            request_params: serde_json::to_string(&request.params).ok(),
+            // Capture response data (serialize the response)
+            response_data: response.and_then(|r| serde_json::to_string(r).ok()),
+            response_status: status,
+            error_message: error_msg,
+            duration_ms,
+            timestamp: Utc::now(),
+            client_info: None,
+        };
+
+        // Don't block on metrics recording
+        self.metrics.record_call_detached(log);
+    }
+}
+
+/// Build the error response for a failed request, logging internal errors
+fn error_response(request: &JsonRpcRequest, action: &str, error: McpError) -> JsonRpcResponse {
+    if let McpError::Internal { .. } = error {
+        error!("Failed to {}: {}", action, error);
+    }
+    error.to_response(request.id.clone())
+}
+
+/// Build an "Invalid Request" error response
+fn invalid_request(id: Option<JsonValue>, reason: &str) -> JsonValue {
+    serde_json::to_value(McpError::invalid_request(reason).to_response(id)).unwrap()
+}
+
+async fn handle_initialize(
+    server: &Arc<McpServer>,
+    request: &JsonRpcRequest,
+    session: Option<&Arc<Session>>,
+) -> JsonRpcResponse {
+    let params: InitializeParams = match serde_json::from_value(
+        request.params.clone().unwrap_or(json!({})),
+    ) {
+        Ok(params) => params,
+        Err(e) => return McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    };
+
+    let protocol_version = negotiate_protocol_version(&params.protocol_version);
+    info!(
+        "Client {} {} requested protocol {}, using {}",
+        params.client_info.name, params.client_info.version, params.protocol_version, protocol_version
+    );
+
+    if let Some(session) = session {
+        session.set_client(ClientDetails {
+            protocol_version: protocol_version.to_string(),
+            capabilities: params.capabilities,
+            info: params.client_info,
+        }).await;
+    }
+
+    let result = InitializeResult {
+        protocol_version: protocol_version.to_string(),
+        capabilities: server.capabilities().await,
+        server_info: ServerInfo {
+            name: "MeCP".to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+    };
+
+    JsonRpcResponse::success(
+        request.id.clone(),
+        serde_json::to_value(result).unwrap(),
+    )
+}
+
+/// Parse the params of a paginated list request, which may be omitted
+fn parse_list_params(request: &JsonRpcRequest) -> Result<PaginatedParams, serde_json::Error> {
+    match &request.params {
+        None => Ok(PaginatedParams::default()),
+        Some(params) => serde_json::from_value(params.clone()),
+    }
+}
+
+async fn handle_list_resources(
+    server: &Arc<McpServer>,
+    request: &JsonRpcRequest,
+) -> JsonRpcResponse {
+    let params = match parse_list_params(request) {
+        Ok(params) => params,
+        Err(e) => return McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    };
+
+    match server.list_resources_page(params.cursor.as_deref()).await {
+        Ok(Page { items: resources, next_cursor }) => {
+            let resource_infos: Vec<ResourceInfo> = resources
+                .into_iter()
+                .map(|r| ResourceInfo {
+                    uri: r.uri.clone(),
+                    name: r.name.clone(),
+                    description: Some(r.description.clone()),
+                    mime_type: r.mime_type.clone(),
+                })
+                .collect();
+
+            let result = ResourceListResult {
+                resources: resource_infos,
+                next_cursor,
+            };
+
+            JsonRpcResponse::success(
+                request.id.clone(),
+                serde_json::to_value(result).unwrap(),
+            )
+        }
+        Err(e) => error_response(request, "list resources", e),
+    }
+}
+
+async fn handle_list_resource_templates(
+    server: &Arc<McpServer>,
+    request: &JsonRpcRequest,
+) -> JsonRpcResponse {
+    let params = match parse_list_params(request) {
+        Ok(params) => params,
+        Err(e) => return McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    };
+
+    match server.list_resource_templates_page(params.cursor.as_deref()).await {
+        Ok(Page { items: templates, next_cursor }) => {
+            let template_infos: Vec<ResourceTemplateInfo> = templates
+                .into_iter()
+                .map(|t| ResourceTemplateInfo {
+                    uri_template: t.uri_template,
+                    name: t.name,
+                    description: Some(t.description),
+                    mime_type: t.mime_type,
+                })
+                .collect();
+
+            let result = ResourceTemplateListResult {
+                resource_templates: template_infos,
+                next_cursor,
+            };
+
+            JsonRpcResponse::success(
+                request.id.clone(),
+                serde_json::to_value(result).unwrap(),
+            )
+        }
+        Err(e) => error_response(request, "list resource templates", e),
+    }
+}
+
+async fn handle_read_resource(
+    server: &Arc<McpServer>,
+    request: &JsonRpcRequest,
+    ctx: &RequestContext,
+) -> JsonRpcResponse {
+    let params: Result<ReadResourceParams, _> = serde_json::from_value(
+        request.params.clone().unwrap_or(json!({})),
+    );
+
+    match params {
+        Ok(params) => match server.read_resource_contents_with_context(&params.uri, ctx).await {
+            Ok(contents) => {
+                let result = ReadResourceResult { contents };
+
+                JsonRpcResponse::success(
+                    request.id.clone(),
+                    serde_json::to_value(result).unwrap(),
+                )
+            }
+            Err(e) => error_response(request, "read resource", e),
+        },
+        Err(e) => McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    }
+}
+
+async fn handle_subscribe(
+    server: &Arc<McpServer>,
+    request: &JsonRpcRequest,
+    session: Option<&Arc<Session>>,
+) -> JsonRpcResponse {
+    let Some(session) = session else {
+        return McpError::invalid_request("subscriptions require a session").to_response(request.id.clone());
+    };
+    let params: SubscribeParams = match serde_json::from_value(
+        request.params.clone().unwrap_or(json!({})),
+    ) {
+        Ok(params) => params,
+        Err(e) => return McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    };
+
+    match server.subscribe_resource(session, &params.uri).await {
+        Ok(()) => {
+            info!("Session {} subscribed to {}", session.id(), params.uri);
+            JsonRpcResponse::success(request.id.clone(), json!({}))
+        }
+        Err(e) => error_response(request, "subscribe", e),
+    }
+}
+
+async fn handle_unsubscribe(
+    server: &Arc<McpServer>,
+    request: &JsonRpcRequest,
+    session: Option<&Arc<Session>>,
+) -> JsonRpcResponse {
+    let Some(session) = session else {
+        return McpError::invalid_request("subscriptions require a session").to_response(request.id.clone());
+    };
+    let params: SubscribeParams = match serde_json::from_value(
+        request.params.clone().unwrap_or(json!({})),
+    ) {
+        Ok(params) => params,
+        Err(e) => return McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    };
+
+    if server.unsubscribe_resource(session, &params.uri).await {
+        info!("Session {} unsubscribed from {}", session.id(), params.uri);
+    }
+    JsonRpcResponse::success(request.id.clone(), json!({}))
+}
+
+async fn handle_list_tools(
+    server: &Arc<McpServer>,
+    request: &JsonRpcRequest,
+) -> JsonRpcResponse {
+    let params = match parse_list_params(request) {
+        Ok(params) => params,
+        Err(e) => return McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    };
+
+    match server.list_tools_page(params.cursor.as_deref()).await {
+        Ok(Page { items: tools, next_cursor }) => {
+            let tool_infos: Vec<ToolInfo> = tools
+                .into_iter()
+                .map(|t| ToolInfo {
+                    name: t.name.clone(),
+                    description: t.description.clone(),
+                    input_schema: t.schema(),
+                    output_schema: t.output_schema.clone(),
+                    annotations: t.annotations.clone(),
+                })
+                .collect();
+
+            let result = ToolListResult {
+                tools: tool_infos,
+                next_cursor,
+            };
+
+            JsonRpcResponse::success(
+                request.id.clone(),
+                serde_json::to_value(result).unwrap(),
+            )
+        }
+        Err(e) => error_response(request, "list tools", e),
+    }
+}
+
+async fn handle_call_tool(
+    server: &Arc<McpServer>,
+    request: &JsonRpcRequest,
+    ctx: &RequestContext,
+) -> JsonRpcResponse {
+    let params: Result<CallToolParams, _> = serde_json::from_value(
+        request.params.clone().unwrap_or(json!({})),
+    );
+
+    match params {
+        Ok(params) => {
+            let args = params.arguments.unwrap_or(json!({}));
+            match server.call_tool_with_context(&params.name, args, ctx).await {
+                Ok(result) => {
+                    let content = if !result.content.is_empty() {
+                        result.content
+                    } else if let (false, Some(error)) = (result.success, &result.error) {
+                        // A failed call tells the client what went wrong
+                        vec![ToolContent::text(error.clone())]
+                    } else if let Some(ref structured) = result.structured_content {
+                        // Clients that don't read structuredContent get it serialized as text
+                        vec![ToolContent::text(structured.to_string())]
+                    } else {
+                        vec![ToolContent::text(result.output.to_string())]
+                    };
+
+                    let call_result = CallToolResult {
+                        content,
+                        structured_content: result.structured_content,
+                        is_error: Some(!result.success),
+                    };
+
+                    JsonRpcResponse::success(
+                        request.id.clone(),
+                        serde_json::to_value(call_result).unwrap(),
+                    )
+                }
+                Err(McpError::ToolExecution { message, .. }) => {
+                    // The tool failed, not the request: let the model see why
+                    let call_result = CallToolResult {
+                        content: vec![ToolContent::text(message)],
+                        structured_content: None,
+                        is_error: Some(true),
+                    };
+
+                    JsonRpcResponse::success(
+                        request.id.clone(),
+                        serde_json::to_value(call_result).unwrap(),
+                    )
+                }
+                Err(e) => error_response(request, "call tool", e),
+            }
+        }
+        Err(e) => McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    }
+}
+
+async fn handle_list_prompts(
+    server: &Arc<McpServer>,
+    request: &JsonRpcRequest,
+) -> JsonRpcResponse {
+    let params = match parse_list_params(request) {
+        Ok(params) => params,
+        Err(e) => return McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    };
+
+    match server.list_prompts_page(params.cursor.as_deref()).await {
+        Ok(Page { items: prompts, next_cursor }) => {
+            let prompt_infos: Vec<PromptInfo> = prompts
+                .into_iter()
+                .map(|p| PromptInfo {
+                    name: p.name.clone(),
+                    description: Some(p.description.clone()),
+                    arguments: Some(
+                        p.arguments
+                            .into_iter()
+                            .map(|a| PromptArgument {
+                                name: a.name.clone(),
+                                description: Some(a.description.clone()),
+                                required: Some(a.required),
+                            })
+                            .collect(),
+                    ),
+                })
+                .collect();
+
+            let result = PromptListResult {
+                prompts: prompt_infos,
+                next_cursor,
+            };
+
+            JsonRpcResponse::success(
+                request.id.clone(),
+                serde_json::to_value(result).unwrap(),
+            )
+        }
+        Err(e) => error_response(request, "list prompts", e),
+    }
+}
+
+async fn handle_get_prompt(
+    server: &Arc<McpServer>,
+    request: &JsonRpcRequest,
+    ctx: &RequestContext,
+) -> JsonRpcResponse {
+    let params: Result<GetPromptParams, _> = serde_json::from_value(
+        request.params.clone().unwrap_or(json!({})),
+    );
+
+    match params {
+        Ok(params) => {
+            let args = params.arguments.unwrap_or(json!({}));
+            match server.get_prompt_with_context(&params.name, args, ctx).await {
+                Ok(result) => {
+                    let messages: Vec<PromptMessage> = result
+                        .messages
+                        .into_iter()
+                        .map(|m| PromptMessage {
+                            role: m.role.clone(),
+                            content: PromptContent {
+                                content_type: "text".to_string(),
+                                text: m.content.clone(),
+                            },
+                        })
+                        .collect();
+
+                    let prompt_result = GetPromptResult {
+                        messages,
+                        description: None,
+                    };
+
+                    JsonRpcResponse::success(
+                        request.id.clone(),
+                        serde_json::to_value(prompt_result).unwrap(),
+                    )
+                }
+                Err(e) => error_response(request, "get prompt", e),
+            }
+        }
+        Err(e) => McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    }
+}
+
+async fn handle_set_log_level(
+    request: &JsonRpcRequest,
+    session: Option<&Arc<Session>>,
+) -> JsonRpcResponse {
+    let Some(session) = session else {
+        return McpError::invalid_request("logging requires a session").to_response(request.id.clone());
+    };
+    let params: SetLevelParams = match serde_json::from_value(
+        request.params.clone().unwrap_or(json!({})),
+    ) {
+        Ok(params) => params,
+        Err(e) => return McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    };
+
+    session.set_log_level(params.level).await;
+    info!("Session {} set log level to {:?}", session.id(), params.level);
+    JsonRpcResponse::success(request.id.clone(), json!({}))
+}
+
+async fn handle_complete(
+    server: &Arc<McpServer>,
+    request: &JsonRpcRequest,
+) -> JsonRpcResponse {
+    let params: CompleteParams = match serde_json::from_value(
+        request.params.clone().unwrap_or(json!({})),
+    ) {
+        Ok(params) => params,
+        Err(e) => return McpError::invalid_params(e.to_string()).to_response(request.id.clone()),
+    };
+
+    let context = params.context.unwrap_or_default().arguments;
+    match server
+        .complete(&params.reference, &params.argument.name, &params.argument.value, &context)
+        .await
+    {
+        Ok(completion) => JsonRpcResponse::success(
+            request.id.clone(),
+            serde_json::to_value(CompleteResult { completion }).unwrap(),
+        ),
+        Err(e) => error_response(request, "complete", e),
+    }
+}
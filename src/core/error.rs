@@ -0,0 +1,212 @@
+use serde_json::json;
+use thiserror::Error;
+
+use crate::core::pagination::InvalidCursor;
+use crate::core::protocol::JsonRpcResponse;
+use crate::core::types::JsonValue;
+use crate::tools::InvalidArguments;
+
+/// JSON-RPC error codes used by MCP
+pub mod codes {
+    pub const PARSE_ERROR: i32 = -32700;
+    pub const INVALID_REQUEST: i32 = -32600;
+    pub const METHOD_NOT_FOUND: i32 = -32601;
+    pub const INVALID_PARAMS: i32 = -32602;
+    pub const INTERNAL_ERROR: i32 = -32603;
+    pub const RESOURCE_NOT_FOUND: i32 = -32002;
+    /// The `Mcp-Session-Id` a request names is unknown or has been closed
+    pub const SESSION_NOT_FOUND: i32 = -32001;
+}
+
+/// An error answering an MCP request
+///
+/// Tools, resources and prompts return `anyhow` errors; returning one of
+/// these inside it chooses how the failure is reported to the client. Any
+/// other error from a tool is a `ToolExecution` error, and from anything
+/// else an `Internal` one.
+#[derive(Debug, Error)]
+pub enum McpError {
+    #[error("Invalid Request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Method not found: {0}")]
+    MethodNotFound(String),
+
+    #[error("Invalid params: {message}")]
+    InvalidParams { message: String, data: Option<JsonValue> },
+
+    #[error("Resource not found: {uri}")]
+    ResourceNotFound { uri: String },
+
+    /// The tool ran and failed; reported as a result with `isError` set
+    #[error("{message}")]
+    ToolExecution { message: String, data: Option<JsonValue> },
+
+    #[error("Internal error: {message}")]
+    Internal { message: String, data: Option<JsonValue> },
+}
+
+impl McpError {
+    pub fn invalid_request(reason: impl Into<String>) -> Self {
+        Self::InvalidRequest(reason.into())
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self::InvalidParams {
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn resource_not_found(uri: impl Into<String>) -> Self {
+        Self::ResourceNotFound { uri: uri.into() }
+    }
+
+    pub fn tool_execution(message: impl Into<String>) -> Self {
+        Self::ToolExecution {
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::Internal {
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Attach details for the client, replacing any already attached
+    ///
+    /// Ignored by variants that build their own details.
+    pub fn with_data(mut self, details: JsonValue) -> Self {
+        match &mut self {
+            Self::InvalidParams { data, .. } | Self::ToolExecution { data, .. } | Self::Internal { data, .. } => {
+                *data = Some(details);
+            }
+            Self::InvalidRequest(_) | Self::MethodNotFound(_) | Self::ResourceNotFound { .. } => {}
+        }
+        self
+    }
+
+    /// Classify an error returned by a tool
+    ///
+    /// Errors that aren't already an `McpError` become `ToolExecution` errors.
+    pub fn from_tool_error(error: anyhow::Error) -> Self {
+        match error.downcast::<McpError>() {
+            Ok(error) => error,
+            Err(error) => Self::tool_execution(format!("{:#}", error)),
+        }
+    }
+
+    /// Get the JSON-RPC error code
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::InvalidRequest(_) => codes::INVALID_REQUEST,
+            Self::MethodNotFound(_) => codes::METHOD_NOT_FOUND,
+            Self::InvalidParams { .. } => codes::INVALID_PARAMS,
+            Self::ResourceNotFound { .. } => codes::RESOURCE_NOT_FOUND,
+            Self::ToolExecution { .. } | Self::Internal { .. } => codes::INTERNAL_ERROR,
+        }
+    }
+
+    /// Get the details sent in the error's `data` field
+    pub fn data(&self) -> Option<JsonValue> {
+        match self {
+            Self::InvalidRequest(_) | Self::MethodNotFound(_) => None,
+            Self::ResourceNotFound { uri } => Some(json!({ "uri": uri })),
+            Self::InvalidParams { data, .. } | Self::ToolExecution { data, .. } | Self::Internal { data, .. } => {
+                data.clone()
+            }
+        }
+    }
+
+    /// Build the JSON-RPC error response
+    pub fn to_response(&self, id: Option<JsonValue>) -> JsonRpcResponse {
+        match self.data() {
+            Some(data) => JsonRpcResponse::error_with_data(id, self.code(), self.to_string(), data),
+            None => JsonRpcResponse::error(id, self.code(), self.to_string()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for McpError {
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<McpError>() {
+            Ok(error) => return error,
+            Err(error) => error,
+        };
+        let error = match error.downcast::<InvalidCursor>() {
+            Ok(cursor) => return cursor.into(),
+            Err(error) => error,
+        };
+        match error.downcast::<InvalidArguments>() {
+            Ok(invalid) => invalid.into(),
+            Err(error) => Self::internal(format!("{:#}", error)),
+        }
+    }
+}
+
+impl From<InvalidCursor> for McpError {
+    fn from(error: InvalidCursor) -> Self {
+        Self::invalid_params(error.to_string())
+    }
+}
+
+impl From<InvalidArguments> for McpError {
+    fn from(error: InvalidArguments) -> Self {
+        let path = error.error.path.clone();
+        Self::invalid_params(error.to_string()).with_data(json!({ "path": path }))
+    }
+}
+
+impl From<serde_json::Error> for McpError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::invalid_params(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes() {
+        assert_eq!(McpError::invalid_request("x").code(), -32600);
+        assert_eq!(McpError::MethodNotFound("x".into()).code(), -32601);
+        assert_eq!(McpError::invalid_params("x").code(), -32602);
+        assert_eq!(McpError::resource_not_found("mock://x").code(), -32002);
+        assert_eq!(McpError::internal("x").code(), -32603);
+    }
+
+    #[test]
+    fn test_resource_not_found_response_carries_uri() {
+        let response = McpError::resource_not_found("mock://missing").to_response(Some(json!(1)));
+        let error = response.error.unwrap();
+
+        assert_eq!(error.code, -32002);
+        assert_eq!(error.message, "Resource not found: mock://missing");
+        assert_eq!(error.data, Some(json!({"uri": "mock://missing"})));
+    }
+
+    #[test]
+    fn test_from_anyhow_keeps_mcp_errors() {
+        let error: McpError = anyhow::Error::from(McpError::resource_not_found("mock://x")).into();
+        assert!(matches!(error, McpError::ResourceNotFound { .. }));
+
+        let error: McpError = anyhow::Error::from(InvalidCursor("bogus".into())).into();
+        assert!(matches!(error, McpError::InvalidParams { .. }));
+
+        let error: McpError = anyhow::anyhow!("disk on fire").into();
+        assert!(matches!(error, McpError::Internal { ref message, .. } if message == "disk on fire"));
+    }
+
+    #[test]
+    fn test_tool_errors_become_execution_errors() {
+        let error = McpError::from_tool_error(anyhow::anyhow!("upstream timed out"));
+        assert!(matches!(error, McpError::ToolExecution { .. }));
+
+        let error = McpError::from_tool_error(McpError::invalid_params("bad").into());
+        assert!(matches!(error, McpError::InvalidParams { .. }));
+    }
+}
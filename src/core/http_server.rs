@@ -1,29 +1,42 @@
 use axum::{
-    extract::{State, Request},
-    http::{StatusCode, header, HeaderMap},
-    response::{IntoResponse, Response, sse::Event, Sse},
+    extract::{Query, State, Request},
+    http::{StatusCode, header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response, sse::{Event, KeepAlive}, Sse},
     routing::{post, get},
     Json, Router,
     middleware::{self, Next},
 };
-use futures::stream::{self, Stream};
+use futures::stream::{self, Stream, StreamExt};
 use std::convert::Infallible;
+use serde::Deserialize;
 use serde_json::json;
 use std::sync::Arc;
 use tower_http::cors::CorsLayer;
-use tracing::{info, error};
+use std::future::Future;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, error, warn};
 use chrono::Utc;
-use std::time::Instant;
+use tokio::sync::mpsc;
 
+use super::dispatch::McpDispatcher;
+use super::error::codes;
 use super::protocol::*;
 use super::server::McpServer;
-use super::metrics::{MetricsCollector, ApiCallLog};
+use super::shutdown::{shutdown_signal, DEFAULT_SHUTDOWN_GRACE};
+use super::session::{Session, SessionManager, DEFAULT_SESSION_TTL};
+use super::metrics::MetricsCollector;
 use super::auth::{AuthService, ChallengeRequest, VerifyRequest};
 use crate::core::types::JsonValue;
 
+/// Header carrying the session ID for the Streamable HTTP transport
+const MCP_SESSION_ID: &str = "mcp-session-id";
+const MCP_PROTOCOL_VERSION: &str = "mcp-protocol-version";
+
 /// Shared application state
 #[derive(Clone)]
 pub struct AppState {
+    dispatcher: McpDispatcher,
     mcp_server: Arc<McpServer>,
     metrics: Arc<MetricsCollector>,
     auth: Option<Arc<AuthService>>,
@@ -36,6 +49,10 @@ pub struct HttpServer {
     auth: Option<Arc<AuthService>>,
     host: String,
     port: u16,
+    /// How long in-flight work gets to finish on shutdown
+    shutdown_grace: Duration,
+    /// How long a session may go without requests before it is closed
+    session_ttl: Duration,
 }
 
 impl HttpServer {
@@ -45,7 +62,9 @@ impl HttpServer {
             metrics: Arc::new(MetricsCollector::new()),
             auth: None,
             host,
-            port 
+            port,
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
+            session_ttl: DEFAULT_SESSION_TTL,
         }
     }
 
@@ -55,7 +74,9 @@ impl HttpServer {
             metrics,
             auth: None,
             host,
-            port 
+            port,
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
+            session_ttl: DEFAULT_SESSION_TTL,
         }
     }
 
@@ -64,8 +85,38 @@ impl HttpServer {
         self
     }
 
+    pub fn with_shutdown_grace(mut self, grace: Duration) -> Self {
+        self.shutdown_grace = grace;
+        self
+    }
+
+    /// Close sessions that go `ttl` without requests and have no stream open
+    pub fn with_session_ttl(mut self, ttl: Duration) -> Self {
+        self.session_ttl = ttl;
+        self
+    }
+
+    /// Serve until SIGINT or SIGTERM, then shut down gracefully
     pub async fn start(self) -> anyhow::Result<()> {
+        self.start_with_shutdown(shutdown_signal()).await
+    }
+
+    /// Serve until `signal` resolves, then shut down gracefully
+    ///
+    /// The listener stops accepting connections while open ones are served
+    /// until they close. The MCP server gets the grace period to drain its
+    /// tool calls before closing its sessions, which ends their streams, and
+    /// metrics still being written get as long again to be flushed.
+    pub async fn start_with_shutdown<F>(self, signal: F) -> anyhow::Result<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mcp_server = Arc::clone(&self.mcp_server);
+        let metrics = Arc::clone(&self.metrics);
+        let grace = self.shutdown_grace;
+
         let state = AppState {
+            dispatcher: McpDispatcher::new(Arc::clone(&self.mcp_server), Arc::clone(&self.metrics)),
             mcp_server: self.mcp_server,
             metrics: self.metrics,
             auth: self.auth.clone(),
@@ -93,8 +144,9 @@ impl HttpServer {
         };
 
         let app = Router::new()
-            .route("/mcp", post(handle_mcp_request))
+            .route("/mcp", post(handle_mcp_request).get(handle_mcp_stream).delete(handle_mcp_delete))
             .route("/sse", get(handle_sse_stream))
+            .route("/messages", post(handle_legacy_message))
             .route("/health", get(health_check))
             // Dashboard HTML (public - auth checked by JavaScript)
             .route("/dashboard", get(serve_dashboard))
@@ -113,12 +165,52 @@ impl HttpServer {
         info!("Dashboard available at http://{}/dashboard", addr);
         
         let listener = tokio::net::TcpListener::bind(&addr).await?;
-        axum::serve(listener, app).await?;
+        let stopping = CancellationToken::new();
+        // Clients that vanish without a DELETE leave their sessions behind
+        tokio::spawn(reap_idle_sessions(mcp_server.sessions(), self.session_ttl, stopping.clone()));
+        let lifecycle = tokio::spawn({
+            let stopping = stopping.clone();
+            async move {
+                signal.await;
+                stopping.cancel();
+                mcp_server.shutdown(grace).await;
+            }
+        });
+
+        let served = axum::serve(listener, app)
+            .with_graceful_shutdown(stopping.cancelled_owned())
+            .await;
+        if served.is_err() {
+            lifecycle.abort();
+        }
+        let _ = lifecycle.await;
+
+        if !metrics.flush(grace).await {
+            warn!("Gave up on metrics still being written after {:?}", grace);
+        }
+        info!("MCP HTTP Server stopped");
+        served?;
 
         Ok(())
     }
 }
 
+/// Close idle sessions every so often until `stopping` is cancelled
+async fn reap_idle_sessions(sessions: Arc<SessionManager>, ttl: Duration, stopping: CancellationToken) {
+    let mut interval = tokio::time::interval((ttl / 4).max(Duration::from_secs(1)));
+    loop {
+        tokio::select! {
+            _ = stopping.cancelled() => return,
+            _ = interval.tick() => {
+                let closed = sessions.reap_idle(ttl).await;
+                if closed > 0 {
+                    info!("Closed {} idle session(s)", closed);
+                }
+            }
+        }
+    }
+}
+
 async fn health_check() -> impl IntoResponse {
     Json(json!({
         "status": "healthy",
@@ -127,378 +219,364 @@ async fn health_check() -> impl IntoResponse {
     }))
 }
 
-/// SSE endpoint for MCP streaming
-/// This endpoint supports Server-Sent Events for ChatGPT and other MCP clients
+/// Legacy HTTP+SSE transport (protocol 2024-11-05): GET opens the event stream
+///
+/// Each connection gets its own session. The first event is `endpoint`, naming
+/// the `/messages?sessionId=...` URL the client must POST its requests to;
+/// JSON-RPC responses then arrive on this stream as `message` events.
 async fn handle_sse_stream(
-    State(_state): State<AppState>,
-    _headers: HeaderMap,
-) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
-    info!("SSE connection established");
-    
-    // Create a stream that sends initial connection event and periodic heartbeats
-    let stream = stream::unfold(true, |first| async move {
-        if first {
-            // Send initial connection event
-            let event = Event::default()
-                .event("connected")
-                .data(json!({
-                    "status": "connected",
-                    "service": "mecp",
-                    "version": env!("CARGO_PKG_VERSION"),
-                    "protocol": "sse"
-                }).to_string());
-            Some((
-                Ok(event),
-                false
-            ))
-        } else {
-            // Send periodic heartbeat to keep connection alive
-            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
-            let heartbeat = Event::default()
-                .event("heartbeat")
-                .data(json!({"timestamp": Utc::now().to_rfc3339()}).to_string());
-            Some((
-                Ok(heartbeat),
-                false
-            ))
-        }
-    });
-    
-    Sse::new(stream)
-        .keep_alive(axum::response::sse::KeepAlive::default())
-}
-
-async fn handle_mcp_request(
     State(state): State<AppState>,
-    Json(request): Json<JsonRpcRequest>,
+    headers: HeaderMap,
 ) -> Response {
-    let start_time = Instant::now();
-    let method = request.method.clone();
-    let request_params = serde_json::to_string(&request.params).ok();
-    
-    info!("Received MCP request: method={}", method);
-
-    let response = match request.method.as_str() {
-        methods::INITIALIZE => handle_initialize(&request).await,
-        methods::LIST_RESOURCES => handle_list_resources(&state.mcp_server, &request).await,
-        methods::READ_RESOURCE => handle_read_resource(&state.mcp_server, &request).await,
-        methods::LIST_TOOLS => handle_list_tools(&state.mcp_server, &request).await,
-        methods::CALL_TOOL => handle_call_tool(&state.mcp_server, &request).await,
-        methods::LIST_PROMPTS => handle_list_prompts(&state.mcp_server, &request).await,
-        methods::GET_PROMPT => handle_get_prompt(&state.mcp_server, &request).await,
-        _ => JsonRpcResponse::error(
-            request.id.clone(),
-            -32601,
-            format!("Method not found: {}", request.method),
-        ),
+    let user = match authenticate(&state, &headers).await {
+        Ok(user) => user,
+        Err(response) => return response,
     };
 
-    // Record metrics
-    let duration_ms = start_time.elapsed().as_millis() as u64;
-    let (status, error_msg) = if response.error.is_some() {
-        ("error".to_string(), response.error.as_ref().map(|e| e.message.clone()))
-    } else {
-        ("success".to_string(), None)
+    let sessions = state.mcp_server.sessions();
+    let session = sessions.create().await;
+    if let Some(user) = user {
+        session.set_user(&user).await;
+    }
+    let rx = session.attach_stream().await;
+    info!("SSE connection established for session {}", session.id());
+
+    let endpoint = Event::default()
+        .event("endpoint")
+        .data(format!("/messages?sessionId={}", session.id()));
+
+    // The guard ends the session once the client disconnects
+    let guard = SessionGuard {
+        sessions,
+        id: session.id().to_string(),
     };
+    let messages = stream::unfold((rx, guard), |(mut rx, guard)| async move {
+        let message = rx.recv().await?;
+        let event = Event::default().event("message").data(message.to_string());
+        Some((Ok(event), (rx, guard)))
+    });
+
+    Sse::new(stream::once(async move { Ok::<_, Infallible>(endpoint) }).chain(messages))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct MessagesQuery {
+    #[serde(rename = "sessionId")]
+    session_id: String,
+}
 
-    // Capture response data (serialize the response)
-    let response_data = serde_json::to_string(&response).ok();
-
-    let log = ApiCallLog {
-        id: None,
-        method: method.clone(),
-        endpoint: "/mcp".to_string(),
-        request_params,
-        response_data,
-        response_status: status,
-        error_message: error_msg,
-        duration_ms,
-        timestamp: Utc::now(),
-        client_info: None,
+/// Legacy HTTP+SSE transport: POST a JSON-RPC request for an SSE session
+///
+/// The request is accepted with `202 Accepted` and its response is delivered
+/// over the session's event stream.
+async fn handle_legacy_message(
+    State(state): State<AppState>,
+    Query(query): Query<MessagesQuery>,
+    headers: HeaderMap,
+    Json(message): Json<JsonValue>,
+) -> Response {
+    let user = match authenticate(&state, &headers).await {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    let Some(session) = state.mcp_server.sessions().get(&query.session_id).await else {
+        return (StatusCode::NOT_FOUND, "Session not found").into_response();
     };
+    if let Err(response) = claim_session(&session, user.as_deref()).await {
+        return response;
+    }
+    session.touch().await;
 
-    // Don't block on metrics recording
-    let metrics = state.metrics.clone();
     tokio::spawn(async move {
-        if let Err(e) = metrics.record_call(log).await {
-            error!("Failed to record metrics: {}", e);
+        let response = state
+            .dispatcher
+            .handle_message(message, Some(Arc::clone(&session)), "/messages")
+            .await;
+        if let Some(response) = response {
+            if !session.send(response).await {
+                error!("SSE stream for session {} is closed, dropping response", session.id());
+            }
         }
     });
 
-    (StatusCode::OK, Json(response)).into_response()
+    StatusCode::ACCEPTED.into_response()
 }
 
-async fn handle_initialize(request: &JsonRpcRequest) -> JsonRpcResponse {
-    let result = InitializeResult {
-        protocol_version: "2024-11-05".to_string(),
-        capabilities: ServerCapabilities {
-            resources: Some(ResourcesCapability {
-                subscribe: false,
-                list_changed: false,
-            }),
-            tools: Some(ToolsCapability {
-                list_changed: false,
-            }),
-            prompts: Some(PromptsCapability {
-                list_changed: false,
-            }),
-        },
-        server_info: ServerInfo {
-            name: "MeCP".to_string(),
-            version: env!("CARGO_PKG_VERSION").to_string(),
-        },
-    };
+/// Removes a session when the transport connection owning it goes away
+struct SessionGuard {
+    sessions: Arc<SessionManager>,
+    id: String,
+}
 
-    JsonRpcResponse::success(
-        request.id.clone(),
-        serde_json::to_value(result).unwrap(),
-    )
+impl Drop for SessionGuard {
+    fn drop(&mut self) {
+        let sessions = Arc::clone(&self.sessions);
+        let id = std::mem::take(&mut self.id);
+        tokio::spawn(async move {
+            if sessions.remove(&id).await.is_some() {
+                info!("SSE connection closed for session {}", id);
+            }
+        });
+    }
 }
 
-async fn handle_list_resources(
-    server: &Arc<McpServer>,
-    request: &JsonRpcRequest,
-) -> JsonRpcResponse {
-    match server.list_resources().await {
-        Ok(resources) => {
-            let resource_infos: Vec<ResourceInfo> = resources
-                .into_iter()
-                .map(|r| ResourceInfo {
-                    uri: r.uri.clone(),
-                    name: r.name.clone(),
-                    description: Some(r.description.clone()),
-                    mime_type: r.mime_type.clone(),
-                })
-                .collect();
-
-            let result = ResourceListResult {
-                resources: resource_infos,
-            };
-
-            JsonRpcResponse::success(
-                request.id.clone(),
-                serde_json::to_value(result).unwrap(),
-            )
+/// Streamable HTTP transport: POST a JSON-RPC request, notification, response or batch
+///
+/// The `initialize` handshake creates a session whose ID is returned in the
+/// `Mcp-Session-Id` header. Tool calls from clients that accept
+/// `text/event-stream` are answered with an SSE stream, everything else with
+/// JSON. Messages containing only notifications or responses to server
+/// requests get `202 Accepted`.
+async fn handle_mcp_request(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let user = match authenticate(&state, &headers).await {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    let session = match lookup_session(&state, &headers).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+
+    // Clients repeat the negotiated version on every request after initialize
+    if let Some(version) = headers.get(MCP_PROTOCOL_VERSION).and_then(|h| h.to_str().ok()) {
+        if !SUPPORTED_PROTOCOL_VERSIONS.contains(&version) {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Unsupported MCP-Protocol-Version: {}", version),
+            ).into_response();
+        }
+    }
+
+    let message: JsonValue = match serde_json::from_str(&body) {
+        Ok(message) => message,
+        Err(e) => return (
+            StatusCode::OK,
+            Json(JsonRpcResponse::error(None, codes::PARSE_ERROR, format!("Parse error: {}", e))),
+        ).into_response(),
+    };
+    let method = message.get("method").and_then(|m| m.as_str()).map(|m| m.to_string());
+    let is_request = message.get("id").is_some_and(|id| !id.is_null());
+
+    // A new session is established by the initialize handshake, on its own
+    // or in a batch; it is only registered once the handshake succeeds
+    let initialize = session.is_none().then(|| initialize_id(&message)).flatten();
+    let new_session = initialize.as_ref().map(|_| state.mcp_server.sessions().prepare());
+    let session = session.or_else(|| new_session.clone());
+    if let Some(session) = &session {
+        if let Err(response) = claim_session(session, user.as_deref()).await {
+            return response;
         }
-        Err(e) => {
-            error!("Failed to list resources: {}", e);
-            JsonRpcResponse::error(request.id.clone(), -32603, e.to_string())
+    }
+
+    let streamed = is_request
+        && method.as_deref() == Some(methods::CALL_TOOL)
+        && accepts_event_stream(&headers);
+
+    let (mut response, established) = if streamed {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let response = state
+                .dispatcher
+                .handle_streamed_message(message, session, tx.clone(), "/mcp")
+                .await;
+            if let Some(response) = response {
+                let _ = tx.send(response);
+            }
+        });
+
+        (Sse::new(event_stream(rx)).into_response(), false)
+    } else {
+        let reply = state.dispatcher.handle_message(message, session, "/mcp").await;
+        let established = match (&initialize, &reply) {
+            (Some(id), Some(reply)) => answered(reply, id),
+            _ => false,
+        };
+        let response = match reply {
+            Some(reply) => (StatusCode::OK, Json(reply)).into_response(),
+            None => StatusCode::ACCEPTED.into_response(),
+        };
+        (response, established)
+    };
+
+    if let Some(session) = new_session.filter(|_| established) {
+        info!("Created MCP session {}", session.id());
+        if let Ok(value) = HeaderValue::from_str(session.id()) {
+            response.headers_mut().insert(MCP_SESSION_ID, value);
         }
+        state.mcp_server.sessions().register(session).await;
     }
+
+    response
 }
 
-async fn handle_read_resource(
-    server: &Arc<McpServer>,
-    request: &JsonRpcRequest,
-) -> JsonRpcResponse {
-    let params: Result<ReadResourceParams, _> = serde_json::from_value(
-        request.params.clone().unwrap_or(json!({})),
-    );
-
-    match params {
-        Ok(params) => match server.read_resource(&params.uri).await {
-            Ok(content) => {
-                let resource_content = ResourceContent {
-                    uri: content.uri.clone(),
-                    mime_type: Some("application/json".to_string()),
-                    text: Some(content.content.to_string()),
-                    blob: None,
-                };
-
-                let result = ReadResourceResult {
-                    contents: vec![resource_content],
-                };
-
-                JsonRpcResponse::success(
-                    request.id.clone(),
-                    serde_json::to_value(result).unwrap(),
-                )
-            }
-            Err(e) => {
-                error!("Failed to read resource: {}", e);
-                JsonRpcResponse::error(request.id.clone(), -32603, e.to_string())
-            }
-        },
-        Err(e) => JsonRpcResponse::error(
-            request.id.clone(),
-            -32602,
-            format!("Invalid params: {}", e),
-        ),
+/// Streamable HTTP transport: GET opens the server-to-client stream of a session
+async fn handle_mcp_stream(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    let user = match authenticate(&state, &headers).await {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    let session = match lookup_session(&state, &headers).await {
+        Ok(Some(session)) => session,
+        Ok(None) => return (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response(),
+        Err(response) => return response,
+    };
+    if let Err(response) = claim_session(&session, user.as_deref()).await {
+        return response;
     }
+
+    info!("Opened server-to-client stream for session {}", session.id());
+    let rx = session.attach_stream().await;
+
+    Sse::new(event_stream(rx))
+        .keep_alive(KeepAlive::default())
+        .into_response()
 }
 
-async fn handle_list_tools(
-    server: &Arc<McpServer>,
-    request: &JsonRpcRequest,
-) -> JsonRpcResponse {
-    match server.list_tools().await {
-        Ok(tools) => {
-            let tool_infos: Vec<ToolInfo> = tools
-                .into_iter()
-                .map(|t| ToolInfo {
-                    name: t.name.clone(),
-                    description: t.description.clone(),
-                    input_schema: json!({
-                        "type": "object",
-                        "properties": t.parameters.iter().map(|p| {
-                            (p.name.clone(), json!({
-                                "type": p.param_type,
-                                "description": p.description
-                            }))
-                        }).collect::<serde_json::Map<String, JsonValue>>(),
-                        "required": t.parameters.iter()
-                            .filter(|p| p.required)
-                            .map(|p| p.name.clone())
-                            .collect::<Vec<_>>()
-                    }),
-                })
-                .collect();
-
-            let result = ToolListResult { tools: tool_infos };
-
-            JsonRpcResponse::success(
-                request.id.clone(),
-                serde_json::to_value(result).unwrap(),
-            )
+/// Streamable HTTP transport: DELETE ends a session
+async fn handle_mcp_delete(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    let user = match authenticate(&state, &headers).await {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    let Some(id) = headers.get(MCP_SESSION_ID).and_then(|h| h.to_str().ok()) else {
+        return (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response();
+    };
+    if let Some(session) = state.mcp_server.sessions().get(id).await {
+        if let Err(response) = claim_session(&session, user.as_deref()).await {
+            return response;
         }
-        Err(e) => {
-            error!("Failed to list tools: {}", e);
-            JsonRpcResponse::error(request.id.clone(), -32603, e.to_string())
+    }
+
+    match state.mcp_server.sessions().remove(id).await {
+        Some(_) => {
+            info!("Terminated MCP session {}", id);
+            StatusCode::OK.into_response()
         }
+        None => StatusCode::NOT_FOUND.into_response(),
     }
 }
 
-async fn handle_call_tool(
-    server: &Arc<McpServer>,
-    request: &JsonRpcRequest,
-) -> JsonRpcResponse {
-    let params: Result<CallToolParams, _> = serde_json::from_value(
-        request.params.clone().unwrap_or(json!({})),
-    );
-
-    match params {
-        Ok(params) => {
-            let args = params.arguments.unwrap_or(json!({}));
-            match server.call_tool(&params.name, args).await {
-                Ok(result) => {
-                    let content = ToolContent {
-                        content_type: "text".to_string(),
-                        text: result.output.to_string(),
-                    };
-
-                    let call_result = CallToolResult {
-                        content: vec![content],
-                        is_error: Some(!result.success),
-                    };
-
-                    JsonRpcResponse::success(
-                        request.id.clone(),
-                        serde_json::to_value(call_result).unwrap(),
-                    )
-                }
-                Err(e) => {
-                    error!("Failed to call tool: {}", e);
-                    JsonRpcResponse::error(request.id.clone(), -32603, e.to_string())
-                }
-            }
+/// Resolve the session named by the `Mcp-Session-Id` header
+///
+/// Returns `Ok(None)` when no header was sent, and a 404 response when the
+/// session is unknown or has been terminated.
+async fn lookup_session(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<Option<Arc<Session>>, Response> {
+    let Some(id) = headers.get(MCP_SESSION_ID).and_then(|h| h.to_str().ok()) else {
+        return Ok(None);
+    };
+
+    match state.mcp_server.sessions().get(id).await {
+        Some(session) => {
+            session.touch().await;
+            Ok(Some(session))
         }
-        Err(e) => JsonRpcResponse::error(
-            request.id.clone(),
-            -32602,
-            format!("Invalid params: {}", e),
-        ),
+        None => Err((
+            StatusCode::NOT_FOUND,
+            Json(JsonRpcResponse::error(None, codes::SESSION_NOT_FOUND, format!("Session not found: {}", id))),
+        ).into_response()),
     }
 }
 
-async fn handle_list_prompts(
-    server: &Arc<McpServer>,
-    request: &JsonRpcRequest,
-) -> JsonRpcResponse {
-    match server.list_prompts().await {
-        Ok(prompts) => {
-            let prompt_infos: Vec<PromptInfo> = prompts
-                .into_iter()
-                .map(|p| PromptInfo {
-                    name: p.name.clone(),
-                    description: Some(p.description.clone()),
-                    arguments: Some(
-                        p.arguments
-                            .into_iter()
-                            .map(|a| PromptArgument {
-                                name: a.name.clone(),
-                                description: Some(a.description.clone()),
-                                required: Some(a.required),
-                            })
-                            .collect(),
-                    ),
-                })
-                .collect();
-
-            let result = PromptListResult {
-                prompts: prompt_infos,
-            };
-
-            JsonRpcResponse::success(
-                request.id.clone(),
-                serde_json::to_value(result).unwrap(),
-            )
-        }
-        Err(e) => {
-            error!("Failed to list prompts: {}", e);
-            JsonRpcResponse::error(request.id.clone(), -32603, e.to_string())
-        }
+/// Resolve the API key in the `Authorization: Bearer` header to a user
+///
+/// Returns `Ok(None)` when the server takes no API keys. Once it does,
+/// every request must carry a known key; others get a 401 response.
+async fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<Option<String>, Response> {
+    if !state.mcp_server.has_api_keys().await {
+        return Ok(None);
+    }
+    let Some(value) = headers.get(header::AUTHORIZATION) else {
+        return Err((StatusCode::UNAUTHORIZED, "Missing API key").into_response());
+    };
+
+    let key = value.to_str().ok().and_then(|v| v.strip_prefix("Bearer "));
+    match key {
+        Some(key) => match state.mcp_server.user_for_api_key(key.trim()).await {
+            Some(user) => Ok(Some(user)),
+            None => Err((StatusCode::UNAUTHORIZED, "Unknown API key").into_response()),
+        },
+        None => Err((StatusCode::UNAUTHORIZED, "Expected a Bearer API key").into_response()),
     }
 }
 
-async fn handle_get_prompt(
-    server: &Arc<McpServer>,
-    request: &JsonRpcRequest,
-) -> JsonRpcResponse {
-    let params: Result<GetPromptParams, _> = serde_json::from_value(
-        request.params.clone().unwrap_or(json!({})),
-    );
-
-    match params {
-        Ok(params) => {
-            let args = params.arguments.unwrap_or(json!({}));
-            match server.get_prompt(&params.name, args).await {
-                Ok(result) => {
-                    let messages: Vec<PromptMessage> = result
-                        .messages
-                        .into_iter()
-                        .map(|m| PromptMessage {
-                            role: m.role.clone(),
-                            content: PromptContent {
-                                content_type: "text".to_string(),
-                                text: m.content.clone(),
-                            },
-                        })
-                        .collect();
-
-                    let prompt_result = GetPromptResult {
-                        messages,
-                        description: None,
-                    };
-
-                    JsonRpcResponse::success(
-                        request.id.clone(),
-                        serde_json::to_value(prompt_result).unwrap(),
-                    )
-                }
-                Err(e) => {
-                    error!("Failed to get prompt: {}", e);
-                    JsonRpcResponse::error(request.id.clone(), -32603, e.to_string())
-                }
-            }
-        }
-        Err(e) => JsonRpcResponse::error(
-            request.id.clone(),
-            -32602,
-            format!("Invalid params: {}", e),
-        ),
+/// Run a session's requests on behalf of the user that authenticated them
+///
+/// A session belongs to the first user that authenticates in it; requests
+/// with another user's key, or with none, are refused with 403.
+async fn claim_session(session: &Session, user: Option<&str>) -> Result<(), Response> {
+    let allowed = match user {
+        Some(user) => session.claim_user(user).await,
+        None => session.user().await.is_none(),
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::FORBIDDEN,
+            format!("Session {} belongs to another user", session.id()),
+        ).into_response())
+    }
+}
+
+/// Get the id of the `initialize` request in a message or batch
+fn initialize_id(message: &JsonValue) -> Option<JsonValue> {
+    batch_entries(message)
+        .iter()
+        .find(|entry| entry.get("method").and_then(|m| m.as_str()) == Some(methods::INITIALIZE))
+        .and_then(|entry| entry.get("id"))
+        .filter(|id| !id.is_null())
+        .cloned()
+}
+
+/// Check if a reply, or an entry of a batch reply, is a result for request `id`
+fn answered(reply: &JsonValue, id: &JsonValue) -> bool {
+    batch_entries(reply)
+        .iter()
+        .any(|entry| entry.get("id") == Some(id) && entry.get("result").is_some())
+}
+
+/// The entries of a batch, or a single message as a batch of one
+fn batch_entries(message: &JsonValue) -> &[JsonValue] {
+    match message {
+        JsonValue::Array(entries) => entries,
+        message => std::slice::from_ref(message),
     }
 }
 
+/// Check if the client accepts an SSE stream as the response
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|h| h.to_str().ok())
+        .map(|accept| accept.contains("text/event-stream"))
+        .unwrap_or(false)
+}
+
+/// Turn a channel of JSON-RPC messages into SSE `message` events
+fn event_stream(
+    rx: mpsc::UnboundedReceiver<JsonValue>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(rx, |mut rx| async move {
+        let message = rx.recv().await?;
+        Some((Ok(Event::default().data(message.to_string())), rx))
+    })
+}
+
 // Dashboard endpoints
 
 async fn serve_dashboard() -> impl IntoResponse {
@@ -562,6 +640,7 @@ async fn get_stats(State(state): State<AppState>) -> impl IntoResponse {
         "avg_duration_ms": avg_duration,
         "endpoints_count": metrics.len(),
         "recent_logs_count": logs.len(),
+        "cache": state.metrics.cache_stats(),
         "timestamp": Utc::now()
     })))
 }
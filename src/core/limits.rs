@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// Execution limits for a tool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ToolLimits {
+    /// How long a call may take, including waiting for a concurrency slot
+    pub timeout: Option<Duration>,
+    /// How many calls of the tool may run at once
+    pub max_concurrency: Option<usize>,
+}
+
+impl ToolLimits {
+    /// Fill in the limits this one leaves unset from `defaults`
+    pub fn or(self, defaults: ToolLimits) -> ToolLimits {
+        ToolLimits {
+            timeout: self.timeout.or(defaults.timeout),
+            max_concurrency: self.max_concurrency.or(defaults.max_concurrency),
+        }
+    }
+}
+
+/// The limits in force for one tool, with its concurrency slots
+pub struct Limiter {
+    limits: ToolLimits,
+    permits: Option<Arc<Semaphore>>,
+}
+
+impl Limiter {
+    fn new(limits: ToolLimits) -> Self {
+        Self {
+            limits,
+            permits: limits.max_concurrency.map(|max| Arc::new(Semaphore::new(max.max(1)))),
+        }
+    }
+
+    pub fn limits(&self) -> ToolLimits {
+        self.limits
+    }
+
+    /// Wait for a concurrency slot, if the tool has a limit
+    ///
+    /// The slot is released when the returned permit is dropped.
+    pub async fn acquire(&self) -> Option<tokio::sync::OwnedSemaphorePermit> {
+        match self.permits {
+            // The semaphore is never closed, so acquiring only fails if it were
+            Some(ref permits) => Arc::clone(permits).acquire_owned().await.ok(),
+            None => None,
+        }
+    }
+}
+
+/// Default and per-tool limits, with a limiter per tool created on first use
+#[derive(Default)]
+pub struct ToolLimiters {
+    defaults: ToolLimits,
+    overrides: HashMap<String, ToolLimits>,
+    active: HashMap<String, Arc<Limiter>>,
+}
+
+impl ToolLimiters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the limits of tools without their own
+    pub fn set_defaults(&mut self, limits: ToolLimits) {
+        self.defaults = limits;
+        // Calls already running keep the limiter they started with
+        self.active.clear();
+    }
+
+    /// Set a tool's limits; unset fields fall back to the defaults
+    pub fn set(&mut self, name: &str, limits: ToolLimits) {
+        self.overrides.insert(name.to_string(), limits);
+        self.active.remove(name);
+    }
+
+    /// Get the limits in force for a tool
+    pub fn limits(&self, name: &str) -> ToolLimits {
+        self.overrides
+            .get(name)
+            .map(|limits| limits.or(self.defaults))
+            .unwrap_or(self.defaults)
+    }
+
+    /// Get the limiter for a tool if one has already been created
+    pub fn active_limiter(&self, name: &str) -> Option<Arc<Limiter>> {
+        self.active.get(name).cloned()
+    }
+
+    /// Get the limiter for a tool, creating it on first use
+    pub fn limiter(&mut self, name: &str) -> Arc<Limiter> {
+        if let Some(limiter) = self.active.get(name) {
+            return Arc::clone(limiter);
+        }
+        let limiter = Arc::new(Limiter::new(self.limits(name)));
+        self.active.insert(name.to_string(), Arc::clone(&limiter));
+        limiter
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_fall_back_to_defaults() {
+        let mut limiters = ToolLimiters::new();
+        limiters.set_defaults(ToolLimits {
+            timeout: Some(Duration::from_secs(30)),
+            max_concurrency: None,
+        });
+        limiters.set(
+            "render_chart",
+            ToolLimits {
+                timeout: None,
+                max_concurrency: Some(2),
+            },
+        );
+
+        assert_eq!(
+            limiters.limits("render_chart"),
+            ToolLimits {
+                timeout: Some(Duration::from_secs(30)),
+                max_concurrency: Some(2),
+            }
+        );
+        assert_eq!(limiters.limits("hello_world").max_concurrency, None);
+    }
+
+    #[test]
+    fn test_limiter_is_shared_until_limits_change() {
+        let mut limiters = ToolLimiters::new();
+        assert!(limiters.active_limiter("search").is_none());
+        let first = limiters.limiter("search");
+        assert!(Arc::ptr_eq(&first, &limiters.limiter("search")));
+        assert!(Arc::ptr_eq(&first, &limiters.active_limiter("search").unwrap()));
+
+        limiters.set("search", ToolLimits { timeout: None, max_concurrency: Some(1) });
+        assert!(limiters.active_limiter("search").is_none());
+        let second = limiters.limiter("search");
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert_eq!(second.limits().max_concurrency, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_limits_concurrency() {
+        let limiter = Limiter::new(ToolLimits { timeout: None, max_concurrency: Some(1) });
+
+        let permit = limiter.acquire().await;
+        assert!(permit.is_some());
+        assert!(tokio::time::timeout(Duration::from_millis(20), limiter.acquire()).await.is_err());
+
+        drop(permit);
+        assert!(limiter.acquire().await.is_some());
+    }
+}
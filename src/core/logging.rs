@@ -0,0 +1,80 @@
+use serde_json::json;
+use tracing::{debug, error, info, warn};
+
+use super::context::RequestContext;
+use super::protocol::{methods, JsonRpcNotification, LoggingLevel, LoggingMessageParams};
+use crate::core::types::JsonValue;
+
+/// Sends log messages to the client a request came from
+///
+/// Messages are delivered as `notifications/message` once the session has
+/// set a level with `logging/setLevel`, if they are at least that severe.
+/// They are always logged on the host through `tracing`.
+#[derive(Clone)]
+pub struct ClientLogger {
+    ctx: RequestContext,
+    name: Option<String>,
+}
+
+impl ClientLogger {
+    /// Log on behalf of a request, naming the component that logs
+    pub fn new(ctx: RequestContext, name: Option<&str>) -> Self {
+        Self {
+            ctx,
+            name: name.map(String::from),
+        }
+    }
+
+    /// Get the name messages are logged under
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Log a message
+    ///
+    /// `data` may be a string or any JSON value. Returns false if the message
+    /// was filtered out or could not be delivered.
+    pub async fn log(&self, level: LoggingLevel, data: impl Into<JsonValue>) -> bool {
+        let data = data.into();
+        let logger = self.name.as_deref().unwrap_or("client");
+        match level {
+            LoggingLevel::Debug => debug!("[{}] {}", logger, data),
+            LoggingLevel::Info | LoggingLevel::Notice => info!("[{}] {}", logger, data),
+            LoggingLevel::Warning => warn!("[{}] {}", logger, data),
+            _ => error!("[{}] {}", logger, data),
+        }
+
+        let threshold = match self.ctx.session() {
+            Some(session) => session.log_level().await,
+            None => None,
+        };
+        if threshold.is_none_or(|threshold| level < threshold) {
+            return false;
+        }
+
+        let params = LoggingMessageParams {
+            level,
+            logger: self.name.clone(),
+            data,
+        };
+        self.ctx
+            .notify(JsonRpcNotification::new(methods::LOG_MESSAGE, Some(json!(params))))
+            .await
+    }
+
+    pub async fn debug(&self, data: impl Into<JsonValue>) -> bool {
+        self.log(LoggingLevel::Debug, data).await
+    }
+
+    pub async fn info(&self, data: impl Into<JsonValue>) -> bool {
+        self.log(LoggingLevel::Info, data).await
+    }
+
+    pub async fn warning(&self, data: impl Into<JsonValue>) -> bool {
+        self.log(LoggingLevel::Warning, data).await
+    }
+
+    pub async fn error(&self, data: impl Into<JsonValue>) -> bool {
+        self.log(LoggingLevel::Error, data).await
+    }
+}
@@ -1,7 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio_util::task::TaskTracker;
+use tracing::error;
 use anyhow::Result;
 use mysql_async::prelude::*;
 
@@ -20,6 +24,9 @@ pub struct ApiCallLog {
     pub client_info: Option<String>,
 }
 
+/// A row of the `history_logs` table, in column order
+type ApiCallLogRow = (i64, String, String, Option<String>, Option<String>, String, Option<String>, u64, i64, Option<String>);
+
 /// Represents aggregated metrics for an endpoint
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EndpointMetrics {
@@ -32,10 +39,21 @@ pub struct EndpointMetrics {
     pub last_called: Option<DateTime<Utc>>,
 }
 
+/// Hit and miss counts of the tool result cache
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
 /// Metrics collector for tracking API calls
 pub struct MetricsCollector {
     logs: Arc<RwLock<Vec<ApiCallLog>>>,
     mysql_writer: Option<Arc<MySqlMetricsWriter>>,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    /// Records still being written in the background
+    pending: TaskTracker,
 }
 
 impl MetricsCollector {
@@ -43,6 +61,9 @@ impl MetricsCollector {
         Self {
             logs: Arc::new(RwLock::new(Vec::new())),
             mysql_writer: None,
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            pending: TaskTracker::new(),
         }
     }
 
@@ -50,6 +71,23 @@ impl MetricsCollector {
         Self {
             logs: Arc::new(RwLock::new(Vec::new())),
             mysql_writer: Some(mysql_writer),
+            cache_hits: AtomicU64::new(0),
+            cache_misses: AtomicU64::new(0),
+            pending: TaskTracker::new(),
+        }
+    }
+
+    /// Count a lookup in the tool result cache
+    pub fn record_cache_lookup(&self, hit: bool) {
+        let counter = if hit { &self.cache_hits } else { &self.cache_misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Get the hit and miss counts of the tool result cache
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.cache_hits.load(Ordering::Relaxed),
+            misses: self.cache_misses.load(Ordering::Relaxed),
         }
     }
 
@@ -74,6 +112,26 @@ impl MetricsCollector {
         Ok(())
     }
 
+    /// Record an API call in the background, without waiting for it to be stored
+    pub fn record_call_detached(self: &Arc<Self>, log: ApiCallLog) {
+        let metrics = Arc::clone(self);
+        self.pending.spawn(async move {
+            if let Err(e) = metrics.record_call(log).await {
+                error!("Failed to record metrics: {}", e);
+            }
+        });
+    }
+
+    /// Wait for records still being written in the background
+    ///
+    /// Returns false if some were still pending after `timeout`.
+    pub async fn flush(&self, timeout: Duration) -> bool {
+        self.pending.close();
+        let flushed = tokio::time::timeout(timeout, self.pending.wait()).await.is_ok();
+        self.pending.reopen();
+        flushed
+    }
+
     /// Get all logs (from MySQL if available, otherwise from memory)
     pub async fn get_recent_logs(&self, limit: usize) -> Vec<ApiCallLog> {
         // Try to get from MySQL first if available
@@ -108,7 +166,7 @@ impl MetricsCollector {
         // Group logs by endpoint
         for log in logs.iter() {
             let key = format!("{}:{}", log.method, log.endpoint);
-            metrics_map.entry(key).or_insert_with(Vec::new).push(log);
+            metrics_map.entry(key).or_default().push(log);
         }
 
         // Calculate metrics for each endpoint
@@ -116,7 +174,7 @@ impl MetricsCollector {
             .into_iter()
             .map(|(key, endpoint_logs)| {
                 let parts: Vec<&str> = key.split(':').collect();
-                let method = parts.get(0).unwrap_or(&"").to_string();
+                let method = parts.first().unwrap_or(&"").to_string();
                 let endpoint = parts.get(1).unwrap_or(&"").to_string();
 
                 let total_calls = endpoint_logs.len() as u64;
@@ -234,7 +292,7 @@ impl MySqlMetricsWriter {
         let logs: Vec<ApiCallLog> = conn
             .query_map(
                 query,
-                |(id, method, endpoint, request_params, response_data, response_status, error_message, duration_ms, ts, client_info): (i64, String, String, Option<String>, Option<String>, String, Option<String>, u64, i64, Option<String>)| {
+                |(id, method, endpoint, request_params, response_data, response_status, error_message, duration_ms, ts, client_info): ApiCallLogRow| {
                     ApiCallLog {
                         id: Some(id),
                         method,
@@ -244,7 +302,7 @@ impl MySqlMetricsWriter {
                         response_status,
                         error_message,
                         duration_ms,
-                        timestamp: DateTime::from_timestamp(ts, 0).unwrap_or_else(|| Utc::now()),
+                        timestamp: DateTime::from_timestamp(ts, 0).unwrap_or_else(Utc::now),
                         client_info,
                     }
                 },
@@ -310,7 +368,7 @@ impl MySqlMetricsWriter {
         let logs: Vec<ApiCallLog> = conn
             .query_map(
                 query,
-                |(id, method, endpoint, request_params, response_data, response_status, error_message, duration_ms, ts, client_info): (i64, String, String, Option<String>, Option<String>, String, Option<String>, u64, i64, Option<String>)| {
+                |(id, method, endpoint, request_params, response_data, response_status, error_message, duration_ms, ts, client_info): ApiCallLogRow| {
                     ApiCallLog {
                         id: Some(id),
                         method,
@@ -320,7 +378,7 @@ impl MySqlMetricsWriter {
                         response_status,
                         error_message,
                         duration_ms,
-                        timestamp: DateTime::from_timestamp(ts, 0).unwrap_or_else(|| Utc::now()),
+                        timestamp: DateTime::from_timestamp(ts, 0).unwrap_or_else(Utc::now),
                         client_info,
                     }
                 },
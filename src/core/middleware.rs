@@ -0,0 +1,231 @@
+use async_trait::async_trait;
+use std::future::Future;
+use std::sync::Arc;
+
+use super::context::RequestContext;
+use super::error::McpError;
+use super::protocol::{self, methods, ClientInfo};
+use crate::core::types::{JsonValue, PromptResult, ToolResult};
+use crate::tools::ToolMetadata;
+
+/// Who a call is made on behalf of
+#[derive(Debug, Clone, Default)]
+pub struct CallerIdentity {
+    /// The session the call arrived in, if any
+    pub session_id: Option<String>,
+    /// The user the session belongs to
+    pub user: Option<String>,
+    /// The client that announced itself in the `initialize` handshake
+    pub client: Option<ClientInfo>,
+}
+
+impl CallerIdentity {
+    /// Identify the caller of a request from its session
+    pub async fn from_context(ctx: &RequestContext) -> Self {
+        let Some(session) = ctx.session() else {
+            return Self::default();
+        };
+
+        Self {
+            session_id: Some(session.id().to_string()),
+            user: session.user().await,
+            client: session.client().await.map(|client| client.info),
+        }
+    }
+}
+
+/// A tool call, resource read or prompt request about to be handled
+#[derive(Debug, Clone)]
+pub struct Call {
+    /// The JSON-RPC method, e.g. `tools/call`
+    pub method: &'static str,
+    /// The tool or prompt name, or the resource URI
+    pub target: String,
+    /// The tool or prompt arguments; null for resource reads
+    pub arguments: JsonValue,
+    pub caller: CallerIdentity,
+    /// The metadata of the tool being called, if it exists; looked up again
+    /// when a `before` hook changes `target`
+    pub tool: Option<ToolMetadata>,
+    /// The request's `_meta` object
+    pub meta: Option<JsonValue>,
+}
+
+impl Call {
+    /// Get a field of the request's `_meta` object
+    pub fn meta(&self, key: &str) -> Option<&JsonValue> {
+        self.meta.as_ref().and_then(|meta| meta.get(key))
+    }
+}
+
+/// The result of a call, as seen by middleware
+#[derive(Debug, Clone)]
+pub enum CallResult {
+    Tool(ToolResult),
+    Resource(Vec<protocol::ResourceContent>),
+    Prompt(PromptResult),
+}
+
+impl CallResult {
+    pub fn into_tool(self) -> Result<ToolResult, McpError> {
+        match self {
+            CallResult::Tool(result) => Ok(result),
+            other => Err(mismatched(methods::CALL_TOOL, &other)),
+        }
+    }
+
+    pub fn into_resource(self) -> Result<Vec<protocol::ResourceContent>, McpError> {
+        match self {
+            CallResult::Resource(contents) => Ok(contents),
+            other => Err(mismatched(methods::READ_RESOURCE, &other)),
+        }
+    }
+
+    pub fn into_prompt(self) -> Result<PromptResult, McpError> {
+        match self {
+            CallResult::Prompt(result) => Ok(result),
+            other => Err(mismatched(methods::GET_PROMPT, &other)),
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            CallResult::Tool(_) => "tool",
+            CallResult::Resource(_) => "resource",
+            CallResult::Prompt(_) => "prompt",
+        }
+    }
+}
+
+fn mismatched(method: &str, result: &CallResult) -> McpError {
+    McpError::internal(format!("Middleware answered {} with a {} result", method, result.kind()))
+}
+
+/// Hooks run around every tool call, resource read and prompt request
+///
+/// `before` hooks run in the order the middleware was added and may rewrite
+/// the call, answer it themselves by returning a result, or reject it with
+/// an error. `after` hooks then run in reverse order for every middleware
+/// whose `before` hook ran, and may rewrite the result or error.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Inspect or rewrite a call before it is handled
+    ///
+    /// Returning `Ok(Some(result))` answers the call without handling it.
+    async fn before(&self, _call: &mut Call) -> Result<Option<CallResult>, McpError> {
+        Ok(None)
+    }
+
+    /// Inspect or rewrite the outcome of a call
+    async fn after(&self, _call: &Call, result: Result<CallResult, McpError>) -> Result<CallResult, McpError> {
+        result
+    }
+}
+
+/// Refuses tools that modify their environment to callers with read-only access
+///
+/// A tool counts as read-only only if its annotations say so.
+pub struct ReadOnlyPolicy {
+    is_restricted: Box<dyn Fn(&CallerIdentity) -> bool + Send + Sync>,
+    allow_non_destructive: bool,
+}
+
+impl ReadOnlyPolicy {
+    /// Restrict the callers for which `is_restricted` returns true
+    pub fn new(is_restricted: impl Fn(&CallerIdentity) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            is_restricted: Box::new(is_restricted),
+            allow_non_destructive: false,
+        }
+    }
+
+    /// Restrict the named users
+    pub fn for_users<I, S>(users: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let users: Vec<String> = users.into_iter().map(Into::into).collect();
+        Self::new(move |caller| caller.user.as_ref().is_some_and(|user| users.contains(user)))
+    }
+
+    /// Only refuse tools that may make destructive changes, letting through
+    /// ones annotated `destructiveHint: false`
+    pub fn allow_non_destructive(mut self) -> Self {
+        self.allow_non_destructive = true;
+        self
+    }
+}
+
+#[async_trait]
+impl Middleware for ReadOnlyPolicy {
+    async fn before(&self, call: &mut Call) -> Result<Option<CallResult>, McpError> {
+        if call.method != methods::CALL_TOOL || !(self.is_restricted)(&call.caller) {
+            return Ok(None);
+        }
+
+        let annotations = call.tool.as_ref().and_then(|tool| tool.annotations.clone()).unwrap_or_default();
+        let allowed = annotations.is_read_only() || (self.allow_non_destructive && !annotations.is_destructive());
+        if allowed {
+            Ok(None)
+        } else {
+            Err(McpError::tool_execution(format!(
+                "Tool {} may modify data, and the caller has read-only access",
+                call.target
+            )))
+        }
+    }
+}
+
+/// Run a call through a middleware chain
+///
+/// `tool_of` looks up the metadata of a target's tool, so that each hook sees
+/// that of the target it is given. `handle` is given the call's target
+/// and arguments as left by the `before` hooks.
+pub(crate) async fn run_chain<T, TFut, F, Fut>(
+    chain: &[Arc<dyn Middleware>],
+    mut call: Call,
+    tool_of: T,
+    handle: F,
+) -> Result<CallResult, McpError>
+where
+    T: Fn(String) -> TFut,
+    TFut: Future<Output = Option<ToolMetadata>>,
+    F: FnOnce(String, JsonValue) -> Fut,
+    Fut: Future<Output = Result<CallResult, McpError>>,
+{
+    let mut looked_up = call.target.clone();
+    let mut ran = 0;
+    let mut answer = None;
+    for middleware in chain {
+        if call.target != looked_up {
+            looked_up = call.target.clone();
+            call.tool = tool_of(looked_up.clone()).await;
+        }
+        ran += 1;
+        match middleware.before(&mut call).await {
+            Ok(None) => {}
+            Ok(Some(result)) => {
+                answer = Some(Ok(result));
+                break;
+            }
+            Err(e) => {
+                answer = Some(Err(e));
+                break;
+            }
+        }
+    }
+
+    if call.target != looked_up {
+        call.tool = tool_of(call.target.clone()).await;
+    }
+
+    let mut result = match answer {
+        Some(answer) => answer,
+        None => handle(call.target.clone(), call.arguments.clone()).await,
+    };
+    for middleware in chain[..ran].iter().rev() {
+        result = middleware.after(&call, result).await;
+    }
+    result
+}
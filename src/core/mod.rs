@@ -5,4 +5,22 @@ pub mod types;
 pub mod protocol;
 pub mod http_server;
 pub mod metrics;
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod session;
+pub mod shutdown;
+pub mod cache;
+pub mod context;
+pub mod error;
+pub mod limits;
+pub mod logging;
+pub mod middleware;
+pub mod notifications;
+pub mod pagination;
+pub mod registry;
+pub mod schema;
+pub mod dispatch;
+pub mod stdio;
+#[cfg(feature = "wassette")]
+pub mod message_broker;
+#[cfg(feature = "wassette")]
+pub mod wassette_runtime;
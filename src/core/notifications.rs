@@ -0,0 +1,86 @@
+use serde_json::json;
+use std::sync::Arc;
+use tracing::debug;
+
+use super::protocol::{methods, JsonRpcNotification};
+use super::session::{Session, SessionManager};
+
+/// Notifications the server pushes to clients
+#[derive(Debug, Clone, PartialEq)]
+pub enum McpNotification {
+    /// A resource the client subscribed to has changed
+    ResourceUpdated { uri: String },
+    /// Resources or resource templates were added, removed, replaced, enabled or disabled
+    ResourceListChanged,
+    /// Tools were added, removed, replaced, enabled or disabled
+    ToolListChanged,
+    /// Prompts were added, removed, replaced, enabled or disabled
+    PromptListChanged,
+}
+
+impl McpNotification {
+    /// Get the JSON-RPC method of the notification
+    pub fn method(&self) -> &'static str {
+        match self {
+            McpNotification::ResourceUpdated { .. } => methods::RESOURCE_UPDATED,
+            McpNotification::ResourceListChanged => methods::RESOURCES_LIST_CHANGED,
+            McpNotification::ToolListChanged => methods::TOOLS_LIST_CHANGED,
+            McpNotification::PromptListChanged => methods::PROMPTS_LIST_CHANGED,
+        }
+    }
+
+    /// Build the JSON-RPC message sent to clients
+    pub fn to_message(&self) -> JsonRpcNotification {
+        let params = match self {
+            McpNotification::ResourceUpdated { uri } => Some(json!({ "uri": uri })),
+            _ => None,
+        };
+        JsonRpcNotification::new(self.method(), params)
+    }
+
+    /// Check if a session should receive the notification
+    async fn is_wanted_by(&self, session: &Session) -> bool {
+        match self {
+            McpNotification::ResourceUpdated { uri } => session.is_subscribed(uri).await,
+            _ => true,
+        }
+    }
+}
+
+/// Delivers notifications to client sessions over their streams
+///
+/// Resources and tools that change state hold a broadcaster and call it
+/// when something a client may be watching changes. Resource updates only
+/// reach sessions subscribed to that resource; list changes reach every
+/// session.
+pub struct NotificationBroadcaster {
+    sessions: Arc<SessionManager>,
+}
+
+impl NotificationBroadcaster {
+    pub fn new(sessions: Arc<SessionManager>) -> Self {
+        Self { sessions }
+    }
+
+    /// Send a notification to every session that wants it
+    ///
+    /// Returns the number of sessions it was delivered to.
+    pub async fn broadcast(&self, notification: McpNotification) -> usize {
+        let sessions = self.sessions.all().await;
+        self.deliver(sessions, &notification).await
+    }
+
+    async fn deliver(&self, sessions: Vec<Arc<Session>>, notification: &McpNotification) -> usize {
+        let message = serde_json::to_value(notification.to_message()).unwrap();
+        let mut delivered = 0;
+
+        for session in sessions {
+            if notification.is_wanted_by(&session).await && session.send(message.clone()).await {
+                delivered += 1;
+            }
+        }
+
+        debug!("Delivered {} to {} session(s)", notification.method(), delivered);
+        delivered
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Result;
+
+/// Number of items per page when none is configured
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// A cursor that was not issued by this server
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid cursor: {0}")]
+pub struct InvalidCursor(pub String);
+
+/// One page of a paginated list
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor for the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Paginate `items` by a unique, stable key
+///
+/// Items are ordered by key and the cursor encodes the last key of the
+/// previous page, so pages stay consistent while items are registered or
+/// removed between requests. Cursors are opaque to clients.
+pub fn paginate<T>(
+    mut items: Vec<T>,
+    key: impl Fn(&T) -> &str,
+    cursor: Option<&str>,
+    page_size: usize,
+) -> Result<Page<T>> {
+    items.sort_by(|a, b| key(a).cmp(key(b)));
+
+    let start = match cursor {
+        Some(cursor) => {
+            let after = decode_cursor(cursor)?;
+            items.partition_point(|item| key(item) <= after.as_str())
+        }
+        None => 0,
+    };
+
+    let mut items: Vec<T> = items.into_iter().skip(start).collect();
+    let next_cursor = if items.len() > page_size {
+        items.truncate(page_size);
+        items.last().map(|item| encode_cursor(key(item)))
+    } else {
+        None
+    };
+
+    Ok(Page { items, next_cursor })
+}
+
+fn encode_cursor(key: &str) -> String {
+    hex::encode(key)
+}
+
+fn decode_cursor(cursor: &str) -> Result<String, InvalidCursor> {
+    hex::decode(cursor)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| InvalidCursor(cursor.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("item_{:02}", i)).collect()
+    }
+
+    #[test]
+    fn test_paginate_walks_all_pages() {
+        let mut cursor = None;
+        let mut seen = Vec::new();
+
+        loop {
+            let page = paginate(names(7), |s| s.as_str(), cursor.as_deref(), 3).unwrap();
+            seen.extend(page.items);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        assert_eq!(seen, names(7));
+    }
+
+    #[test]
+    fn test_paginate_orders_by_key() {
+        let items = vec!["b".to_string(), "c".to_string(), "a".to_string()];
+        let page = paginate(items, |s| s.as_str(), None, 10).unwrap();
+
+        assert_eq!(page.items, vec!["a", "b", "c"]);
+        assert!(page.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_paginate_exact_page_has_no_next_cursor() {
+        let page = paginate(names(3), |s| s.as_str(), None, 3).unwrap();
+
+        assert_eq!(page.items.len(), 3);
+        assert!(page.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_paginate_stable_after_removal() {
+        let first = paginate(names(6), |s| s.as_str(), None, 2).unwrap();
+
+        // The last item of the first page goes away before the next request
+        let mut remaining = names(6);
+        remaining.retain(|s| s != "item_01");
+        let second = paginate(remaining, |s| s.as_str(), first.next_cursor.as_deref(), 2).unwrap();
+
+        assert_eq!(second.items, vec!["item_02", "item_03"]);
+    }
+
+    #[test]
+    fn test_paginate_invalid_cursor() {
+        let err = paginate(names(3), |s| s.as_str(), Some("not-a-cursor"), 2).unwrap_err();
+        assert!(err.downcast_ref::<InvalidCursor>().is_some());
+    }
+}
@@ -1,5 +1,9 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 /// MCP JSON-RPC Request
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +56,58 @@ impl JsonRpcResponse {
             }),
         }
     }
+
+    pub fn error_with_data(id: Option<JsonValue>, code: i32, message: String, data: JsonValue) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: None,
+            error: Some(JsonRpcError {
+                code,
+                message,
+                data: Some(data),
+            }),
+        }
+    }
+}
+
+/// MCP JSON-RPC Notification (a message without an id)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<JsonValue>,
+}
+
+impl JsonRpcNotification {
+    pub fn new(method: &str, params: Option<JsonValue>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+        }
+    }
+}
+
+/// Protocol versions this server speaks, newest first
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// The newest protocol version this server speaks
+pub const LATEST_PROTOCOL_VERSION: &str = SUPPORTED_PROTOCOL_VERSIONS[0];
+
+/// Pick the protocol version to use for a client requesting `requested`
+///
+/// A supported version is accepted as-is. Otherwise the newest supported
+/// version not newer than the request is chosen (versions are dates, so they
+/// compare as strings), falling back to the latest version for clients older
+/// than anything we speak; such clients may then disconnect.
+pub fn negotiate_protocol_version(requested: &str) -> &'static str {
+    SUPPORTED_PROTOCOL_VERSIONS
+        .iter()
+        .find(|version| **version <= requested)
+        .copied()
+        .unwrap_or(LATEST_PROTOCOL_VERSION)
 }
 
 /// MCP Protocol Methods
@@ -59,10 +115,30 @@ pub mod methods {
     pub const INITIALIZE: &str = "initialize";
     pub const LIST_RESOURCES: &str = "resources/list";
     pub const READ_RESOURCE: &str = "resources/read";
+    pub const LIST_RESOURCE_TEMPLATES: &str = "resources/templates/list";
+    pub const SUBSCRIBE: &str = "resources/subscribe";
+    pub const UNSUBSCRIBE: &str = "resources/unsubscribe";
     pub const LIST_TOOLS: &str = "tools/list";
     pub const CALL_TOOL: &str = "tools/call";
     pub const LIST_PROMPTS: &str = "prompts/list";
     pub const GET_PROMPT: &str = "prompts/get";
+    pub const COMPLETE: &str = "completion/complete";
+    pub const SET_LOG_LEVEL: &str = "logging/setLevel";
+    pub const PING: &str = "ping";
+    // Requests the server sends to the client
+    pub const CREATE_MESSAGE: &str = "sampling/createMessage";
+    pub const LIST_ROOTS: &str = "roots/list";
+
+    // Notifications (no response is sent)
+    pub const INITIALIZED: &str = "notifications/initialized";
+    pub const CANCELLED: &str = "notifications/cancelled";
+    pub const PROGRESS: &str = "notifications/progress";
+    pub const LOG_MESSAGE: &str = "notifications/message";
+    pub const RESOURCE_UPDATED: &str = "notifications/resources/updated";
+    pub const RESOURCES_LIST_CHANGED: &str = "notifications/resources/list_changed";
+    pub const TOOLS_LIST_CHANGED: &str = "notifications/tools/list_changed";
+    pub const PROMPTS_LIST_CHANGED: &str = "notifications/prompts/list_changed";
+    pub const ROOTS_LIST_CHANGED: &str = "notifications/roots/list_changed";
 }
 
 /// Initialize Request Parameters
@@ -75,12 +151,22 @@ pub struct InitializeParams {
     pub client_info: ClientInfo,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ClientCapabilities {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub experimental: Option<JsonValue>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sampling: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub roots: Option<RootsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elicitation: Option<JsonValue>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RootsCapability {
+    #[serde(rename = "listChanged", default)]
+    pub list_changed: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -101,9 +187,16 @@ pub struct InitializeResult {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerCapabilities {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub resources: Option<ResourcesCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<ToolsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub prompts: Option<PromptsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completions: Option<CompletionsCapability>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logging: Option<LoggingCapability>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -125,16 +218,34 @@ pub struct PromptsCapability {
     pub list_changed: bool,
 }
 
+/// Advertises `completion/complete`; it has no options
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompletionsCapability {}
+
+/// Advertises `logging/setLevel`; it has no options
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoggingCapability {}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerInfo {
     pub name: String,
     pub version: String,
 }
 
+/// Parameters of the paginated list methods
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PaginatedParams {
+    /// Opaque cursor from the previous page's `nextCursor`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+}
+
 /// Resource List Response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceListResult {
     pub resources: Vec<ResourceInfo>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -147,19 +258,45 @@ pub struct ResourceInfo {
     pub mime_type: Option<String>,
 }
 
+/// Resource Template List Result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceTemplateListResult {
+    #[serde(rename = "resourceTemplates")]
+    pub resource_templates: Vec<ResourceTemplateInfo>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceTemplateInfo {
+    #[serde(rename = "uriTemplate")]
+    pub uri_template: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
 /// Resource Read Parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReadResourceParams {
     pub uri: String,
 }
 
+/// Resource Subscribe / Unsubscribe Parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscribeParams {
+    pub uri: String,
+}
+
 /// Resource Read Result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReadResourceResult {
     pub contents: Vec<ResourceContent>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ResourceContent {
     pub uri: String,
     #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
@@ -170,10 +307,34 @@ pub struct ResourceContent {
     pub blob: Option<String>,
 }
 
+impl ResourceContent {
+    /// Create text contents
+    pub fn text(uri: &str, mime_type: Option<&str>, text: impl Into<String>) -> Self {
+        Self {
+            uri: uri.to_string(),
+            mime_type: mime_type.map(str::to_string),
+            text: Some(text.into()),
+            blob: None,
+        }
+    }
+
+    /// Create binary contents, base64-encoding the data
+    pub fn blob(uri: &str, mime_type: Option<&str>, data: &[u8]) -> Self {
+        Self {
+            uri: uri.to_string(),
+            mime_type: mime_type.map(str::to_string),
+            text: None,
+            blob: Some(BASE64.encode(data)),
+        }
+    }
+}
+
 /// Tool List Result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolListResult {
     pub tools: Vec<ToolInfo>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -182,6 +343,65 @@ pub struct ToolInfo {
     pub description: String,
     #[serde(rename = "inputSchema")]
     pub input_schema: JsonValue,
+    #[serde(rename = "outputSchema", skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<JsonValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<ToolAnnotations>,
+}
+
+/// Hints about how a tool behaves, for clients deciding which calls need
+/// confirmation
+///
+/// Hints are not guarantees; clients should not trust them from servers
+/// they don't trust. Unset hints take the defaults of the accessors.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolAnnotations {
+    /// Human-readable name of the tool
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The tool does not modify its environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_only_hint: Option<bool>,
+    /// A tool that modifies its environment may do so destructively
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub destructive_hint: Option<bool>,
+    /// Repeating a call with the same arguments has no further effect
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idempotent_hint: Option<bool>,
+    /// The tool interacts with an open world of external entities
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_world_hint: Option<bool>,
+}
+
+impl ToolAnnotations {
+    /// Annotations of a tool that only reads
+    pub fn read_only() -> Self {
+        Self {
+            read_only_hint: Some(true),
+            ..Default::default()
+        }
+    }
+
+    /// Check if the tool does not modify its environment (default false)
+    pub fn is_read_only(&self) -> bool {
+        self.read_only_hint.unwrap_or(false)
+    }
+
+    /// Check if the tool may make destructive changes (default true unless read-only)
+    pub fn is_destructive(&self) -> bool {
+        !self.is_read_only() && self.destructive_hint.unwrap_or(true)
+    }
+
+    /// Check if repeated calls have no further effect (default false)
+    pub fn is_idempotent(&self) -> bool {
+        self.is_read_only() || self.idempotent_hint.unwrap_or(false)
+    }
+
+    /// Check if the tool reaches outside the server (default true)
+    pub fn is_open_world(&self) -> bool {
+        self.open_world_hint.unwrap_or(true)
+    }
 }
 
 /// Call Tool Parameters
@@ -196,21 +416,99 @@ pub struct CallToolParams {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CallToolResult {
     pub content: Vec<ToolContent>,
+    #[serde(rename = "structuredContent", skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<JsonValue>,
     #[serde(rename = "isError", skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ToolContent {
-    #[serde(rename = "type")]
-    pub content_type: String,
-    pub text: String,
+/// A content part of a tool result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolContent {
+    Text {
+        text: String,
+    },
+    /// Base64-encoded image data
+    Image {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    /// Base64-encoded audio data
+    Audio {
+        data: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    /// A link to a resource the client can read separately
+    ResourceLink {
+        uri: String,
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        #[serde(rename = "mimeType", skip_serializing_if = "Option::is_none")]
+        mime_type: Option<String>,
+    },
+    /// Resource contents embedded in the result
+    Resource {
+        resource: ResourceContent,
+    },
+}
+
+impl ToolContent {
+    /// Create a text content part
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text { text: text.into() }
+    }
+
+    /// Create an image content part, base64-encoding the data
+    pub fn image(data: &[u8], mime_type: &str) -> Self {
+        Self::Image {
+            data: BASE64.encode(data),
+            mime_type: mime_type.to_string(),
+        }
+    }
+
+    /// Create an audio content part, base64-encoding the data
+    pub fn audio(data: &[u8], mime_type: &str) -> Self {
+        Self::Audio {
+            data: BASE64.encode(data),
+            mime_type: mime_type.to_string(),
+        }
+    }
+
+    /// Create a link to a resource
+    pub fn resource_link(uri: &str, name: &str) -> Self {
+        Self::ResourceLink {
+            uri: uri.to_string(),
+            name: name.to_string(),
+            description: None,
+            mime_type: None,
+        }
+    }
+
+    /// Embed the text contents of a resource
+    pub fn embedded_text(uri: &str, mime_type: Option<&str>, text: impl Into<String>) -> Self {
+        Self::Resource {
+            resource: ResourceContent::text(uri, mime_type, text),
+        }
+    }
+
+    /// Embed the binary contents of a resource, base64-encoding the data
+    pub fn embedded_blob(uri: &str, mime_type: Option<&str>, data: &[u8]) -> Self {
+        Self::Resource {
+            resource: ResourceContent::blob(uri, mime_type, data),
+        }
+    }
 }
 
 /// Prompt List Result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptListResult {
     pub prompts: Vec<PromptInfo>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -259,3 +557,423 @@ pub struct PromptContent {
     pub content_type: String,
     pub text: String,
 }
+
+/// Severity of a log message, as in RFC 5424, least severe first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoggingLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl std::str::FromStr for LoggingLevel {
+    type Err = serde_json::Error;
+
+    fn from_str(level: &str) -> Result<Self, Self::Err> {
+        serde_json::from_value(JsonValue::String(level.to_string()))
+    }
+}
+
+/// Set Level Parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetLevelParams {
+    pub level: LoggingLevel,
+}
+
+/// Parameters of a `notifications/message` log message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingMessageParams {
+    pub level: LoggingLevel,
+    /// Name of the component that logged the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logger: Option<String>,
+    pub data: JsonValue,
+}
+
+/// A message in a sampling conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingMessage {
+    /// `user` or `assistant`
+    pub role: String,
+    /// Text, image or audio content
+    pub content: ToolContent,
+}
+
+impl SamplingMessage {
+    /// Create a text message from the user
+    pub fn user(text: impl Into<String>) -> Self {
+        Self {
+            role: "user".to_string(),
+            content: ToolContent::text(text),
+        }
+    }
+
+    /// Create a text message from the assistant
+    pub fn assistant(text: impl Into<String>) -> Self {
+        Self {
+            role: "assistant".to_string(),
+            content: ToolContent::text(text),
+        }
+    }
+}
+
+/// What the server would like the client to weigh when picking a model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPreferences {
+    /// Model names to prefer, in order; the client may map them to similar models
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hints: Vec<ModelHint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_priority: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed_priority: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intelligence_priority: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelHint {
+    pub name: String,
+}
+
+/// Create Message Parameters, sent to the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMessageParams {
+    pub messages: Vec<SamplingMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_preferences: Option<ModelPreferences>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    pub max_tokens: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stop_sequences: Vec<String>,
+}
+
+impl CreateMessageParams {
+    /// Ask for a reply to `messages` of at most `max_tokens`
+    pub fn new(messages: Vec<SamplingMessage>, max_tokens: u32) -> Self {
+        Self {
+            messages,
+            model_preferences: None,
+            system_prompt: None,
+            temperature: None,
+            max_tokens,
+            stop_sequences: Vec::new(),
+        }
+    }
+
+    pub fn with_system_prompt(mut self, system_prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(system_prompt.into());
+        self
+    }
+
+    pub fn with_model_preferences(mut self, preferences: ModelPreferences) -> Self {
+        self.model_preferences = Some(preferences);
+        self
+    }
+}
+
+/// Create Message Result, returned by the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMessageResult {
+    pub role: String,
+    pub content: ToolContent,
+    /// The model that produced the message
+    pub model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+}
+
+impl CreateMessageResult {
+    /// Get the message's text, if it is a text message
+    pub fn text(&self) -> Option<&str> {
+        match &self.content {
+            ToolContent::Text { text } => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// A directory or other URI the user has exposed to the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Root {
+    /// Usually a `file://` URI
+    pub uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl Root {
+    pub fn new(uri: impl Into<String>) -> Self {
+        Self {
+            uri: uri.into(),
+            name: None,
+        }
+    }
+
+    /// Get the local directory a `file://` root names
+    pub fn path(&self) -> Option<PathBuf> {
+        file_uri_path(&self.uri)
+    }
+
+    /// Check if a URI is the root or lies beneath it
+    ///
+    /// Percent-escapes are decoded and `.` and `..` segments resolved first,
+    /// so a URI can't escape the root by climbing out of it. The check is
+    /// lexical: it can't see symlinks, so file access should go through
+    /// `RequestContext::resolve_within_roots`.
+    pub fn contains(&self, uri: &str) -> bool {
+        match (normalize_uri(&self.uri), normalize_uri(uri)) {
+            (Some(root), Some(uri)) => {
+                let root = root.trim_end_matches('/');
+                uri == root || uri.strip_prefix(root).is_some_and(|rest| rest.starts_with('/'))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Get the local path a `file://` URI names, decoding its percent-escapes
+///
+/// Returns `None` for other schemes, remote hosts and malformed escapes.
+pub fn file_uri_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let path = rest.strip_prefix("localhost").unwrap_or(rest);
+    if !path.starts_with('/') {
+        return None;
+    }
+    percent_decode(path).map(PathBuf::from)
+}
+
+/// Decode the `%XX` escapes of a URI component
+///
+/// Returns `None` if an escape is malformed or the result isn't UTF-8.
+pub fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            decoded.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Decode a URI's path and resolve its `.` and `..` segments
+///
+/// Returns `None` if the path is malformed or `..` climbs above its top.
+fn normalize_uri(uri: &str) -> Option<String> {
+    let (prefix, path) = match uri.find("://") {
+        Some(i) => uri.split_at(i + 3),
+        None => ("", uri),
+    };
+    let path = percent_decode(path)?;
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "." => {}
+            ".." => {
+                // Keep the leading empty segment of an absolute path
+                if segments.len() <= 1 {
+                    return None;
+                }
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    Some(format!("{}{}", prefix, segments.join("/")))
+}
+
+/// List Roots Result, returned by the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListRootsResult {
+    pub roots: Vec<Root>,
+}
+
+/// Most values a completion result carries
+pub const MAX_COMPLETION_VALUES: usize = 100;
+
+/// Complete Parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteParams {
+    #[serde(rename = "ref")]
+    pub reference: CompletionReference,
+    pub argument: CompletionArgument,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<CompletionContext>,
+}
+
+/// What is being completed: a prompt, or a resource template by its URI template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum CompletionReference {
+    #[serde(rename = "ref/prompt")]
+    Prompt { name: String },
+    #[serde(rename = "ref/resource")]
+    Resource { uri: String },
+}
+
+/// The argument being completed and what has been typed so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletionArgument {
+    pub name: String,
+    pub value: String,
+}
+
+/// Arguments the client has already filled in
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompletionContext {
+    #[serde(default)]
+    pub arguments: HashMap<String, String>,
+}
+
+/// Complete Result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteResult {
+    pub completion: Completion,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Completion {
+    pub values: Vec<String>,
+    /// Number of matching values, which may exceed those returned
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<usize>,
+    pub has_more: bool,
+}
+
+impl Completion {
+    /// Build a completion from every matching value, keeping the first
+    /// `MAX_COMPLETION_VALUES`
+    pub fn from_values(mut values: Vec<String>) -> Self {
+        let total = values.len();
+        values.truncate(MAX_COMPLETION_VALUES);
+        Self {
+            has_more: total > values.len(),
+            values,
+            total: Some(total),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate_supported_version() {
+        assert_eq!(negotiate_protocol_version("2024-11-05"), "2024-11-05");
+        assert_eq!(negotiate_protocol_version("2025-03-26"), "2025-03-26");
+    }
+
+    #[test]
+    fn test_negotiate_newer_client() {
+        assert_eq!(negotiate_protocol_version("2099-01-01"), LATEST_PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn test_negotiate_unknown_version_between_supported() {
+        assert_eq!(negotiate_protocol_version("2025-01-01"), "2024-11-05");
+    }
+
+    #[test]
+    fn test_negotiate_older_client() {
+        assert_eq!(negotiate_protocol_version("2023-01-01"), LATEST_PROTOCOL_VERSION);
+    }
+
+    #[test]
+    fn test_tool_content_wire_format() {
+        let image = serde_json::to_value(ToolContent::image(b"png", "image/png")).unwrap();
+        assert_eq!(image, serde_json::json!({"type": "image", "data": "cG5n", "mimeType": "image/png"}));
+
+        let link = serde_json::to_value(ToolContent::resource_link("file:///a.txt", "a.txt")).unwrap();
+        assert_eq!(link, serde_json::json!({"type": "resource_link", "uri": "file:///a.txt", "name": "a.txt"}));
+
+        let embedded = serde_json::to_value(ToolContent::embedded_text("file:///a.txt", None, "hi")).unwrap();
+        assert_eq!(
+            embedded,
+            serde_json::json!({"type": "resource", "resource": {"uri": "file:///a.txt", "text": "hi"}})
+        );
+    }
+
+    #[test]
+    fn test_tool_annotation_defaults() {
+        let unannotated = ToolAnnotations::default();
+        assert!(!unannotated.is_read_only());
+        assert!(unannotated.is_destructive());
+        assert!(!unannotated.is_idempotent());
+        assert!(unannotated.is_open_world());
+
+        let read_only = ToolAnnotations::read_only();
+        assert!(!read_only.is_destructive());
+        assert!(read_only.is_idempotent());
+        assert_eq!(serde_json::to_value(&read_only).unwrap(), serde_json::json!({"readOnlyHint": true}));
+    }
+
+    #[test]
+    fn test_completion_is_capped() {
+        let values: Vec<String> = (0..150).map(|i| format!("topic-{}", i)).collect();
+        let completion = Completion::from_values(values);
+
+        assert_eq!(completion.values.len(), MAX_COMPLETION_VALUES);
+        assert_eq!(completion.values[0], "topic-0");
+        assert_eq!(completion.total, Some(150));
+        assert!(completion.has_more);
+
+        let completion = Completion::from_values(vec!["rust".to_string()]);
+        assert_eq!(
+            serde_json::to_value(completion).unwrap(),
+            serde_json::json!({"values": ["rust"], "total": 1, "hasMore": false})
+        );
+    }
+
+    #[test]
+    fn test_root_contains() {
+        let root = Root::new("file:///home/user/project/");
+
+        assert!(root.contains("file:///home/user/project"));
+        assert!(root.contains("file:///home/user/project/src/main.rs"));
+        assert!(root.contains("file:///home/user/project/src/../Cargo.toml"));
+        assert!(!root.contains("file:///home/user/project-old/notes.txt"));
+        assert!(!root.contains("file:///home/user/project/../secrets"));
+        assert!(!root.contains("file:///../../etc/passwd"));
+        assert!(!root.contains("https://home/user/project/README.md"));
+
+        // Escapes are decoded before climbing out is checked
+        assert!(root.contains("file:///home/user/project/my%20notes.txt"));
+        assert!(!root.contains("file:///home/user/project/%2e%2e/secrets"));
+        assert!(!root.contains("file:///home/user/project/%zz"));
+    }
+
+    #[test]
+    fn test_file_uri_path() {
+        assert_eq!(file_uri_path("file:///tmp/my%20notes.txt"), Some(PathBuf::from("/tmp/my notes.txt")));
+        assert_eq!(file_uri_path("file://localhost/tmp"), Some(PathBuf::from("/tmp")));
+        assert_eq!(file_uri_path("file://server/share"), None);
+        assert_eq!(file_uri_path("https://example.com/tmp"), None);
+        assert_eq!(file_uri_path("file:///tmp/%2"), None);
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A registered component with its metadata, cached at registration
+struct Entry<T: ?Sized, M> {
+    item: Arc<T>,
+    metadata: M,
+    enabled: bool,
+}
+
+/// Name-indexed store of registered components
+///
+/// Lookups are by key (a tool or prompt name, a resource URI) and never call
+/// into the components themselves; metadata is read once when a component is
+/// registered. Iteration follows registration order. Disabled components stay
+/// registered but are skipped by `get` and the listing methods.
+pub struct Registry<T: ?Sized, M> {
+    entries: HashMap<String, Entry<T, M>>,
+    /// Keys in registration order
+    order: Vec<String>,
+}
+
+impl<T: ?Sized, M: Clone> Registry<T, M> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: Vec::new(),
+        }
+    }
+
+    /// Add a component under a new key
+    ///
+    /// Returns false, leaving the registry unchanged, if the key is taken.
+    pub fn insert(&mut self, key: String, item: Arc<T>, metadata: M) -> bool {
+        if self.entries.contains_key(&key) {
+            return false;
+        }
+        self.order.push(key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                item,
+                metadata,
+                enabled: true,
+            },
+        );
+        true
+    }
+
+    /// Add a component, overriding any component registered under the key
+    ///
+    /// An overridden component keeps its place and enabled state. Returns true
+    /// if a component was overridden.
+    pub fn replace(&mut self, key: String, item: Arc<T>, metadata: M) -> bool {
+        match self.entries.get_mut(&key) {
+            Some(entry) => {
+                entry.item = item;
+                entry.metadata = metadata;
+                true
+            }
+            None => {
+                self.insert(key, item, metadata);
+                false
+            }
+        }
+    }
+
+    /// Remove a component, returning false if the key is not registered
+    pub fn remove(&mut self, key: &str) -> bool {
+        if self.entries.remove(key).is_none() {
+            return false;
+        }
+        self.order.retain(|k| k != key);
+        true
+    }
+
+    /// Enable or disable a component
+    ///
+    /// Returns `None` if the key is not registered, otherwise whether the
+    /// state changed.
+    pub fn set_enabled(&mut self, key: &str, enabled: bool) -> Option<bool> {
+        let entry = self.entries.get_mut(key)?;
+        let changed = entry.enabled != enabled;
+        entry.enabled = enabled;
+        Some(changed)
+    }
+
+    /// Get an enabled component by key
+    pub fn get(&self, key: &str) -> Option<Arc<T>> {
+        self.entries
+            .get(key)
+            .filter(|entry| entry.enabled)
+            .map(|entry| Arc::clone(&entry.item))
+    }
+
+    /// Get an enabled component together with its cached metadata
+    pub fn get_with_metadata(&self, key: &str) -> Option<(Arc<T>, M)> {
+        self.entries
+            .get(key)
+            .filter(|entry| entry.enabled)
+            .map(|entry| (Arc::clone(&entry.item), entry.metadata.clone()))
+    }
+
+    /// Iterate over the enabled components and their metadata in registration order
+    pub fn enabled(&self) -> impl Iterator<Item = (&Arc<T>, &M)> {
+        self.order
+            .iter()
+            .filter_map(|key| self.entries.get(key))
+            .filter(|entry| entry.enabled)
+            .map(|entry| (&entry.item, &entry.metadata))
+    }
+
+    /// Get the metadata of the enabled components in registration order
+    pub fn metadata(&self) -> Vec<M> {
+        self.enabled().map(|(_, metadata)| metadata.clone()).collect()
+    }
+
+    /// Iterate over all components and their keys, enabled or not, in registration order
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &Arc<T>)> {
+        self.order
+            .iter()
+            .filter_map(|key| self.entries.get(key).map(|entry| (key.as_str(), &entry.item)))
+    }
+
+    /// Check if any component is enabled
+    pub fn has_enabled(&self) -> bool {
+        self.entries.values().any(|entry| entry.enabled)
+    }
+
+    /// Get the number of registered components, enabled or not
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<T: ?Sized, M: Clone> Default for Registry<T, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> Registry<str, u32> {
+        let mut registry = Registry::new();
+        registry.insert("b".to_string(), Arc::from("first"), 1);
+        registry.insert("a".to_string(), Arc::from("second"), 2);
+        registry
+    }
+
+    #[test]
+    fn test_insert_rejects_duplicates() {
+        let mut registry = registry();
+
+        assert!(!registry.insert("b".to_string(), Arc::from("other"), 3));
+        assert_eq!(&*registry.get("b").unwrap(), "first");
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[test]
+    fn test_replace_keeps_position() {
+        let mut registry = registry();
+
+        assert!(registry.replace("b".to_string(), Arc::from("replaced"), 3));
+        assert!(!registry.replace("c".to_string(), Arc::from("new"), 4));
+
+        assert_eq!(&*registry.get("b").unwrap(), "replaced");
+        assert_eq!(registry.metadata(), vec![3, 2, 4]);
+    }
+
+    #[test]
+    fn test_disabled_entries_are_hidden() {
+        let mut registry = registry();
+
+        assert_eq!(registry.set_enabled("b", false), Some(true));
+        assert_eq!(registry.set_enabled("b", false), Some(false));
+        assert_eq!(registry.set_enabled("missing", false), None);
+
+        assert!(registry.get("b").is_none());
+        assert_eq!(registry.metadata(), vec![2]);
+        assert_eq!(registry.len(), 2);
+        assert!(registry.has_enabled());
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut registry = registry();
+
+        assert!(registry.remove("b"));
+        assert!(!registry.remove("b"));
+        assert!(registry.get("b").is_none());
+        assert_eq!(registry.metadata(), vec![2]);
+    }
+}
@@ -0,0 +1,682 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{OnceLock, RwLock};
+
+use regex::Regex;
+
+use crate::core::types::JsonValue;
+
+/// A value that does not conform to a JSON Schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    /// JSON Pointer to the offending value, e.g. `/items/0/name`
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() { "/" } else { &self.path };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Keywords `validate` enforces
+const ASSERTIONS: &[&str] = &[
+    "type", "enum", "const",
+    "minimum", "maximum", "exclusiveMinimum", "exclusiveMaximum", "multipleOf",
+    "minLength", "maxLength", "pattern", "format",
+    "properties", "required", "additionalProperties", "patternProperties",
+    "items", "minItems", "maxItems", "uniqueItems",
+    "allOf", "anyOf", "oneOf", "not", "$ref",
+];
+
+/// Keywords that only describe a schema, so have nothing to enforce
+const ANNOTATIONS: &[&str] = &[
+    "$schema", "$id", "$comment", "$defs", "definitions",
+    "title", "description", "default", "examples", "deprecated", "readOnly", "writeOnly",
+];
+
+/// Values of `type` that `validate` knows
+const TYPES: &[&str] = &["null", "boolean", "object", "array", "string", "number", "integer"];
+
+/// Values of `format` that `validate` checks
+const FORMATS: &[&str] = &["date-time", "date", "time", "email", "uri", "uuid", "ipv4", "ipv6"];
+
+/// Validate a value against a JSON Schema
+///
+/// Covers the keywords tool schemas use: `type`, `enum`, `const`, the
+/// numeric, string length and array length bounds, `multipleOf`, `pattern`,
+/// `format`, `properties`, `patternProperties`, `required`,
+/// `additionalProperties`, `items`, `uniqueItems`, `allOf`/`anyOf`/`oneOf`,
+/// `not` and local `$ref`s. Unknown keywords are ignored; use `check` to
+/// refuse schemas that rely on them. Returns the first violation found.
+pub fn validate(schema: &JsonValue, value: &JsonValue) -> Result<(), SchemaError> {
+    validate_at(schema, schema, value, "")
+}
+
+/// Check that `validate` enforces everything a schema asks for
+///
+/// Fails on keywords it doesn't know, `type`s and `format`s it doesn't
+/// check, invalid `pattern`s, `$ref`s that don't point into the schema
+/// itself and `$ref`s that lead back to where they started without
+/// descending into the value, which `validate` would follow forever.
+pub fn check(schema: &JsonValue) -> Result<(), SchemaError> {
+    check_at(schema, schema, "")
+}
+
+fn check_at(root: &JsonValue, schema: &JsonValue, path: &str) -> Result<(), SchemaError> {
+    let fail = |keyword: &str, message: String| Err(SchemaError {
+        path: format!("{}/{}", path, escape_pointer(keyword)),
+        message,
+    });
+
+    let schema = match schema {
+        JsonValue::Bool(_) => return Ok(()),
+        JsonValue::Object(schema) => schema,
+        _ => {
+            return Err(SchemaError {
+                path: path.to_string(),
+                message: "must be an object or a boolean".to_string(),
+            })
+        }
+    };
+
+    for (keyword, argument) in schema {
+        let keyword_path = format!("{}/{}", path, escape_pointer(keyword));
+        match keyword.as_str() {
+            "properties" | "patternProperties" | "$defs" | "definitions" => {
+                let Some(schemas) = argument.as_object() else {
+                    return fail(keyword, "must be an object of schemas".to_string());
+                };
+                for (name, sub) in schemas {
+                    if keyword == "patternProperties" {
+                        if let Err(e) = compile_pattern(name) {
+                            return fail(keyword, format!("invalid pattern '{}': {}", name, e));
+                        }
+                    }
+                    check_at(root, sub, &format!("{}/{}", keyword_path, escape_pointer(name)))?;
+                }
+            }
+            "allOf" | "anyOf" | "oneOf" => {
+                let Some(schemas) = argument.as_array() else {
+                    return fail(keyword, "must be an array of schemas".to_string());
+                };
+                for (index, sub) in schemas.iter().enumerate() {
+                    check_at(root, sub, &format!("{}/{}", keyword_path, index))?;
+                }
+            }
+            "items" | "additionalProperties" | "not" => check_at(root, argument, &keyword_path)?,
+            "type" => {
+                let names = match argument {
+                    JsonValue::Array(names) => names.iter().collect(),
+                    name => vec![name],
+                };
+                for name in names {
+                    match name.as_str() {
+                        Some(name) if TYPES.contains(&name) => {}
+                        Some(name) => return fail(keyword, format!("unknown type '{}'", name)),
+                        None => return fail(keyword, "must be a type name or an array of them".to_string()),
+                    }
+                }
+            }
+            "exclusiveMinimum" | "exclusiveMaximum" | "multipleOf" if !argument.is_number() => {
+                return fail(keyword, "must be a number".to_string());
+            }
+            "pattern" => {
+                let pattern = argument.as_str().unwrap_or_default();
+                if let Err(e) = compile_pattern(pattern) {
+                    return fail(keyword, format!("invalid pattern: {}", e));
+                }
+            }
+            "format" => {
+                let format = argument.as_str().unwrap_or_default();
+                if !FORMATS.contains(&format) {
+                    return fail(keyword, format!("unsupported format '{}'", format));
+                }
+            }
+            "$ref" => {
+                let target = argument.as_str().unwrap_or_default();
+                if resolve_ref(root, target).is_none() {
+                    return fail(keyword, format!("cannot resolve '{}'", target));
+                }
+                if let Some(cycle) = find_ref_cycle(root, path, &mut Vec::new(), &mut HashSet::new()) {
+                    return fail(keyword, format!("'{}' leads back to '#{}' without descending into the value", target, cycle));
+                }
+            }
+            keyword if ASSERTIONS.contains(&keyword) || ANNOTATIONS.contains(&keyword) => {}
+            keyword => return fail(keyword, format!("unsupported keyword '{}'", keyword)),
+        }
+    }
+    Ok(())
+}
+
+/// Resolve a `$ref` to a location within the schema itself, e.g. `#/$defs/point`
+fn resolve_ref<'a>(root: &'a JsonValue, reference: &str) -> Option<&'a JsonValue> {
+    root.pointer(reference.strip_prefix('#')?)
+}
+
+/// Look for a loop of `$ref`s and in-place applicators starting at the
+/// subschema `pointer`
+///
+/// `validate` applies `$ref`, `allOf`, `anyOf`, `oneOf` and `not` to the same
+/// value, so arriving back at a subschema on `stack` through them alone
+/// would never end. Returns the pointer of the subschema that repeats.
+fn find_ref_cycle(
+    root: &JsonValue,
+    pointer: &str,
+    stack: &mut Vec<String>,
+    done: &mut HashSet<String>,
+) -> Option<String> {
+    if stack.iter().any(|seen| seen == pointer) {
+        return Some(pointer.to_string());
+    }
+    if done.contains(pointer) {
+        return None;
+    }
+    let schema = root.pointer(pointer)?.as_object()?;
+
+    let mut next = Vec::new();
+    if let Some(target) = schema.get("$ref").and_then(|r| r.as_str()).and_then(|r| r.strip_prefix('#')) {
+        next.push(target.to_string());
+    }
+    for keyword in ["allOf", "anyOf", "oneOf"] {
+        let count = schema.get(keyword).and_then(|s| s.as_array()).map_or(0, Vec::len);
+        next.extend((0..count).map(|index| format!("{}/{}/{}", pointer, keyword, index)));
+    }
+    if schema.contains_key("not") {
+        next.push(format!("{}/not", pointer));
+    }
+
+    stack.push(pointer.to_string());
+    let cycle = next.iter().find_map(|target| find_ref_cycle(root, target, stack, done));
+    stack.pop();
+    done.insert(pointer.to_string());
+    cycle
+}
+
+/// Compile a `pattern`, reusing the regex from earlier calls
+///
+/// Patterns come from registered schemas, so there are only a few of them
+/// but each is matched on every call of its tool.
+fn compile_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    static PATTERNS: OnceLock<RwLock<HashMap<String, Regex>>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| RwLock::new(HashMap::new()));
+
+    if let Some(regex) = patterns.read().unwrap().get(pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = Regex::new(pattern)?;
+    patterns.write().unwrap().insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+/// Fill in the declared `default` of missing object properties, recursively
+pub fn apply_defaults(schema: &JsonValue, value: &mut JsonValue) {
+    let (Some(properties), Some(object)) = (
+        schema.get("properties").and_then(|p| p.as_object()),
+        value.as_object_mut(),
+    ) else {
+        return;
+    };
+
+    for (name, property) in properties {
+        match object.get_mut(name) {
+            Some(existing) => apply_defaults(property, existing),
+            None => {
+                if let Some(default) = property.get("default") {
+                    object.insert(name.clone(), default.clone());
+                }
+            }
+        }
+    }
+}
+
+fn validate_at(root: &JsonValue, schema: &JsonValue, value: &JsonValue, path: &str) -> Result<(), SchemaError> {
+    let fail = |message: String| Err(SchemaError { path: path.to_string(), message });
+
+    let schema = match schema {
+        JsonValue::Bool(true) => return Ok(()),
+        JsonValue::Bool(false) => return fail("no value is allowed here".to_string()),
+        JsonValue::Object(schema) => schema,
+        _ => return Ok(()),
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            JsonValue::String(t) => vec![t.as_str()],
+            JsonValue::Array(types) => types.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| has_type(value, t)) {
+            return fail(format!("expected {}, got {}", allowed.join(" or "), type_name(value)));
+        }
+    }
+
+    if let Some(JsonValue::Array(options)) = schema.get("enum") {
+        if !options.iter().any(|option| json_equal(option, value)) {
+            return fail(format!("must be one of {}", JsonValue::Array(options.clone())));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if !json_equal(value, expected) {
+            return fail(format!("must be {}", expected));
+        }
+    }
+
+    if let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) {
+        match resolve_ref(root, reference) {
+            Some(target) => validate_at(root, target, value, path)?,
+            None => return fail(format!("cannot resolve $ref '{}'", reference)),
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(|m| m.as_f64()) {
+            if number < minimum {
+                return fail(format!("must be at least {}", minimum));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(|m| m.as_f64()) {
+            if number > maximum {
+                return fail(format!("must be at most {}", maximum));
+            }
+        }
+        if let Some(minimum) = schema.get("exclusiveMinimum").and_then(|m| m.as_f64()) {
+            if number <= minimum {
+                return fail(format!("must be greater than {}", minimum));
+            }
+        }
+        if let Some(maximum) = schema.get("exclusiveMaximum").and_then(|m| m.as_f64()) {
+            if number >= maximum {
+                return fail(format!("must be less than {}", maximum));
+            }
+        }
+        if let Some(divisor) = schema.get("multipleOf").and_then(|m| m.as_f64()) {
+            let quotient = number / divisor;
+            if divisor > 0.0 && (quotient - quotient.round()).abs() > 1e-9 * quotient.abs().max(1.0) {
+                return fail(format!("must be a multiple of {}", divisor));
+            }
+        }
+    }
+
+    if let Some(text) = value.as_str() {
+        let length = text.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(|m| m.as_u64()) {
+            if length < min {
+                return fail(format!("must be at least {} characters", min));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(|m| m.as_u64()) {
+            if length > max {
+                return fail(format!("must be at most {} characters", max));
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(|p| p.as_str()) {
+            match compile_pattern(pattern) {
+                Ok(regex) if regex.is_match(text) => {}
+                Ok(_) => return fail(format!("must match the pattern {}", pattern)),
+                Err(e) => return fail(format!("invalid pattern {}: {}", pattern, e)),
+            }
+        }
+        if let Some(format) = schema.get("format").and_then(|f| f.as_str()) {
+            if !has_format(text, format) {
+                return fail(format!("must be a valid {}", format));
+            }
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        if let Some(JsonValue::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(|r| r.as_str()) {
+                if !object.contains_key(name) {
+                    return fail(format!("missing required property '{}'", name));
+                }
+            }
+        }
+
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        let mut patterns = Vec::new();
+        for (pattern, property_schema) in schema.get("patternProperties").and_then(|p| p.as_object()).into_iter().flatten() {
+            match compile_pattern(pattern) {
+                Ok(regex) => patterns.push((regex, property_schema)),
+                Err(e) => return fail(format!("invalid pattern {}: {}", pattern, e)),
+            }
+        }
+
+        for (name, property) in object {
+            let property_path = format!("{}/{}", path, escape_pointer(name));
+            let declared = properties.and_then(|p| p.get(name));
+            if let Some(property_schema) = declared {
+                validate_at(root, property_schema, property, &property_path)?;
+            }
+            let mut matched = declared.is_some();
+            for (regex, property_schema) in &patterns {
+                if regex.is_match(name) {
+                    validate_at(root, property_schema, property, &property_path)?;
+                    matched = true;
+                }
+            }
+            if matched {
+                continue;
+            }
+            match schema.get("additionalProperties") {
+                Some(JsonValue::Bool(false)) => {
+                    return Err(SchemaError {
+                        path: property_path,
+                        message: "unexpected property".to_string(),
+                    })
+                }
+                Some(additional) => validate_at(root, additional, property, &property_path)?,
+                None => {}
+            }
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        if let Some(min) = schema.get("minItems").and_then(|m| m.as_u64()) {
+            if (items.len() as u64) < min {
+                return fail(format!("must have at least {} items", min));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(|m| m.as_u64()) {
+            if (items.len() as u64) > max {
+                return fail(format!("must have at most {} items", max));
+            }
+        }
+        if schema.get("uniqueItems") == Some(&JsonValue::Bool(true)) {
+            for (index, item) in items.iter().enumerate() {
+                if items[..index].iter().any(|earlier| json_equal(earlier, item)) {
+                    return Err(SchemaError {
+                        path: format!("{}/{}", path, index),
+                        message: "duplicates an earlier item".to_string(),
+                    });
+                }
+            }
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for (index, item) in items.iter().enumerate() {
+                validate_at(root, item_schema, item, &format!("{}/{}", path, index))?;
+            }
+        }
+    }
+
+    if let Some(JsonValue::Array(schemas)) = schema.get("allOf") {
+        for sub in schemas {
+            validate_at(root, sub, value, path)?;
+        }
+    }
+    if let Some(JsonValue::Array(schemas)) = schema.get("anyOf") {
+        if !schemas.iter().any(|sub| validate_at(root, sub, value, path).is_ok()) {
+            return fail("does not match any of the allowed schemas".to_string());
+        }
+    }
+    if let Some(JsonValue::Array(schemas)) = schema.get("oneOf") {
+        let matches = schemas.iter().filter(|sub| validate_at(root, sub, value, path).is_ok()).count();
+        if matches != 1 {
+            return fail(format!("must match exactly one schema, matched {}", matches));
+        }
+    }
+    if let Some(forbidden) = schema.get("not") {
+        if validate_at(root, forbidden, value, path).is_ok() {
+            return fail("must not match the schema it excludes".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+fn has_type(value: &JsonValue, expected: &str) -> bool {
+    match expected {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        _ => true,
+    }
+}
+
+fn has_format(text: &str, format: &str) -> bool {
+    match format {
+        "date-time" => chrono::DateTime::parse_from_rfc3339(text).is_ok(),
+        "date" => chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d").is_ok(),
+        "time" => chrono::DateTime::parse_from_rfc3339(&format!("1970-01-01T{}", text)).is_ok(),
+        "email" => text
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.') && !domain.contains('@')),
+        "uri" => text.split_once(':').is_some_and(|(scheme, _)| {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        }),
+        "uuid" => uuid::Uuid::parse_str(text).is_ok(),
+        "ipv4" => text.parse::<std::net::Ipv4Addr>().is_ok(),
+        "ipv6" => text.parse::<std::net::Ipv6Addr>().is_ok(),
+        _ => true,
+    }
+}
+
+/// Compare values the way JSON Schema does, where `1` and `1.0` are equal
+fn json_equal(a: &JsonValue, b: &JsonValue) -> bool {
+    match (a, b) {
+        (JsonValue::Number(x), JsonValue::Number(y)) => x.as_f64() == y.as_f64(),
+        (JsonValue::Array(x), JsonValue::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| json_equal(x, y))
+        }
+        (JsonValue::Object(x), JsonValue::Object(y)) => {
+            x.len() == y.len() && x.iter().all(|(key, x)| y.get(key).is_some_and(|y| json_equal(x, y)))
+        }
+        _ => a == b,
+    }
+}
+
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chart_schema() -> JsonValue {
+        json!({
+            "type": "object",
+            "properties": {
+                "title": {"type": "string", "minLength": 1},
+                "points": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {"x": {"type": "number"}, "y": {"type": "number"}},
+                        "required": ["x", "y"]
+                    }
+                },
+                "kind": {"enum": ["bar", "line"]}
+            },
+            "required": ["title"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn test_valid_value() {
+        let value = json!({"title": "Sales", "points": [{"x": 1, "y": 2.5}], "kind": "bar"});
+        assert!(validate(&chart_schema(), &value).is_ok());
+    }
+
+    #[test]
+    fn test_missing_required_property() {
+        let err = validate(&chart_schema(), &json!({"points": []})).unwrap_err();
+        assert_eq!(err.path, "");
+        assert!(err.message.contains("title"));
+    }
+
+    #[test]
+    fn test_nested_type_mismatch_reports_path() {
+        let value = json!({"title": "Sales", "points": [{"x": 1, "y": 2}, {"x": "2", "y": 3}]});
+        let err = validate(&chart_schema(), &value).unwrap_err();
+        assert_eq!(err.path, "/points/1/x");
+        assert_eq!(err.message, "expected number, got string");
+    }
+
+    #[test]
+    fn test_additional_properties_rejected() {
+        let err = validate(&chart_schema(), &json!({"title": "Sales", "colour": "red"})).unwrap_err();
+        assert_eq!(err.path, "/colour");
+    }
+
+    #[test]
+    fn test_enum_and_bounds() {
+        assert!(validate(&chart_schema(), &json!({"title": "Sales", "kind": "pie"})).is_err());
+        assert!(validate(&chart_schema(), &json!({"title": ""})).is_err());
+        assert!(validate(&json!({"type": "integer", "minimum": 1, "maximum": 10}), &json!(11)).is_err());
+        assert!(validate(&json!({"type": "integer"}), &json!(1.5)).is_err());
+        assert!(validate(&json!({"type": "integer"}), &json!(3)).is_ok());
+
+        // Numbers compare by value, as in uniqueItems
+        assert!(validate(&json!({"enum": [1, "one"]}), &json!(1.0)).is_ok());
+        assert!(validate(&json!({"const": {"n": 2.0}}), &json!({"n": 2})).is_ok());
+        assert!(validate(&json!({"const": 2}), &json!(2.5)).is_err());
+    }
+
+    #[test]
+    fn test_apply_defaults() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "limit": {"type": "integer", "default": 10},
+                "filter": {
+                    "type": "object",
+                    "properties": {"archived": {"type": "boolean", "default": false}}
+                }
+            }
+        });
+        let mut value = json!({"filter": {}});
+
+        apply_defaults(&schema, &mut value);
+        assert_eq!(value, json!({"limit": 10, "filter": {"archived": false}}));
+    }
+
+    #[test]
+    fn test_exclusive_bounds_and_multiples() {
+        let schema = json!({"type": "number", "exclusiveMinimum": 0, "exclusiveMaximum": 1});
+        assert!(validate(&schema, &json!(0)).is_err());
+        assert!(validate(&schema, &json!(0.5)).is_ok());
+        assert!(validate(&schema, &json!(1)).is_err());
+
+        let schema = json!({"multipleOf": 0.1});
+        assert!(validate(&schema, &json!(0.3)).is_ok());
+        assert!(validate(&schema, &json!(0.35)).is_err());
+    }
+
+    #[test]
+    fn test_patterns_and_formats() {
+        let schema = json!({"type": "string", "pattern": "^[a-z]+-[0-9]+$"});
+        assert!(validate(&schema, &json!("issue-42")).is_ok());
+        assert!(validate(&schema, &json!("Issue 42")).is_err());
+
+        let schema = json!({
+            "type": "object",
+            "patternProperties": {"^x-": {"type": "string"}},
+            "additionalProperties": false
+        });
+        assert!(validate(&schema, &json!({"x-trace": "abc"})).is_ok());
+        assert_eq!(validate(&schema, &json!({"x-trace": 1})).unwrap_err().path, "/x-trace");
+        assert_eq!(validate(&schema, &json!({"trace": "abc"})).unwrap_err().path, "/trace");
+
+        for (format, valid, invalid) in [
+            ("date-time", "2024-05-01T12:00:00Z", "2024-05-01"),
+            ("date", "2024-05-01", "01/05/2024"),
+            ("email", "ada@example.com", "ada"),
+            ("uri", "https://example.com/a", "example.com/a"),
+            ("uuid", "67e55044-10b1-426f-9247-bb680e5fe0c8", "67e55044"),
+            ("ipv4", "10.0.0.1", "10.0.0.256"),
+        ] {
+            let schema = json!({"type": "string", "format": format});
+            assert!(validate(&schema, &json!(valid)).is_ok(), "{}", valid);
+            assert!(validate(&schema, &json!(invalid)).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_unique_items_not_and_refs() {
+        let schema = json!({"type": "array", "uniqueItems": true});
+        assert!(validate(&schema, &json!([1, 2, 3])).is_ok());
+        assert_eq!(validate(&schema, &json!([1, 2, 1.0])).unwrap_err().path, "/2");
+
+        let schema = json!({"not": {"type": "null"}});
+        assert!(validate(&schema, &json!(null)).is_err());
+        assert!(validate(&schema, &json!(0)).is_ok());
+
+        let schema = json!({
+            "$defs": {"point": {"type": "object", "required": ["x", "y"]}},
+            "type": "array",
+            "items": {"$ref": "#/$defs/point"}
+        });
+        assert!(validate(&schema, &json!([{"x": 1, "y": 2}])).is_ok());
+        assert_eq!(validate(&schema, &json!([{"x": 1}])).unwrap_err().path, "/0");
+    }
+
+    #[test]
+    fn test_check_rejects_unsupported_schemas() {
+        assert!(check(&chart_schema()).is_ok());
+        assert!(check(&json!({"$defs": {"id": {"format": "uuid"}}, "$ref": "#/$defs/id"})).is_ok());
+
+        let err = check(&json!({"properties": {"tags": {"contains": {"const": "x"}}}})).unwrap_err();
+        assert_eq!(err.path, "/properties/tags/contains");
+        assert!(check(&json!({"format": "credit-card"})).is_err());
+        assert!(check(&json!({"type": ["string", "null"]})).is_ok());
+        assert_eq!(check(&json!({"properties": {"n": {"type": "integr"}}})).unwrap_err().path, "/properties/n/type");
+        assert!(check(&json!({"type": 5})).is_err());
+        assert!(check(&json!({"pattern": "("})).is_err());
+        assert!(check(&json!({"$ref": "https://example.com/schema.json"})).is_err());
+        assert!(check(&json!({"$ref": "#/$defs/missing"})).is_err());
+    }
+
+    #[test]
+    fn test_check_rejects_ref_cycles() {
+        assert!(check(&json!({"$ref": "#"})).is_err());
+        let err = check(&json!({
+            "$defs": {"a": {"$ref": "#/$defs/b"}, "b": {"anyOf": [{"$ref": "#/$defs/a"}]}},
+            "$ref": "#/$defs/a"
+        }))
+        .unwrap_err();
+        assert!(err.message.contains("without descending"));
+
+        // Recursion through a property consumes the value, so it ends
+        let tree = json!({
+            "type": "object",
+            "properties": {"children": {"type": "array", "items": {"$ref": "#"}}}
+        });
+        assert!(check(&tree).is_ok());
+        assert!(validate(&tree, &json!({"children": [{"children": []}]})).is_ok());
+        assert!(validate(&tree, &json!({"children": [{"children": 1}]})).is_err());
+    }
+
+    #[test]
+    fn test_combinators() {
+        let schema = json!({"anyOf": [{"type": "string"}, {"type": "null"}]});
+        assert!(validate(&schema, &json!(null)).is_ok());
+        assert!(validate(&schema, &json!(1)).is_err());
+
+        let schema = json!({"oneOf": [{"type": "number"}, {"type": "integer"}]});
+        assert!(validate(&schema, &json!(1)).is_err());
+        assert!(validate(&schema, &json!(1.5)).is_ok());
+    }
+}
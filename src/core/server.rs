@@ -1,46 +1,434 @@
 use anyhow::{Result, anyhow};
+use futures::FutureExt;
+use std::any::Any;
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio_util::task::TaskTracker;
+use tracing::{error, info, warn};
 
-use crate::resources::Resource;
+use crate::resources::{Resource, ResourceTemplate};
+use crate::resources::template::UriTemplate;
 use crate::tools::Tool;
 use crate::prompts::Prompt;
-use crate::core::types::{ResourceMetadata, ToolResult, JsonValue};
-use crate::tools::ToolMetadata;
+use crate::core::context::RequestContext;
+use crate::core::error::McpError;
+use crate::core::cache::ToolCache;
+use crate::core::limits::{ToolLimiters, ToolLimits};
+use crate::core::middleware::{self, Call, CallResult, CallerIdentity, Middleware};
+use crate::core::pagination::{paginate, Page, DEFAULT_PAGE_SIZE};
+use crate::core::registry::Registry;
+use crate::core::schema::{self, SchemaError};
+use crate::core::protocol::{
+    methods, Completion, CompletionReference, CompletionsCapability, LoggingCapability, PromptsCapability,
+    ResourcesCapability, ServerCapabilities, ToolsCapability,
+};
+use crate::core::notifications::{McpNotification, NotificationBroadcaster};
+use crate::core::session::{Session, SessionManager};
+use crate::core::shutdown::{shutdown_signal, DEFAULT_SHUTDOWN_GRACE};
+use crate::core::types::{PromptMetadata, ResourceMetadata, ResourceTemplateMetadata, ToolResult, JsonValue};
+use crate::tools::{InvalidArguments, ToolMetadata};
+
+/// A resource template with its URI template parsed at registration
+#[derive(Clone)]
+struct TemplateMetadata {
+    metadata: ResourceTemplateMetadata,
+    uri_template: UriTemplate,
+}
 
 /// Main MCP Server structure
+///
+/// Components are indexed by tool/prompt name and resource URI. Their
+/// metadata is read once at registration, so lookups never call into them.
+/// Registering a second component under a taken name is an error; use the
+/// `replace_*` methods to override one. Components can be registered,
+/// replaced, removed, enabled and disabled while clients are connected;
+/// every change is announced to the sessions with a `list_changed`
+/// notification. Disabled components stay registered but are hidden from
+/// clients.
 pub struct McpServer {
-    resources: Arc<RwLock<Vec<Box<dyn Resource>>>>,
-    tools: Arc<RwLock<Vec<Box<dyn Tool>>>>,
-    prompts: Arc<RwLock<Vec<Box<dyn Prompt>>>>,
+    resources: Arc<RwLock<Registry<dyn Resource, ResourceMetadata>>>,
+    resource_templates: Arc<RwLock<Registry<dyn ResourceTemplate, TemplateMetadata>>>,
+    tools: Arc<RwLock<Registry<dyn Tool, ToolMetadata>>>,
+    prompts: Arc<RwLock<Registry<dyn Prompt, PromptMetadata>>>,
+    sessions: Arc<SessionManager>,
+    notifications: Arc<NotificationBroadcaster>,
+    /// Maximum number of items per page of the list methods
+    page_size: usize,
+    /// Timeouts and concurrency limits of tool calls
+    tool_limits: RwLock<ToolLimiters>,
+    /// Hooks around tool calls, resource reads and prompt requests
+    middleware: RwLock<Vec<Arc<dyn Middleware>>>,
+    /// Results of cacheable tools, if caching is on
+    tool_cache: RwLock<Option<Arc<ToolCache>>>,
+    /// Users keyed by the API keys clients authenticate with
+    api_keys: RwLock<HashMap<String, String>>,
+    /// Tool calls still running; closed once shutdown starts
+    in_flight: TaskTracker,
 }
 
 impl McpServer {
     /// Create a new MCP server instance
     pub fn new() -> Self {
+        Self::with_page_size(DEFAULT_PAGE_SIZE)
+    }
+
+    /// Create a new MCP server whose list methods return pages of `page_size` items
+    pub fn with_page_size(page_size: usize) -> Self {
+        let sessions = Arc::new(SessionManager::new());
+
         Self {
-            resources: Arc::new(RwLock::new(Vec::new())),
-            tools: Arc::new(RwLock::new(Vec::new())),
-            prompts: Arc::new(RwLock::new(Vec::new())),
+            resources: Arc::new(RwLock::new(Registry::new())),
+            resource_templates: Arc::new(RwLock::new(Registry::new())),
+            tools: Arc::new(RwLock::new(Registry::new())),
+            prompts: Arc::new(RwLock::new(Registry::new())),
+            sessions: Arc::clone(&sessions),
+            notifications: Arc::new(NotificationBroadcaster::new(sessions)),
+            page_size: page_size.max(1),
+            tool_limits: RwLock::new(ToolLimiters::new()),
+            middleware: RwLock::new(Vec::new()),
+            tool_cache: RwLock::new(None),
+            api_keys: RwLock::new(HashMap::new()),
+            in_flight: TaskTracker::new(),
+        }
+    }
+
+    /// Get the maximum number of items per page of the list methods
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Set the limits of tools that have none of their own
+    pub async fn set_default_tool_limits(&self, limits: ToolLimits) {
+        self.tool_limits.write().await.set_defaults(limits);
+    }
+
+    /// Set the limits of a tool; unset fields fall back to the defaults
+    pub async fn set_tool_limits(&self, name: &str, limits: ToolLimits) {
+        self.tool_limits.write().await.set(name, limits);
+    }
+
+    /// Get the limits in force for a tool
+    pub async fn tool_limits(&self, name: &str) -> ToolLimits {
+        self.tool_limits.read().await.limits(name)
+    }
+
+    /// Turn on caching of tool results
+    ///
+    /// The cache runs as the last middleware in the chain, so every other
+    /// middleware sees calls it answers. Replaces any previous cache,
+    /// dropping its results.
+    pub async fn set_tool_cache(&self, cache: ToolCache) {
+        *self.tool_cache.write().await = Some(Arc::new(cache));
+    }
+
+    /// Get the tool result cache, e.g. to invalidate results
+    pub async fn tool_cache(&self) -> Option<Arc<ToolCache>> {
+        self.tool_cache.read().await.clone()
+    }
+
+    /// Drop the cached results of a tool whose implementation went away
+    async fn invalidate_cached_tool(&self, name: &str) {
+        if let Some(cache) = self.tool_cache.read().await.as_ref() {
+            cache.invalidate_tool(name);
+        }
+    }
+
+    /// Add a middleware to the end of the chain
+    ///
+    /// Its `before` hook runs after those of the middleware added earlier.
+    pub async fn add_middleware(&self, middleware: Box<dyn Middleware>) {
+        self.middleware.write().await.push(Arc::from(middleware));
+    }
+
+    /// Let clients authenticate as `user` with `key`
+    ///
+    /// Once any key is added, transports refuse clients that present no key
+    /// or one that is not known.
+    pub async fn add_api_key(&self, key: impl Into<String>, user: impl Into<String>) {
+        self.api_keys.write().await.insert(key.into(), user.into());
+    }
+
+    /// Check if clients can authenticate with API keys
+    pub async fn has_api_keys(&self) -> bool {
+        !self.api_keys.read().await.is_empty()
+    }
+
+    /// Get the user an API key belongs to
+    pub async fn user_for_api_key(&self, key: &str) -> Option<String> {
+        self.api_keys.read().await.get(key).cloned()
+    }
+
+    /// Run a call through the middleware chain, handling it with `handle`
+    async fn intercept<F, Fut>(
+        &self,
+        method: &'static str,
+        target: &str,
+        arguments: JsonValue,
+        ctx: &RequestContext,
+        handle: F,
+    ) -> Result<CallResult, McpError>
+    where
+        F: FnOnce(String, JsonValue) -> Fut,
+        Fut: std::future::Future<Output = Result<CallResult, McpError>>,
+    {
+        let mut chain = self.middleware.read().await.clone();
+        if let Some(cache) = self.tool_cache().await {
+            chain.push(cache);
+        }
+        if chain.is_empty() {
+            return handle(target.to_string(), arguments).await;
         }
+
+        let tool_of = |target: String| self.tool_of(method, target);
+        let call = Call {
+            method,
+            target: target.to_string(),
+            arguments,
+            caller: CallerIdentity::from_context(ctx).await,
+            tool: tool_of(target.to_string()).await,
+            meta: ctx.meta_object().cloned(),
+        };
+        middleware::run_chain(&chain, call, tool_of, handle).await
+    }
+
+    /// Get the metadata of the tool a call targets
+    async fn tool_of(&self, method: &str, target: String) -> Option<ToolMetadata> {
+        if method != methods::CALL_TOOL {
+            return None;
+        }
+        self.tools
+            .read()
+            .await
+            .get_with_metadata(&target)
+            .map(|(_, metadata)| metadata)
+    }
+
+    /// Get the client sessions shared by all transports
+    pub fn sessions(&self) -> Arc<SessionManager> {
+        Arc::clone(&self.sessions)
+    }
+
+    /// Get the broadcaster that pushes notifications to client sessions
+    pub fn notifications(&self) -> Arc<NotificationBroadcaster> {
+        Arc::clone(&self.notifications)
     }
 
     /// Register a resource
-    pub async fn register_resource(&self, resource: Box<dyn Resource>) {
-        let mut resources = self.resources.write().await;
-        resources.push(resource);
+    ///
+    /// Fails if a resource with the same URI is already registered.
+    pub async fn register_resource(&self, resource: Box<dyn Resource>) -> Result<()> {
+        let uri = resource.uri().await;
+        let metadata = resource.metadata().await?;
+
+        if !self.resources.write().await.insert(uri.clone(), Arc::from(resource), metadata) {
+            return Err(anyhow!("Resource already registered: {}", uri));
+        }
+
+        self.notifications.broadcast(McpNotification::ResourceListChanged).await;
+        Ok(())
+    }
+
+    /// Register a resource, replacing any registered resource with the same URI
+    ///
+    /// Returns true if a resource was replaced. The replacement keeps the
+    /// enabled state of the resource it replaces.
+    pub async fn replace_resource(&self, resource: Box<dyn Resource>) -> Result<bool> {
+        let uri = resource.uri().await;
+        let metadata = resource.metadata().await?;
+
+        let replaced = self.resources.write().await.replace(uri, Arc::from(resource), metadata);
+
+        self.notifications.broadcast(McpNotification::ResourceListChanged).await;
+        Ok(replaced)
+    }
+
+    /// Remove a resource by URI
+    ///
+    /// Returns false if no resource with that URI is registered.
+    pub async fn unregister_resource(&self, uri: &str) -> bool {
+        if !self.resources.write().await.remove(uri) {
+            return false;
+        }
+
+        self.notifications.broadcast(McpNotification::ResourceListChanged).await;
+        true
+    }
+
+    /// Expose or hide a resource
+    ///
+    /// Returns false if no resource with that URI is registered.
+    pub async fn set_resource_enabled(&self, uri: &str, enabled: bool) -> bool {
+        let changed = self.resources.write().await.set_enabled(uri, enabled);
+        self.announce_change(changed, McpNotification::ResourceListChanged).await
+    }
+
+    /// Register a resource template
+    ///
+    /// Fails if the URI template is invalid or already registered.
+    pub async fn register_resource_template(&self, template: Box<dyn ResourceTemplate>) -> Result<()> {
+        let metadata = template_metadata(template.as_ref()).await?;
+        let key = metadata.metadata.uri_template.clone();
+
+        if !self.resource_templates.write().await.insert(key.clone(), Arc::from(template), metadata) {
+            return Err(anyhow!("Resource template already registered: {}", key));
+        }
+
+        self.notifications.broadcast(McpNotification::ResourceListChanged).await;
+        Ok(())
+    }
+
+    /// Register a resource template, replacing any template with the same URI template
+    ///
+    /// Returns true if a template was replaced.
+    pub async fn replace_resource_template(&self, template: Box<dyn ResourceTemplate>) -> Result<bool> {
+        let metadata = template_metadata(template.as_ref()).await?;
+        let key = metadata.metadata.uri_template.clone();
+
+        let replaced = self.resource_templates.write().await.replace(key, Arc::from(template), metadata);
+
+        self.notifications.broadcast(McpNotification::ResourceListChanged).await;
+        Ok(replaced)
+    }
+
+    /// Remove a resource template by its URI template
+    ///
+    /// Returns false if no template with that URI template is registered.
+    pub async fn unregister_resource_template(&self, uri_template: &str) -> bool {
+        if !self.resource_templates.write().await.remove(uri_template) {
+            return false;
+        }
+
+        self.notifications.broadcast(McpNotification::ResourceListChanged).await;
+        true
+    }
+
+    /// Expose or hide a resource template
+    ///
+    /// Returns false if no template with that URI template is registered.
+    pub async fn set_resource_template_enabled(&self, uri_template: &str, enabled: bool) -> bool {
+        let changed = self.resource_templates.write().await.set_enabled(uri_template, enabled);
+        self.announce_change(changed, McpNotification::ResourceListChanged).await
     }
 
     /// Register a tool
-    pub async fn register_tool(&self, tool: Box<dyn Tool>) {
-        let mut tools = self.tools.write().await;
-        tools.push(tool);
+    ///
+    /// Fails if a tool with the same name is already registered, or if its
+    /// schemas use JSON Schema features that arguments can't be checked against.
+    pub async fn register_tool(&self, tool: Box<dyn Tool>) -> Result<()> {
+        let metadata = tool.metadata().await?;
+        check_tool_schemas(&metadata)?;
+        let name = metadata.name.clone();
+
+        if !self.tools.write().await.insert(name.clone(), Arc::from(tool), metadata) {
+            return Err(anyhow!("Tool already registered: {}", name));
+        }
+
+        self.notifications.broadcast(McpNotification::ToolListChanged).await;
+        Ok(())
+    }
+
+    /// Register a tool, replacing any registered tool with the same name
+    ///
+    /// Returns true if a tool was replaced. The replacement keeps the enabled
+    /// state of the tool it replaces. Calls already running on the old tool
+    /// finish on it.
+    pub async fn replace_tool(&self, tool: Box<dyn Tool>) -> Result<bool> {
+        let metadata = tool.metadata().await?;
+        check_tool_schemas(&metadata)?;
+        let name = metadata.name.clone();
+
+        let replaced = self.tools.write().await.replace(name.clone(), Arc::from(tool), metadata);
+        self.invalidate_cached_tool(&name).await;
+
+        self.notifications.broadcast(McpNotification::ToolListChanged).await;
+        Ok(replaced)
+    }
+
+    /// Remove a tool by name
+    ///
+    /// Returns false if no tool with that name is registered.
+    pub async fn unregister_tool(&self, name: &str) -> bool {
+        if !self.tools.write().await.remove(name) {
+            return false;
+        }
+        self.invalidate_cached_tool(name).await;
+
+        self.notifications.broadcast(McpNotification::ToolListChanged).await;
+        true
+    }
+
+    /// Expose or hide a tool
+    ///
+    /// Returns false if no tool with that name is registered.
+    pub async fn set_tool_enabled(&self, name: &str, enabled: bool) -> bool {
+        let changed = self.tools.write().await.set_enabled(name, enabled);
+        self.announce_change(changed, McpNotification::ToolListChanged).await
     }
 
     /// Register a prompt
-    pub async fn register_prompt(&self, prompt: Box<dyn Prompt>) {
-        let mut prompts = self.prompts.write().await;
-        prompts.push(prompt);
+    ///
+    /// Fails if a prompt with the same name is already registered.
+    pub async fn register_prompt(&self, prompt: Box<dyn Prompt>) -> Result<()> {
+        let metadata = prompt.metadata().await?;
+        let name = metadata.name.clone();
+
+        if !self.prompts.write().await.insert(name.clone(), Arc::from(prompt), metadata) {
+            return Err(anyhow!("Prompt already registered: {}", name));
+        }
+
+        self.notifications.broadcast(McpNotification::PromptListChanged).await;
+        Ok(())
+    }
+
+    /// Register a prompt, replacing any registered prompt with the same name
+    ///
+    /// Returns true if a prompt was replaced. The replacement keeps the
+    /// enabled state of the prompt it replaces.
+    pub async fn replace_prompt(&self, prompt: Box<dyn Prompt>) -> Result<bool> {
+        let metadata = prompt.metadata().await?;
+        let name = metadata.name.clone();
+
+        let replaced = self.prompts.write().await.replace(name, Arc::from(prompt), metadata);
+
+        self.notifications.broadcast(McpNotification::PromptListChanged).await;
+        Ok(replaced)
+    }
+
+    /// Remove a prompt by name
+    ///
+    /// Returns false if no prompt with that name is registered.
+    pub async fn unregister_prompt(&self, name: &str) -> bool {
+        if !self.prompts.write().await.remove(name) {
+            return false;
+        }
+
+        self.notifications.broadcast(McpNotification::PromptListChanged).await;
+        true
+    }
+
+    /// Expose or hide a prompt
+    ///
+    /// Returns false if no prompt with that name is registered.
+    pub async fn set_prompt_enabled(&self, name: &str, enabled: bool) -> bool {
+        let changed = self.prompts.write().await.set_enabled(name, enabled);
+        self.announce_change(changed, McpNotification::PromptListChanged).await
+    }
+
+    /// Broadcast a list change if `set_enabled` changed anything
+    ///
+    /// Returns false if the component was not registered.
+    async fn announce_change(&self, changed: Option<bool>, notification: McpNotification) -> bool {
+        match changed {
+            Some(true) => {
+                self.notifications.broadcast(notification).await;
+                true
+            }
+            Some(false) => true,
+            None => false,
+        }
     }
 
     /// Get count of registered resources
@@ -48,6 +436,11 @@ impl McpServer {
         self.resources.read().await.len()
     }
 
+    /// Get count of registered resource templates
+    pub async fn resource_template_count(&self) -> usize {
+        self.resource_templates.read().await.len()
+    }
+
     /// Get count of registered tools
     pub async fn tool_count(&self) -> usize {
         self.tools.read().await.len()
@@ -58,126 +451,400 @@ impl McpServer {
         self.prompts.read().await.len()
     }
 
-    /// List all registered resources
+    /// Capabilities to advertise, derived from what is registered and enabled
+    pub async fn capabilities(&self) -> ServerCapabilities {
+        let has_templates = self.resource_templates.read().await.has_enabled();
+        let has_resources = self.resources.read().await.has_enabled() || has_templates;
+        let resources = has_resources.then_some(ResourcesCapability {
+            subscribe: true,
+            list_changed: true,
+        });
+        let tools = self.tools.read().await.has_enabled().then_some(ToolsCapability {
+            list_changed: true,
+        });
+        let prompts = self.prompts.read().await.has_enabled().then_some(PromptsCapability {
+            list_changed: true,
+        });
+        let completions = (prompts.is_some() || has_templates).then_some(CompletionsCapability {});
+
+        ServerCapabilities {
+            resources,
+            tools,
+            prompts,
+            completions,
+            logging: Some(LoggingCapability {}),
+        }
+    }
+
+    /// List all enabled resources
     pub async fn list_resources(&self) -> Result<Vec<ResourceMetadata>> {
-        let resources = self.resources.read().await;
-        let mut metadatas = Vec::new();
-        
-        for resource in resources.iter() {
-            metadatas.push(resource.metadata().await?);
+        Ok(self.resources.read().await.metadata())
+    }
+
+    /// List one page of enabled resources, ordered by URI
+    pub async fn list_resources_page(&self, cursor: Option<&str>) -> Result<Page<ResourceMetadata>, McpError> {
+        let resources = self.list_resources().await?;
+        Ok(paginate(resources, |r| r.uri.as_str(), cursor, self.page_size)?)
+    }
+
+    /// List all enabled resource templates
+    pub async fn list_resource_templates(&self) -> Result<Vec<ResourceTemplateMetadata>> {
+        let templates = self.resource_templates.read().await;
+        Ok(templates.enabled().map(|(_, t)| t.metadata.clone()).collect())
+    }
+
+    /// List one page of enabled resource templates, ordered by URI template
+    pub async fn list_resource_templates_page(
+        &self,
+        cursor: Option<&str>,
+    ) -> Result<Page<ResourceTemplateMetadata>, McpError> {
+        let templates = self.list_resource_templates().await?;
+        Ok(paginate(templates, |t| t.uri_template.as_str(), cursor, self.page_size)?)
+    }
+
+    /// Check if a URI names an enabled resource or matches an enabled resource template
+    pub async fn has_resource(&self, uri: &str) -> bool {
+        self.resolve_resource(uri).await.is_some()
+    }
+
+    /// Subscribe a session to updates of a resource
+    pub async fn subscribe_resource(&self, session: &Session, uri: &str) -> Result<(), McpError> {
+        if !self.has_resource(uri).await {
+            return Err(McpError::resource_not_found(uri));
         }
-        
-        Ok(metadatas)
+        session.subscribe(uri).await;
+        Ok(())
+    }
+
+    /// Unsubscribe a session from a resource
+    pub async fn unsubscribe_resource(&self, session: &Session, uri: &str) -> bool {
+        session.unsubscribe(uri).await
+    }
+
+    /// Tell subscribed sessions that a resource has changed
+    ///
+    /// Returns the number of sessions notified.
+    pub async fn notify_resource_updated(&self, uri: &str) -> usize {
+        self.notifications
+            .broadcast(McpNotification::ResourceUpdated { uri: uri.to_string() })
+            .await
     }
 
     /// Read a specific resource by URI
-    pub async fn read_resource(&self, uri: &str) -> Result<crate::core::types::ResourceContent> {
-        let resources = self.resources.read().await;
-        
-        for resource in resources.iter() {
-            if resource.uri().await == uri {
-                return resource.read().await;
+    ///
+    /// Fixed resources take precedence; otherwise the URI is matched against
+    /// the resource templates in registration order.
+    pub async fn read_resource(&self, uri: &str) -> Result<crate::core::types::ResourceContent, McpError> {
+        self.read_resolved(uri).await.map(|(content, _)| content)
+    }
+
+    /// Read a resource as the text and binary contents sent to clients
+    ///
+    /// Resources that only return a JSON `content` have it labelled with
+    /// their declared MIME type.
+    pub async fn read_resource_contents(
+        &self,
+        uri: &str,
+    ) -> Result<Vec<crate::core::protocol::ResourceContent>, McpError> {
+        self.read_resource_contents_with_context(uri, &RequestContext::default()).await
+    }
+
+    /// Read a resource on behalf of a client request, through the middleware chain
+    pub async fn read_resource_contents_with_context(
+        &self,
+        uri: &str,
+        ctx: &RequestContext,
+    ) -> Result<Vec<crate::core::protocol::ResourceContent>, McpError> {
+        self.intercept(methods::READ_RESOURCE, uri, JsonValue::Null, ctx, |uri, _| async move {
+            let (content, mime_type) = self.read_resolved(&uri).await?;
+            Ok(CallResult::Resource(content.into_contents(mime_type.as_deref())))
+        })
+        .await?
+        .into_resource()
+    }
+
+    /// Read a resource along with its declared MIME type
+    async fn read_resolved(
+        &self,
+        uri: &str,
+    ) -> Result<(crate::core::types::ResourceContent, Option<String>), McpError> {
+        match self.resolve_resource(uri).await {
+            Some(ResolvedResource::Fixed(resource, mime_type)) => Ok((resource.read().await?, mime_type)),
+            Some(ResolvedResource::Templated(template, variables, mime_type)) => {
+                Ok((template.read(uri, &variables).await?, mime_type))
             }
+            None => Err(McpError::resource_not_found(uri)),
         }
-        
-        Err(anyhow!("Resource not found: {}", uri))
     }
 
-    /// List all registered tools
-    pub async fn list_tools(&self) -> Result<Vec<ToolMetadata>> {
-        let tools = self.tools.read().await;
-        let mut metadatas = Vec::new();
-        
-        for tool in tools.iter() {
-            metadatas.push(tool.metadata().await?);
+    /// Find the enabled resource or resource template serving a URI
+    async fn resolve_resource(&self, uri: &str) -> Option<ResolvedResource> {
+        if let Some((resource, metadata)) = self.resources.read().await.get_with_metadata(uri) {
+            return Some(ResolvedResource::Fixed(resource, metadata.mime_type));
         }
-        
-        Ok(metadatas)
+
+        let templates = self.resource_templates.read().await;
+        let resolved = templates.enabled().find_map(|(template, metadata)| {
+            metadata.uri_template.match_uri(uri).map(|variables| {
+                ResolvedResource::Templated(Arc::clone(template), variables, metadata.metadata.mime_type.clone())
+            })
+        });
+        resolved
+    }
+
+    /// List all enabled tools
+    pub async fn list_tools(&self) -> Result<Vec<ToolMetadata>> {
+        Ok(self.tools.read().await.metadata())
+    }
+
+    /// List one page of enabled tools, ordered by name
+    pub async fn list_tools_page(&self, cursor: Option<&str>) -> Result<Page<ToolMetadata>, McpError> {
+        let tools = self.list_tools().await?;
+        Ok(paginate(tools, |t| t.name.as_str(), cursor, self.page_size)?)
     }
 
     /// Call a specific tool by name
-    pub async fn call_tool(&self, name: &str, params: JsonValue) -> Result<ToolResult> {
-        let tools = self.tools.read().await;
-        
-        for tool in tools.iter() {
-            let metadata = tool.metadata().await?;
-            if metadata.name == name {
-                return tool.execute(params).await;
-            }
+    pub async fn call_tool(&self, name: &str, params: JsonValue) -> Result<ToolResult, McpError> {
+        self.call_tool_with_context(name, params, &RequestContext::default()).await
+    }
+
+    /// Call a tool on behalf of a client request, through the middleware chain
+    ///
+    /// Errors from the tool itself are `McpError::ToolExecution` unless the
+    /// tool returned an `McpError`.
+    pub async fn call_tool_with_context(
+        &self,
+        name: &str,
+        params: JsonValue,
+        ctx: &RequestContext,
+    ) -> Result<ToolResult, McpError> {
+        if self.is_shutting_down() {
+            return Err(McpError::internal("Server is shutting down"));
         }
-        
-        Err(anyhow!("Tool not found: {}", name))
+
+        let call = self.intercept(methods::CALL_TOOL, name, params, ctx, |name, params| async move {
+            self.execute_tool(&name, params, ctx).await.map(CallResult::Tool)
+        });
+        self.in_flight.track_future(call).await?.into_tool()
     }
 
-    /// List all registered prompts
-    pub async fn list_prompts(&self) -> Result<Vec<crate::core::types::PromptMetadata>> {
-        let prompts = self.prompts.read().await;
-        let mut metadatas = Vec::new();
-        
-        for prompt in prompts.iter() {
-            metadatas.push(prompt.metadata().await?);
+    /// Validate a tool's arguments and run it within its limits
+    async fn execute_tool(
+        &self,
+        name: &str,
+        params: JsonValue,
+        ctx: &RequestContext,
+    ) -> Result<ToolResult, McpError> {
+        // Don't hold the registry lock while the tool runs
+        let (tool, metadata) = self
+            .tools
+            .read()
+            .await
+            .get_with_metadata(name)
+            .ok_or_else(|| McpError::invalid_params(format!("Tool not found: {}", name)))?;
+
+        let params = validate_arguments(tool.as_ref(), &metadata, params).await?;
+
+        // Limiters are created once per tool, so most calls only need the read lock
+        let active = self.tool_limits.read().await.active_limiter(name);
+        let limiter = match active {
+            Some(limiter) => limiter,
+            None => self.tool_limits.write().await.limiter(name),
+        };
+        let run = async {
+            let _permit = limiter.acquire().await;
+            // A panicking tool must not take the connection down with it
+            AssertUnwindSafe(tool.execute_with_context(params, ctx))
+                .catch_unwind()
+                .await
+                .map_err(|panic| {
+                    McpError::tool_execution(format!("Tool {} panicked: {}", name, panic_message(panic.as_ref())))
+                })?
+                .map_err(McpError::from_tool_error)
+        };
+        let result = match limiter.limits().timeout {
+            Some(timeout) => tokio::time::timeout(timeout, run).await.map_err(|_| {
+                McpError::tool_execution(format!("Tool {} timed out after {:?}", name, timeout))
+            })??,
+            None => run.await?,
+        };
+        if let Some(schema) = metadata.output_schema.as_ref().filter(|_| result.success) {
+            let structured = result.structured_content.as_ref().ok_or_else(|| {
+                McpError::internal(format!(
+                    "Tool {} declares an output schema but returned no structured content",
+                    name
+                ))
+            })?;
+            schema::validate(schema, structured).map_err(|e| {
+                McpError::internal(format!(
+                    "Tool {} returned structured content that does not match its output schema: {}",
+                    name, e
+                ))
+                .with_data(serde_json::json!({ "path": e.path }))
+            })?;
         }
-        
-        Ok(metadatas)
+        Ok(result)
+    }
+
+    /// List all enabled prompts
+    pub async fn list_prompts(&self) -> Result<Vec<PromptMetadata>> {
+        Ok(self.prompts.read().await.metadata())
+    }
+
+    /// List one page of enabled prompts, ordered by name
+    pub async fn list_prompts_page(&self, cursor: Option<&str>) -> Result<Page<PromptMetadata>, McpError> {
+        let prompts = self.list_prompts().await?;
+        Ok(paginate(prompts, |p| p.name.as_str(), cursor, self.page_size)?)
     }
 
     /// Get a specific prompt by name
-    pub async fn get_prompt(&self, name: &str, args: JsonValue) -> Result<crate::core::types::PromptResult> {
-        let prompts = self.prompts.read().await;
-        
-        for prompt in prompts.iter() {
-            let metadata = prompt.metadata().await?;
-            if metadata.name == name {
-                return prompt.generate(args).await;
-            }
-        }
-        
-        Err(anyhow!("Prompt not found: {}", name))
+    pub async fn get_prompt(&self, name: &str, args: JsonValue) -> Result<crate::core::types::PromptResult, McpError> {
+        self.get_prompt_with_context(name, args, &RequestContext::default()).await
     }
 
-    /// Run the MCP server
-    pub async fn run(&self) -> Result<()> {
-        println!("\nMCP Server running...");
-        println!("Press Ctrl+C to stop\n");
+    /// Get a prompt on behalf of a client request, through the middleware chain
+    pub async fn get_prompt_with_context(
+        &self,
+        name: &str,
+        args: JsonValue,
+        ctx: &RequestContext,
+    ) -> Result<crate::core::types::PromptResult, McpError> {
+        self.intercept(methods::GET_PROMPT, name, args, ctx, |name, args| async move {
+            let prompt = self
+                .prompts
+                .read()
+                .await
+                .get(&name)
+                .ok_or_else(|| McpError::invalid_params(format!("Prompt not found: {}", name)))?;
 
-        // Demo: Execute registered components
-        self.demo_execution().await?;
+            Ok(CallResult::Prompt(prompt.generate(args).await?))
+        })
+        .await?
+        .into_prompt()
+    }
+
+    /// Suggest values for a prompt argument or resource template variable
+    ///
+    /// `context` holds the arguments the client has already filled in. At
+    /// most `MAX_COMPLETION_VALUES` suggestions are returned.
+    pub async fn complete(
+        &self,
+        reference: &CompletionReference,
+        argument: &str,
+        value: &str,
+        context: &HashMap<String, String>,
+    ) -> Result<Completion, McpError> {
+        let values = match reference {
+            CompletionReference::Prompt { name } => {
+                let (prompt, metadata) = self
+                    .prompts
+                    .read()
+                    .await
+                    .get_with_metadata(name)
+                    .ok_or_else(|| McpError::invalid_params(format!("Prompt not found: {}", name)))?;
+                if !metadata.arguments.iter().any(|arg| arg.name == argument) {
+                    return Err(McpError::invalid_params(format!("Prompt {} has no argument {}", name, argument)));
+                }
+                prompt.complete(argument, value, context).await?
+            }
+            CompletionReference::Resource { uri } => {
+                let (template, metadata) = self
+                    .resource_templates
+                    .read()
+                    .await
+                    .get_with_metadata(uri)
+                    .ok_or_else(|| McpError::invalid_params(format!("Resource template not found: {}", uri)))?;
+                if !metadata.uri_template.variables().contains(&argument) {
+                    return Err(McpError::invalid_params(format!("Resource template {} has no variable {}", uri, argument)));
+                }
+                template.complete(argument, value, context).await?
+            }
+        };
+
+        Ok(Completion::from_values(values))
+    }
 
+    /// Serve until SIGINT or SIGTERM, then shut down gracefully
+    ///
+    /// Transports run alongside; this only owns the server's lifecycle.
+    pub async fn run(&self) -> Result<()> {
+        shutdown_signal().await;
+        self.shutdown(DEFAULT_SHUTDOWN_GRACE).await;
         Ok(())
     }
 
-    /// Demo execution of registered components
-    async fn demo_execution(&self) -> Result<()> {
-        println!("=== Demo Execution ===\n");
+    /// Check if the server has started shutting down
+    pub fn is_shutting_down(&self) -> bool {
+        self.in_flight.is_closed()
+    }
 
-        // Execute resources
-        let resources = self.resources.read().await;
-        for resource in resources.iter() {
-            let metadata = resource.metadata().await?;
-            println!("Resource: {}", metadata.name);
-            let content = resource.read().await?;
-            println!("  Content: {:?}\n", content);
+    /// Shut the server down
+    ///
+    /// New tool calls are refused, and calls already running get up to
+    /// `grace` to finish. Then every session is closed, which cancels what is
+    /// still running and ends the sessions' streams, and the `shutdown` hooks
+    /// of all registered components run.
+    pub async fn shutdown(&self, grace: Duration) {
+        self.in_flight.close();
+        if !self.in_flight.is_empty() {
+            info!("Waiting up to {:?} for {} tool calls to finish", grace, self.in_flight.len());
+            if tokio::time::timeout(grace, self.in_flight.wait()).await.is_err() {
+                warn!("Abandoning {} tool calls still running", self.in_flight.len());
+            }
         }
 
-        // Execute tools
-        let tools = self.tools.read().await;
-        for tool in tools.iter() {
-            let metadata = tool.metadata().await?;
-            println!("Tool: {}", metadata.name);
-            let result = tool.execute(serde_json::json!({})).await?;
-            println!("  Result: {:?}\n", result);
-        }
+        let closed = self.sessions.close_all().await;
+        info!("Closed {} sessions", closed);
 
-        // Execute prompts
-        let prompts = self.prompts.read().await;
-        for prompt in prompts.iter() {
-            let metadata = prompt.metadata().await?;
-            println!("Prompt: {}", metadata.name);
-            let result = prompt.generate(serde_json::json!({})).await?;
-            println!("  Result: {:?}\n", result);
+        // Collect the components first so no registry lock is held across the hooks
+        let tools: Vec<_> = self
+            .tools
+            .read()
+            .await
+            .entries()
+            .map(|(key, tool)| (key.to_string(), Arc::clone(tool)))
+            .collect();
+        for (key, tool) in tools {
+            if let Err(e) = tool.shutdown().await {
+                error!("Failed to shut down tool {}: {}", key, e);
+            }
+        }
+        let resources: Vec<_> = self
+            .resources
+            .read()
+            .await
+            .entries()
+            .map(|(key, resource)| (key.to_string(), Arc::clone(resource)))
+            .collect();
+        for (key, resource) in resources {
+            if let Err(e) = resource.shutdown().await {
+                error!("Failed to shut down resource {}: {}", key, e);
+            }
+        }
+        let templates: Vec<_> = self
+            .resource_templates
+            .read()
+            .await
+            .entries()
+            .map(|(key, template)| (key.to_string(), Arc::clone(template)))
+            .collect();
+        for (key, template) in templates {
+            if let Err(e) = template.shutdown().await {
+                error!("Failed to shut down resource template {}: {}", key, e);
+            }
+        }
+        let prompts: Vec<_> = self
+            .prompts
+            .read()
+            .await
+            .entries()
+            .map(|(key, prompt)| (key.to_string(), Arc::clone(prompt)))
+            .collect();
+        for (key, prompt) in prompts {
+            if let Err(e) = prompt.shutdown().await {
+                error!("Failed to shut down prompt {}: {}", key, e);
+            }
         }
-
-        Ok(())
     }
 }
 
@@ -186,3 +853,64 @@ impl Default for McpServer {
         Self::new()
     }
 }
+
+/// A resource or resource template matched by a URI
+enum ResolvedResource {
+    Fixed(Arc<dyn Resource>, Option<String>),
+    Templated(Arc<dyn ResourceTemplate>, HashMap<String, String>, Option<String>),
+}
+
+/// Make sure every keyword of a tool's schemas is enforced
+fn check_tool_schemas(metadata: &ToolMetadata) -> Result<()> {
+    schema::check(&metadata.schema())
+        .map_err(|e| anyhow!("Unsupported input schema for tool {}: {}", metadata.name, e))?;
+    if let Some(ref output_schema) = metadata.output_schema {
+        schema::check(output_schema)
+            .map_err(|e| anyhow!("Unsupported output schema for tool {}: {}", metadata.name, e))?;
+    }
+    Ok(())
+}
+
+/// Check a tool's arguments against its input schema and `Tool::validate`
+///
+/// Returns the arguments with schema defaults filled in.
+async fn validate_arguments(
+    tool: &dyn Tool,
+    metadata: &ToolMetadata,
+    mut params: JsonValue,
+) -> Result<JsonValue, McpError> {
+    let input_schema = metadata.schema();
+    schema::apply_defaults(&input_schema, &mut params);
+
+    let rejected = |error: SchemaError| InvalidArguments {
+        tool: metadata.name.clone(),
+        error,
+    };
+    schema::validate(&input_schema, &params).map_err(rejected)?;
+    if !tool.validate(&params).await.map_err(McpError::from_tool_error)? {
+        return Err(rejected(SchemaError {
+            path: String::new(),
+            message: "rejected by the tool".to_string(),
+        })
+        .into());
+    }
+    Ok(params)
+}
+
+/// Get the message a panic was raised with
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+/// Read a template's metadata and parse its URI template
+async fn template_metadata(template: &dyn ResourceTemplate) -> Result<TemplateMetadata> {
+    let metadata = template.metadata().await?;
+    let uri_template = UriTemplate::parse(&metadata.uri_template)?;
+    Ok(TemplateMetadata { metadata, uri_template })
+}
@@ -0,0 +1,415 @@
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, RwLock};
+use tokio_util::sync::CancellationToken;
+
+use crate::core::protocol::{ClientCapabilities, ClientInfo, JsonRpcResponse, LoggingLevel, Root};
+use crate::core::types::JsonValue;
+
+/// How long a session may go without requests before it is closed
+pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// What the client announced during the `initialize` handshake
+#[derive(Debug, Clone)]
+pub struct ClientDetails {
+    /// The negotiated protocol version
+    pub protocol_version: String,
+    pub capabilities: ClientCapabilities,
+    pub info: ClientInfo,
+}
+
+/// A client session established by the `initialize` handshake
+pub struct Session {
+    id: String,
+    created_at: DateTime<Utc>,
+    /// When the client last sent a message in the session
+    last_activity: RwLock<Instant>,
+    /// Set once the client sends `notifications/initialized`
+    initialized: AtomicBool,
+    /// Recorded from the `initialize` request
+    client: RwLock<Option<ClientDetails>>,
+    /// User the session belongs to, for per-user connectors
+    user: RwLock<Option<String>>,
+    /// Least severe log message sent to the client, set by `logging/setLevel`
+    log_level: RwLock<Option<LoggingLevel>>,
+    /// The roots the client last listed; cleared when it announces a change
+    roots: RwLock<Option<Vec<Root>>>,
+    /// Bumped on every announced change, to spot `roots/list` replies that
+    /// were in flight during one
+    roots_generation: AtomicU64,
+    /// URIs of the resources the client subscribed to
+    subscriptions: RwLock<HashSet<String>>,
+    /// Server-to-client message stream, if the client has one open
+    stream: RwLock<Option<mpsc::UnboundedSender<JsonValue>>>,
+    /// Requests still being handled, keyed by serialized request ID
+    in_flight: RwLock<HashMap<String, CancellationToken>>,
+    /// Requests sent to the client that await its response, keyed by serialized request ID
+    awaiting: RwLock<HashMap<String, oneshot::Sender<JsonRpcResponse>>>,
+    /// Counter for the IDs of requests sent to the client
+    next_request_id: AtomicU64,
+}
+
+impl Session {
+    fn new(id: String) -> Self {
+        Self {
+            id,
+            created_at: Utc::now(),
+            last_activity: RwLock::new(Instant::now()),
+            initialized: AtomicBool::new(false),
+            client: RwLock::new(None),
+            user: RwLock::new(None),
+            log_level: RwLock::new(None),
+            roots: RwLock::new(None),
+            roots_generation: AtomicU64::new(0),
+            subscriptions: RwLock::new(HashSet::new()),
+            stream: RwLock::new(None),
+            in_flight: RwLock::new(HashMap::new()),
+            awaiting: RwLock::new(HashMap::new()),
+            next_request_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Get the session ID
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Get the time the session was created
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// Record that the client just sent a message in the session
+    pub async fn touch(&self) {
+        *self.last_activity.write().await = Instant::now();
+    }
+
+    /// Get how long ago the client last sent a message in the session
+    pub async fn idle_for(&self) -> Duration {
+        self.last_activity.read().await.elapsed()
+    }
+
+    /// Check if the client has completed the initialization handshake
+    pub fn is_initialized(&self) -> bool {
+        self.initialized.load(Ordering::SeqCst)
+    }
+
+    /// Mark the initialization handshake as complete
+    pub fn mark_initialized(&self) {
+        self.initialized.store(true, Ordering::SeqCst);
+    }
+
+    /// Record the client's handshake details
+    pub async fn set_client(&self, details: ClientDetails) {
+        *self.client.write().await = Some(details);
+    }
+
+    /// Get the client's handshake details, if it has sent `initialize`
+    pub async fn client(&self) -> Option<ClientDetails> {
+        self.client.read().await.clone()
+    }
+
+    /// Get the protocol version negotiated with the client
+    pub async fn protocol_version(&self) -> Option<String> {
+        self.client.read().await.as_ref().map(|c| c.protocol_version.clone())
+    }
+
+    /// Get the capabilities the client announced
+    pub async fn client_capabilities(&self) -> ClientCapabilities {
+        self.client
+            .read()
+            .await
+            .as_ref()
+            .map(|c| c.capabilities.clone())
+            .unwrap_or_default()
+    }
+
+    /// Associate the session with a user
+    pub async fn set_user(&self, username: &str) {
+        *self.user.write().await = Some(username.to_string());
+    }
+
+    /// Associate the session with a user unless it belongs to another one
+    ///
+    /// Returns false, leaving the session alone, if it belongs to a
+    /// different user.
+    pub async fn claim_user(&self, username: &str) -> bool {
+        let mut user = self.user.write().await;
+        match user.as_deref() {
+            Some(owner) => owner == username,
+            None => {
+                *user = Some(username.to_string());
+                true
+            }
+        }
+    }
+
+    /// Get the user the session belongs to
+    pub async fn user(&self) -> Option<String> {
+        self.user.read().await.clone()
+    }
+
+    /// Set the least severe log message sent to the client
+    pub async fn set_log_level(&self, level: LoggingLevel) {
+        *self.log_level.write().await = Some(level);
+    }
+
+    /// Get the least severe log message sent to the client
+    ///
+    /// No log messages are sent until the client sets a level.
+    pub async fn log_level(&self) -> Option<LoggingLevel> {
+        *self.log_level.read().await
+    }
+
+    /// Get the current generation of the client's roots, to pass to `set_roots`
+    pub fn roots_generation(&self) -> u64 {
+        self.roots_generation.load(Ordering::SeqCst)
+    }
+
+    /// Remember the roots the client listed in reply to a request made at
+    /// `generation`
+    ///
+    /// A reply that was in flight while the client announced a change is
+    /// stale, so it is dropped. Returns whether the roots were kept.
+    pub async fn set_roots(&self, roots: Vec<Root>, generation: u64) -> bool {
+        let mut cached = self.roots.write().await;
+        if self.roots_generation.load(Ordering::SeqCst) != generation {
+            return false;
+        }
+        *cached = Some(roots);
+        true
+    }
+
+    /// Get the roots the client last listed, unless they have changed since
+    pub async fn roots(&self) -> Option<Vec<Root>> {
+        self.roots.read().await.clone()
+    }
+
+    /// Forget the listed roots, so they are asked for again
+    pub async fn invalidate_roots(&self) {
+        let mut cached = self.roots.write().await;
+        self.roots_generation.fetch_add(1, Ordering::SeqCst);
+        *cached = None;
+    }
+
+    /// Subscribe to updates of a resource
+    pub async fn subscribe(&self, uri: &str) {
+        self.subscriptions.write().await.insert(uri.to_string());
+    }
+
+    /// Unsubscribe from a resource
+    ///
+    /// Returns false if the session was not subscribed to it.
+    pub async fn unsubscribe(&self, uri: &str) -> bool {
+        self.subscriptions.write().await.remove(uri)
+    }
+
+    /// Check if the session is subscribed to a resource
+    pub async fn is_subscribed(&self, uri: &str) -> bool {
+        self.subscriptions.read().await.contains(uri)
+    }
+
+    /// Track a request that is being handled, returning its cancellation token
+    ///
+    /// Returns None if a request with the same ID is already in flight.
+    pub async fn begin_request(&self, id: &JsonValue) -> Option<CancellationToken> {
+        match self.in_flight.write().await.entry(id.to_string()) {
+            Entry::Occupied(_) => None,
+            Entry::Vacant(entry) => Some(entry.insert(CancellationToken::new()).clone()),
+        }
+    }
+
+    /// Stop tracking a request once it has been answered
+    ///
+    /// A cancelled request was already removed, and its ID may since have
+    /// been reused by a new request that must stay tracked. Answering counts
+    /// as activity, so the idle time of a long call starts when it ends.
+    pub async fn end_request(&self, id: &JsonValue, token: &CancellationToken) {
+        if !token.is_cancelled() {
+            self.in_flight.write().await.remove(&id.to_string());
+        }
+        self.touch().await;
+    }
+
+    /// Check if any request of the session is still being handled
+    pub async fn has_requests_in_flight(&self) -> bool {
+        !self.in_flight.read().await.is_empty()
+    }
+
+    /// Cancel an in-flight request
+    ///
+    /// Returns false if no request with that ID is being handled.
+    pub async fn cancel_request(&self, id: &JsonValue) -> bool {
+        match self.in_flight.write().await.remove(&id.to_string()) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Allocate an ID for a request to the client and wait for its response
+    ///
+    /// The receiver fails if the session is closed before the client answers.
+    pub async fn expect_response(&self) -> (JsonValue, oneshot::Receiver<JsonRpcResponse>) {
+        let id = JsonValue::String(format!("mecp-{}", self.next_request_id.fetch_add(1, Ordering::SeqCst)));
+        let (tx, rx) = oneshot::channel();
+        self.awaiting.write().await.insert(id.to_string(), tx);
+        (id, rx)
+    }
+
+    /// Hand a response from the client to the request awaiting it
+    ///
+    /// Returns false if no request with that ID is awaiting a response.
+    pub async fn resolve_response(&self, response: JsonRpcResponse) -> bool {
+        let id = response.id.clone().unwrap_or(JsonValue::Null);
+        match self.awaiting.write().await.remove(&id.to_string()) {
+            Some(tx) => tx.send(response).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Stop waiting for the response to a request sent to the client
+    pub async fn forget_response(&self, id: &JsonValue) {
+        self.awaiting.write().await.remove(&id.to_string());
+    }
+
+    /// Open the server-to-client stream, replacing any previous one
+    pub async fn attach_stream(&self) -> mpsc::UnboundedReceiver<JsonValue> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        *self.stream.write().await = Some(tx);
+        rx
+    }
+
+    /// Check if the client currently has a server-to-client stream open
+    pub async fn has_stream(&self) -> bool {
+        self.stream
+            .read()
+            .await
+            .as_ref()
+            .map(|tx| !tx.is_closed())
+            .unwrap_or(false)
+    }
+
+    /// Send a JSON-RPC message to the client over its open stream
+    ///
+    /// Returns false if no stream is open or the client has gone away.
+    pub async fn send(&self, message: JsonValue) -> bool {
+        let mut stream = self.stream.write().await;
+        match stream.as_ref() {
+            Some(tx) if tx.send(message).is_ok() => true,
+            Some(_) => {
+                // Receiver dropped - the client disconnected
+                *stream = None;
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+/// Registry of active client sessions, keyed by session ID
+pub struct SessionManager {
+    sessions: RwLock<HashMap<String, Arc<Session>>>,
+}
+
+impl SessionManager {
+    pub fn new() -> Self {
+        Self {
+            sessions: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create and register a new session with a random ID
+    pub async fn create(&self) -> Arc<Session> {
+        let session = self.prepare();
+        self.register(Arc::clone(&session)).await;
+        session
+    }
+
+    /// Create a session with a random ID without registering it yet, e.g.
+    /// until its `initialize` handshake succeeds
+    pub fn prepare(&self) -> Arc<Session> {
+        Arc::new(Session::new(uuid::Uuid::new_v4().to_string()))
+    }
+
+    /// Register a session created with `prepare`
+    pub async fn register(&self, session: Arc<Session>) {
+        self.sessions.write().await.insert(session.id.clone(), session);
+    }
+
+    /// Look up a session by ID
+    pub async fn get(&self, id: &str) -> Option<Arc<Session>> {
+        self.sessions.read().await.get(id).cloned()
+    }
+
+    /// Remove a session, closing its stream
+    pub async fn remove(&self, id: &str) -> Option<Arc<Session>> {
+        let session = self.sessions.write().await.remove(id);
+        if let Some(ref session) = session {
+            *session.stream.write().await = None;
+            for (_, token) in session.in_flight.write().await.drain() {
+                token.cancel();
+            }
+            session.awaiting.write().await.clear();
+        }
+        session
+    }
+
+    /// Remove every session, closing their streams and cancelling their requests
+    ///
+    /// Returns the number of sessions closed.
+    pub async fn close_all(&self) -> usize {
+        let ids: Vec<String> = self.sessions.read().await.keys().cloned().collect();
+        for id in &ids {
+            self.remove(id).await;
+        }
+        ids.len()
+    }
+
+    /// Remove the sessions idle for longer than `ttl`
+    ///
+    /// Sessions with an open server-to-client stream are kept: their client
+    /// is still connected. So are sessions still handling a request, which
+    /// may run for longer than `ttl`. Returns the number of sessions closed.
+    pub async fn reap_idle(&self, ttl: Duration) -> usize {
+        let mut idle = Vec::new();
+        for session in self.all().await {
+            if session.idle_for().await > ttl
+                && !session.has_stream().await
+                && !session.has_requests_in_flight().await
+            {
+                idle.push(session.id.clone());
+            }
+        }
+
+        let mut closed = 0;
+        for id in idle {
+            if self.remove(&id).await.is_some() {
+                closed += 1;
+            }
+        }
+        closed
+    }
+
+    /// Get all active sessions
+    pub async fn all(&self) -> Vec<Arc<Session>> {
+        self.sessions.read().await.values().cloned().collect()
+    }
+
+    /// Get count of active sessions
+    pub async fn count(&self) -> usize {
+        self.sessions.read().await.len()
+    }
+}
+
+impl Default for SessionManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
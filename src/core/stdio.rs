@@ -0,0 +1,195 @@
+use anyhow::{anyhow, Result};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::task::JoinSet;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{info, error, warn};
+
+use super::dispatch::McpDispatcher;
+use super::metrics::MetricsCollector;
+use super::error::codes;
+use super::protocol::*;
+use super::server::McpServer;
+use super::shutdown::{shutdown_signal, DEFAULT_SHUTDOWN_GRACE};
+use crate::core::types::JsonValue;
+
+/// Stdio transport for MCP
+///
+/// Reads newline-delimited JSON-RPC messages from stdin and writes responses
+/// to stdout, one message per line. Desktop MCP hosts launch the server as a
+/// subprocess and talk to it this way. Nothing else may be written to stdout
+/// while this transport is running, so logging must go to stderr.
+pub struct StdioServer {
+    mcp_server: Arc<McpServer>,
+    metrics: Arc<MetricsCollector>,
+    /// How long in-flight requests get to finish once input stops
+    shutdown_grace: Duration,
+    /// The API key the host launched the server with
+    api_key: Option<String>,
+}
+
+impl StdioServer {
+    pub fn new(mcp_server: Arc<McpServer>) -> Self {
+        Self {
+            mcp_server,
+            metrics: Arc::new(MetricsCollector::new()),
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
+            api_key: None,
+        }
+    }
+
+    pub fn with_metrics(mcp_server: Arc<McpServer>, metrics: Arc<MetricsCollector>) -> Self {
+        Self {
+            mcp_server,
+            metrics,
+            shutdown_grace: DEFAULT_SHUTDOWN_GRACE,
+            api_key: None,
+        }
+    }
+
+    pub fn with_shutdown_grace(mut self, grace: Duration) -> Self {
+        self.shutdown_grace = grace;
+        self
+    }
+
+    /// Run the session on behalf of the user `key` belongs to
+    ///
+    /// Hosts have no headers to send over stdio, so they pass the key when
+    /// launching the server, e.g. in an environment variable.
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// Serve requests until stdin is closed or the process gets SIGINT or
+    /// SIGTERM, then shut the server down
+    ///
+    /// In-flight requests get the grace period once, while the transport
+    /// stops; metrics still being written get as long again to be flushed.
+    pub async fn run(self) -> Result<()> {
+        let mcp_server = Arc::clone(&self.mcp_server);
+        let metrics = Arc::clone(&self.metrics);
+        let grace = self.shutdown_grace;
+
+        self.serve_until(tokio::io::stdin(), tokio::io::stdout(), shutdown_signal())
+            .await?;
+
+        // The transport already drained its requests, tool calls included
+        mcp_server.shutdown(Duration::ZERO).await;
+        if !metrics.flush(grace).await {
+            warn!("Gave up on metrics still being written after {:?}", grace);
+        }
+        Ok(())
+    }
+
+    /// Serve requests read from `input` until it is closed, writing to `output`
+    pub async fn serve<R, W>(self, input: R, output: W) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        self.serve_until(input, output, std::future::pending()).await
+    }
+
+    /// Serve requests read from `input`, writing to `output`, until it is
+    /// closed or `stop` resolves
+    ///
+    /// Requests still in flight then get the grace period to finish before
+    /// the session is closed, cancelling them.
+    pub async fn serve_until<R, W, F>(self, input: R, mut output: W, stop: F) -> Result<()>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin + Send + 'static,
+        F: Future<Output = ()>,
+    {
+        // Once the server takes API keys, the host must launch it with one
+        let user = if self.mcp_server.has_api_keys().await {
+            let key = self.api_key.as_deref().ok_or_else(|| anyhow!("Missing API key"))?;
+            Some(
+                self.mcp_server
+                    .user_for_api_key(key)
+                    .await
+                    .ok_or_else(|| anyhow!("Unknown API key"))?,
+            )
+        } else {
+            None
+        };
+        let dispatcher = McpDispatcher::new(Arc::clone(&self.mcp_server), self.metrics);
+        let sessions = self.mcp_server.sessions();
+
+        // The whole process lifetime is a single session
+        let session = sessions.create().await;
+        if let Some(user) = user {
+            session.set_user(&user).await;
+        }
+        let mut outbound = session.attach_stream().await;
+        info!("MCP stdio transport started (session {})", session.id());
+
+        // A single writer keeps concurrent responses from interleaving on stdout
+        let writer = tokio::spawn(async move {
+            while let Some(message) = outbound.recv().await {
+                let mut line = message.to_string();
+                line.push('\n');
+                if let Err(e) = output.write_all(line.as_bytes()).await {
+                    error!("Failed to write to stdout: {}", e);
+                    break;
+                }
+                let _ = output.flush().await;
+            }
+        });
+
+        let mut lines = BufReader::new(input).lines();
+        let mut in_flight = JoinSet::new();
+        tokio::pin!(stop);
+
+        loop {
+            let line = tokio::select! {
+                line = lines.next_line() => match line? {
+                    Some(line) => line,
+                    None => {
+                        info!("Input closed, stopping MCP stdio transport");
+                        break;
+                    }
+                },
+                _ = &mut stop => {
+                    info!("Stopping MCP stdio transport");
+                    break;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<JsonValue>(&line) {
+                Ok(message) => {
+                    let dispatcher = dispatcher.clone();
+                    let session = Arc::clone(&session);
+                    in_flight.spawn(async move {
+                        let response = dispatcher
+                            .handle_message(message, Some(Arc::clone(&session)), "stdio")
+                            .await;
+                        if let Some(response) = response {
+                            session.send(response).await;
+                        }
+                    });
+                }
+                Err(e) => {
+                    let response = JsonRpcResponse::error(None, codes::PARSE_ERROR, format!("Parse error: {}", e));
+                    session.send(serde_json::to_value(response).unwrap()).await;
+                }
+            }
+        }
+
+        // Give in-flight requests a chance to finish; closing the session cancels the rest
+        let drain = async { while in_flight.join_next().await.is_some() {} };
+        if tokio::time::timeout(self.shutdown_grace, drain).await.is_err() {
+            warn!("Cancelling {} requests still running", in_flight.len());
+        }
+        sessions.remove(session.id()).await;
+        while in_flight.join_next().await.is_some() {}
+        let _ = writer.await;
+
+        Ok(())
+    }
+}
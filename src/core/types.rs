@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::core::protocol::{self, ToolContent};
+
 /// Generic JSON value type for flexible data representation
 pub type JsonValue = serde_json::Value;
 
@@ -13,12 +15,45 @@ pub struct ResourceMetadata {
     pub uri: String,
 }
 
-/// Resource content wrapper
+/// Resource template metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceTemplateMetadata {
+    pub name: String,
+    pub description: String,
+    pub mime_type: Option<String>,
+    /// RFC 6570 URI template, e.g. `db://tables/{name}`
+    pub uri_template: String,
+}
+
+/// Resource content wrapper
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ResourceContent {
     pub uri: String,
     pub content: JsonValue,
     pub metadata: Option<HashMap<String, String>>,
+    /// Text or binary contents sent to the client; `content` is sent when empty
+    #[serde(default)]
+    pub contents: Vec<protocol::ResourceContent>,
+}
+
+impl ResourceContent {
+    /// Convert into the contents returned by `resources/read`
+    ///
+    /// Explicit `contents` are passed through unchanged. Otherwise `content`
+    /// becomes a single text entry of `mime_type` (JSON if unknown): a string
+    /// is sent as-is for non-JSON types, anything else is serialized.
+    pub fn into_contents(self, mime_type: Option<&str>) -> Vec<protocol::ResourceContent> {
+        if !self.contents.is_empty() {
+            return self.contents;
+        }
+
+        let mime_type = mime_type.unwrap_or("application/json");
+        let text = match self.content {
+            JsonValue::String(text) if !mime_type.contains("json") => text,
+            content => content.to_string(),
+        };
+        vec![protocol::ResourceContent::text(&self.uri, Some(mime_type), text)]
+    }
 }
 
 /// Tool parameter definition
@@ -31,11 +66,17 @@ pub struct ToolParameter {
 }
 
 /// Tool execution result
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ToolResult {
     pub success: bool,
     pub output: JsonValue,
     pub error: Option<String>,
+    /// Content parts returned to the client; `output` is sent as text when empty
+    #[serde(default)]
+    pub content: Vec<ToolContent>,
+    /// Structured result, validated against the tool's output schema
+    #[serde(default)]
+    pub structured_content: Option<JsonValue>,
 }
 
 /// Prompt metadata
@@ -66,3 +107,34 @@ pub struct PromptMessage {
     pub role: String,
     pub content: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_into_contents_sends_plain_text_unwrapped() {
+        let content = ResourceContent {
+            uri: "file:///notes.txt".to_string(),
+            content: json!("hello"),
+            ..Default::default()
+        };
+
+        let contents = content.into_contents(Some("text/plain"));
+        assert_eq!(contents, vec![protocol::ResourceContent::text("file:///notes.txt", Some("text/plain"), "hello")]);
+    }
+
+    #[test]
+    fn test_into_contents_serializes_json() {
+        let content = ResourceContent {
+            uri: "mock://a".to_string(),
+            content: json!({"a": 1}),
+            ..Default::default()
+        };
+
+        let contents = content.into_contents(None);
+        assert_eq!(contents[0].mime_type.as_deref(), Some("application/json"));
+        assert_eq!(contents[0].text.as_deref(), Some(r#"{"a":1}"#));
+    }
+}
@@ -20,6 +20,7 @@ use std::sync::Arc;
 use tracing::{debug, info};
 
 use crate::core::application::Application;
+use crate::core::context::RequestContext;
 use crate::core::message_broker::InterAppMessage;
 use crate::core::notifications::{McpNotification, NotificationBroadcaster};
 use crate::core::types::{ToolResult as CoreToolResult, ResourceContent as CoreResourceContent, ResourceMetadata, ToolParameter};
@@ -330,29 +331,16 @@ impl WassetteTool {
     }
     
     /// Send resource update notification
-    async fn notify_resource_update(&self, uri: &str, recipient_username: Option<&str>) {
+    ///
+    /// Only sessions subscribed to the resource receive it.
+    async fn notify_resource_update(&self, uri: &str) {
         if let Some(ref broadcaster) = self.notifications {
             let notification = McpNotification::ResourceUpdated { uri: uri.to_string() };
-            
-            // Notify the recipient if different from current user
-            if let Some(recipient) = recipient_username {
-                if recipient != self.username {
-                    info!(
-                        "📢 RESOURCE UPDATE: Notifying '{}' about resource change: {}",
-                        recipient, uri
-                    );
-                    broadcaster.broadcast_to_user(recipient, notification.clone()).await;
-                }
-            }
-            
-            // Also notify current user if their resource changed
-            if uri.contains(&self.username) {
-                info!(
-                    "📢 RESOURCE UPDATE: Notifying '{}' about resource change: {}",
-                    self.username, uri
-                );
-                broadcaster.broadcast_to_user(&self.username, notification).await;
-            }
+            let delivered = broadcaster.broadcast(notification).await;
+            info!(
+                "📢 RESOURCE UPDATE: Notified {} session(s) about resource change: {}",
+                delivered, uri
+            );
         }
     }
 }
@@ -366,10 +354,16 @@ impl Tool for WassetteTool {
             name: self.name.clone(),
             description: self.description.clone(),
             parameters,
+            input_schema: Some(self.input_schema.clone()),
+            ..Default::default()
         })
     }
     
     async fn execute(&self, params: JsonValue) -> Result<CoreToolResult> {
+        self.execute_with_context(params, &RequestContext::default()).await
+    }
+    
+    async fn execute_with_context(&self, params: JsonValue, ctx: &RequestContext) -> Result<CoreToolResult> {
         info!(
             "🔧 TOOL EXECUTE: app='{}', user='{}', tool='{}', params={}",
             self.app_id, self.username, self.name, 
@@ -378,10 +372,10 @@ impl Tool for WassetteTool {
         
         let should_notify = self.triggers_resource_update();
         let resource_uri = self.get_updated_resource_uri(&params);
-        let recipient = params.get("recipient").and_then(|v| v.as_str()).map(|s| s.to_string());
         
         // Call tool with user context to enable KV storage
-        match self.runtime.call_tool_with_user(&self.name, &params, Some(&self.component_id), Some(self.user_id)).await {
+        let logger = Some(ctx.logger(&self.name));
+        match self.runtime.call_tool_logged(&self.name, &params, Some(&self.component_id), Some(self.user_id), logger).await {
             Ok(result_str) => {
                 // Parse the result - Wassette returns JSON
                 let output: JsonValue = serde_json::from_str(&result_str)
@@ -395,7 +389,7 @@ impl Tool for WassetteTool {
                 // Send resource update notification if applicable
                 if should_notify {
                     if let Some(ref uri) = resource_uri {
-                        self.notify_resource_update(uri, recipient.as_deref()).await;
+                        self.notify_resource_update(uri).await;
                     }
                 }
                 
@@ -403,6 +397,7 @@ impl Tool for WassetteTool {
                     success: true,
                     output,
                     error: None,
+                    ..Default::default()
                 })
             }
             Err(e) => {
@@ -415,6 +410,7 @@ impl Tool for WassetteTool {
                     success: false,
                     output: JsonValue::Null,
                     error: Some(e.to_string()),
+                    ..Default::default()
                 })
             }
         }
@@ -572,6 +568,7 @@ impl Resource for WassetteResource {
             uri,
             content,
             metadata: Some(metadata),
+            ..Default::default()
         })
     }
     
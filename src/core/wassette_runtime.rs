@@ -38,12 +38,15 @@ use std::sync::Arc;
 use anyhow::{Context, Result, bail};
 use async_trait::async_trait;
 use serde_json::Value as JsonValue;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
 use tracing::{debug, info, warn};
 use wasmtime::{Engine, Config, Store};
 use wasmtime::component::{Component, Linker, Val};
 use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiView, ResourceTable};
 
+use crate::core::context::RequestContext;
+use crate::core::logging::ClientLogger;
+use crate::core::protocol::LoggingLevel;
 use crate::core::types::{ToolResult, ToolParameter};
 use crate::core::message_broker::AppKvStore;
 use crate::services::config::RedisConfig;
@@ -58,7 +61,10 @@ pub struct WasiState {
     ctx: WasiCtx,
     table: ResourceTable,
     /// KV storage for this component instance (optional)
+    #[allow(dead_code)] // Read once mecp:kv-storage is bound to the linker
     kv_store: Option<Arc<AppKvStore>>,
+    /// Where messages the component logs through mecp:logging go (optional)
+    log_tx: Option<mpsc::UnboundedSender<(LoggingLevel, String)>>,
 }
 
 impl WasiState {
@@ -69,6 +75,7 @@ impl WasiState {
                 .build(),
             table: ResourceTable::new(),
             kv_store: None,
+            log_tx: None,
         }
     }
     
@@ -79,6 +86,7 @@ impl WasiState {
                 .build(),
             table: ResourceTable::new(),
             kv_store: Some(kv_store),
+            log_tx: None,
         }
     }
 }
@@ -184,7 +192,7 @@ impl WassetteRuntime {
         
         info!("🔧 Wassette Runtime initialized (library mode)");
         info!("   Component dir: {}", runtime.component_dir.display());
-        if redis_config.is_some() {
+        if runtime.redis_config.is_some() {
             info!("   Redis KV storage: enabled");
         } else {
             info!("   Redis KV storage: disabled (in-memory only)");
@@ -399,9 +407,9 @@ impl WassetteRuntime {
                 wasmtime::component::types::ComponentItem::ComponentFunc(func_type) => {
                     // Direct function export
                     let tool = self.create_tool_info_from_func(
-                        &name,
+                        name,
                         None,
-                        &name,
+                        name,
                         &func_type,
                         component_id,
                     );
@@ -412,12 +420,12 @@ impl WassetteRuntime {
                     for (func_name, item) in instance_type.exports(&self.engine) {
                         if let wasmtime::component::types::ComponentItem::ComponentFunc(func_type) = item {
                             // Create normalized tool name
-                            let normalized_name = Self::normalize_tool_name(&name, &func_name);
+                            let normalized_name = Self::normalize_tool_name(name, func_name);
                             
                             let tool = self.create_tool_info_from_func(
                                 &normalized_name,
                                 Some(name.to_string()),
-                                &func_name,
+                                func_name,
                                 &func_type,
                                 component_id,
                             );
@@ -448,8 +456,8 @@ impl WassetteRuntime {
         let mut param_names = Vec::new();
         
         // Get parameter types and generate names
-        for (idx, param_type) in func_type.params().enumerate() {
-            // Generate parameter name (param0, param1, etc. since wasmtime v27 doesn't provide names)
+        for (idx, (_, param_type)) in func_type.params().enumerate() {
+            // Name parameters by position (param0, param1, etc.)
             let param_name = format!("param{}", idx);
             let schema = self.component_type_to_json_schema(&param_type);
             properties.insert(param_name.clone(), schema);
@@ -465,7 +473,7 @@ impl WassetteRuntime {
         
         ToolInfo {
             name: name.to_string(),
-            description: format!("Function exported from WebAssembly Component"),
+            description: "Function exported from WebAssembly Component".to_string(),
             input_schema,
             interface_name,
             function_name: function_name.to_string(),
@@ -516,10 +524,7 @@ impl WassetteRuntime {
     /// Normalize tool name (interface + function name)
     fn normalize_tool_name(interface: &str, function: &str) -> String {
         // Convert from "local:package/interface" to "local_package_interface_function"
-        let normalized_interface = interface
-            .replace(':', "_")
-            .replace('/', "_")
-            .replace('-', "-");
+        let normalized_interface = interface.replace([':', '/'], "_");
         format!("{}_{}", normalized_interface, function)
     }
     
@@ -582,6 +587,18 @@ impl WassetteRuntime {
         self.call_tool_with_user(tool_name, arguments, None, None).await
     }
     
+    /// Call a tool by name on behalf of a client request
+    ///
+    /// Messages the component logs are sent to the client.
+    pub async fn call_tool_with_context(
+        &self,
+        tool_name: &str,
+        arguments: &JsonValue,
+        ctx: &RequestContext,
+    ) -> Result<String> {
+        self.call_tool_logged(tool_name, arguments, None, None, Some(ctx.logger(tool_name))).await
+    }
+    
     /// Call a tool by name with user context (enables KV storage)
     pub async fn call_tool_with_user(
         &self,
@@ -589,6 +606,19 @@ impl WassetteRuntime {
         arguments: &JsonValue,
         component_id: Option<&str>,
         user_id: Option<u64>,
+    ) -> Result<String> {
+        self.call_tool_logged(tool_name, arguments, component_id, user_id, None).await
+    }
+    
+    /// Call a tool by name with user context, sending what the component
+    /// logs to `logger`
+    pub async fn call_tool_logged(
+        &self,
+        tool_name: &str,
+        arguments: &JsonValue,
+        component_id: Option<&str>,
+        user_id: Option<u64>,
+        logger: Option<ClientLogger>,
     ) -> Result<String> {
         // Find the component and tool info
         let (component_id, tool_info) = {
@@ -617,7 +647,7 @@ impl WassetteRuntime {
             None
         };
         
-        self.execute_tool(&component_id, &tool_info, arguments, kv_store).await
+        self.execute_tool(&component_id, &tool_info, arguments, kv_store, logger).await
     }
     
     /// Execute a tool on a component
@@ -631,6 +661,7 @@ impl WassetteRuntime {
         tool_info: &ToolInfo,
         arguments: &JsonValue,
         kv_store: Option<Arc<AppKvStore>>,
+        logger: Option<ClientLogger>,
     ) -> Result<String> {
         // Get the loaded component
         let components = self.components.read().await;
@@ -651,12 +682,26 @@ impl WassetteRuntime {
                 .context("Failed to add KV storage to linker")?;
         }
         
-        let wasi_state = if let Some(kv) = kv_store {
+        Self::add_logging_to_linker(&mut linker)
+            .context("Failed to add logging to linker")?;
+        
+        let mut wasi_state = if let Some(kv) = kv_store {
             WasiState::with_kv_store(kv)
         } else {
             WasiState::new()
         };
         
+        // Forward log messages to the client while the component runs
+        let forwarder = logger.map(|logger| {
+            let (log_tx, mut log_rx) = mpsc::unbounded_channel::<(LoggingLevel, String)>();
+            wasi_state.log_tx = Some(log_tx);
+            tokio::spawn(async move {
+                while let Some((level, message)) = log_rx.recv().await {
+                    logger.log(level, message).await;
+                }
+            })
+        });
+        
         let mut store = Store::new(&self.engine, wasi_state);
         
         // Instantiate the component
@@ -691,7 +736,7 @@ impl WassetteRuntime {
         let func_param_types = func.params(&store);
         let mut params = Vec::new();
         
-        for (idx, param_type) in func_param_types.iter().enumerate() {
+        for (idx, (_, param_type)) in func_param_types.iter().enumerate() {
             let param_name = &tool_info.param_names[idx];
             let value = arguments.get(param_name)
                 .ok_or_else(|| anyhow::anyhow!("Missing parameter: {}", param_name))?;
@@ -710,6 +755,13 @@ impl WassetteRuntime {
         func.post_return_async(&mut store).await
             .context("Failed to post-return")?;
         
+        // Dropping the store closes the log channel; wait for the messages
+        // logged so far to reach the client before the result does
+        drop(store);
+        if let Some(forwarder) = forwarder {
+            let _ = forwarder.await;
+        }
+        
         // Convert results to JSON
         let result_json = if results.len() == 1 {
             self.component_val_to_json(&results[0])?
@@ -1077,9 +1129,7 @@ impl WassetteRuntime {
     /// 
     /// This adds the mecp:kv-storage interface functions to the linker
     /// so components can import and use KV storage.
-    fn add_kv_storage_to_linker(linker: &mut Linker<WasiState>, kv_store: Arc<AppKvStore>) -> Result<()> {
-        use wasmtime::component::Resource;
-        
+    fn add_kv_storage_to_linker(_linker: &mut Linker<WasiState>, _kv_store: Arc<AppKvStore>) -> Result<()> {
         // Note: For wasmtime component model, we need to manually implement
         // the host functions. However, wasmtime's component model API doesn't
         // directly support adding arbitrary host functions like the core module API.
@@ -1101,9 +1151,40 @@ impl WassetteRuntime {
         Ok(())
     }
     
-    /// Shutdown the runtime (no-op for library mode)
+    /// Add the mecp:logging host functions to the linker
+    /// 
+    /// Components call `log(level, message)` to log to the client that made
+    /// the call (see wit/mecp-logging.wit). Unknown levels are logged as
+    /// `info`; without a client the message only goes to the host log.
+    fn add_logging_to_linker(linker: &mut Linker<WasiState>) -> Result<()> {
+        let mut logging = linker.instance("mecp:logging/logging")?;
+        logging.func_wrap(
+            "log",
+            |store: wasmtime::StoreContextMut<'_, WasiState>, (level, message): (String, String)| {
+                let level = level.parse().unwrap_or(LoggingLevel::Info);
+                match &store.data().log_tx {
+                    Some(log_tx) => {
+                        let _ = log_tx.send((level, message));
+                    }
+                    None => debug!("Component log ({:?}): {}", level, message),
+                }
+                Ok(())
+            },
+        )?;
+        Ok(())
+    }
+    
+    /// Shut the runtime down, dropping its compiled components
+    ///
+    /// Waits for calls still running on the components. The components stay
+    /// on disk for the next runtime to load; calls made afterwards fail, and
+    /// shutting down again does nothing.
     pub async fn shutdown(&self) -> Result<()> {
-        info!("Wassette runtime shutdown");
+        let unloaded = self.components.write().await.drain().count();
+        self.tool_to_component.write().await.clear();
+        if unloaded > 0 {
+            info!("Wassette runtime shut down, dropped {} components", unloaded);
+        }
         Ok(())
     }
 }
@@ -1166,12 +1247,13 @@ impl WassetteApp {
         match self.runtime.call_tool(tool_name, &params).await {
             Ok(result_str) => {
                 let output = serde_json::from_str(&result_str)
-                    .unwrap_or_else(|_| JsonValue::String(result_str));
+                    .unwrap_or(JsonValue::String(result_str));
                 
                 Ok(ToolResult {
                     success: true,
                     output,
                     error: None,
+                    ..Default::default()
                 })
             }
             Err(e) => {
@@ -1179,6 +1261,7 @@ impl WassetteApp {
                     success: false,
                     output: JsonValue::Null,
                     error: Some(e.to_string()),
+                    ..Default::default()
                 })
             }
         }
@@ -1223,19 +1306,26 @@ impl Tool for WassetteTool {
             name: self.name.clone(),
             description: self.description.clone(),
             parameters,
+            input_schema: Some(self.input_schema.clone()),
+            ..Default::default()
         })
     }
     
     async fn execute(&self, params: JsonValue) -> Result<ToolResult> {
-        match self.runtime.call_tool(&self.name, &params).await {
+        self.execute_with_context(params, &RequestContext::default()).await
+    }
+    
+    async fn execute_with_context(&self, params: JsonValue, ctx: &RequestContext) -> Result<ToolResult> {
+        match self.runtime.call_tool_with_context(&self.name, &params, ctx).await {
             Ok(result_str) => {
                 let output = serde_json::from_str(&result_str)
-                    .unwrap_or_else(|_| JsonValue::String(result_str));
+                    .unwrap_or(JsonValue::String(result_str));
                 
                 Ok(ToolResult {
                     success: true,
                     output,
                     error: None,
+                    ..Default::default()
                 })
             }
             Err(e) => {
@@ -1243,10 +1333,16 @@ impl Tool for WassetteTool {
                     success: false,
                     output: JsonValue::Null,
                     error: Some(e.to_string()),
+                    ..Default::default()
                 })
             }
         }
     }
+    
+    async fn shutdown(&self) -> Result<()> {
+        // The tools of a loader share its runtime; the first hook stops it
+        self.runtime.shutdown().await
+    }
 }
 
 /// Extract parameters from a JSON Schema
@@ -1367,6 +1463,97 @@ impl WassetteAppLoader {
 mod tests {
     use super::*;
     
+    /// A component exporting `answer: func() -> u32`
+    const ANSWER: &str = r#"
+        (component
+            (core module $m
+                (func (export "answer") (result i32) i32.const 42))
+            (core instance $i (instantiate $m))
+            (func (export "answer") (result u32)
+                (canon lift (core func $i "answer"))))
+    "#;
+    
+    /// A component exporting `run: func()`, which logs a warning and a debug
+    /// message through `mecp:logging`
+    const LOGS: &str = r#"
+        (component
+            (import "mecp:logging/logging" (instance $logging
+                (export "log" (func (param "level" string) (param "message" string)))))
+            (core module $mem (memory (export "memory") 1))
+            (core instance $mem (instantiate $mem))
+            (alias core export $mem "memory" (core memory $memory))
+            (alias export $logging "log" (func $log))
+            (core func $log (canon lower (func $log) (memory $memory)))
+            (core module $m
+                (import "host" "log" (func $log (param i32 i32 i32 i32)))
+                (import "host" "memory" (memory 1))
+                (data (i32.const 0) "warning")
+                (data (i32.const 16) "careful")
+                (data (i32.const 32) "debug")
+                (data (i32.const 48) "details")
+                (func (export "run")
+                    (call $log (i32.const 0) (i32.const 7) (i32.const 16) (i32.const 7))
+                    (call $log (i32.const 32) (i32.const 5) (i32.const 48) (i32.const 7))))
+            (core instance $i (instantiate $m
+                (with "host" (instance
+                    (export "log" (func $log))
+                    (export "memory" (memory $memory))))))
+            (func (export "run") (canon lift (core func $i "run"))))
+    "#;
+    
+    /// Start a runtime in a fresh directory and load a component into it
+    async fn runtime_with(component_id: &str, wat: &str) -> (WassetteRuntime, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("mecp-wassette-{}-{}", component_id, std::process::id()));
+        let runtime = WassetteRuntime::new_unloaded(&dir).await.unwrap();
+        let path = dir.join(format!("{}.wasm", component_id));
+        std::fs::write(&path, wat::parse_str(wat).unwrap()).unwrap();
+        runtime.load_component(&format!("file://{}", path.display())).await.unwrap();
+        (runtime, dir)
+    }
+    
+    #[tokio::test]
+    async fn test_component_logs_reach_client() {
+        let (runtime, dir) = runtime_with("logs", LOGS).await;
+        let session = crate::core::session::SessionManager::new().create().await;
+        session.set_log_level(LoggingLevel::Info).await;
+        let (stream, mut messages) = mpsc::unbounded_channel();
+        let ctx = RequestContext::new(
+            Some(serde_json::json!(1)),
+            None,
+            Some(session),
+            Some(stream),
+            tokio_util::sync::CancellationToken::new(),
+        );
+        
+        runtime.call_tool_with_context("run", &serde_json::json!({}), &ctx).await.unwrap();
+        
+        // The debug message is below the session's level
+        let message = messages.try_recv().unwrap();
+        assert_eq!(message["method"], "notifications/message");
+        assert_eq!(message["params"]["level"], "warning");
+        assert_eq!(message["params"]["logger"], "run");
+        assert_eq!(message["params"]["data"], "careful");
+        assert!(messages.try_recv().is_err());
+        
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_shutdown_drops_components() {
+        let (runtime, dir) = runtime_with("answer", ANSWER).await;
+        let result = runtime.call_tool("answer", &serde_json::json!({})).await.unwrap();
+        assert_eq!(result, r#"{"result":42}"#);
+        
+        runtime.shutdown().await.unwrap();
+        assert!(runtime.list_components().await.is_empty());
+        assert!(runtime.call_tool("answer", &serde_json::json!({})).await.is_err());
+        // The component stays on disk, and shutting down again is harmless
+        assert!(dir.join("answer.wasm").exists());
+        runtime.shutdown().await.unwrap();
+        
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+    
     #[test]
     fn test_extract_parameters_from_schema() {
         let schema = serde_json::json!({
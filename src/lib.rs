@@ -1,8 +1,18 @@
 // MeCP - Modular Context Protocol Library
 // Re-export core modules for library usage
 
+// Lets the `#[tool]` macro's `::mecp::` paths resolve inside this crate too
+extern crate self as mecp;
+
 pub mod core;
 pub mod resources;
 pub mod tools;
 pub mod prompts;
 pub mod services;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow;
+    pub use async_trait::async_trait;
+    pub use serde_json;
+}
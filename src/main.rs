@@ -1,52 +1,94 @@
-mod core;
-mod resources;
-mod tools;
-mod prompts;
-mod services;
-
 use anyhow::Result;
+use mecp::{core, prompts, resources, services, tools};
 use std::sync::Arc;
 use std::env;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // In stdio mode stdout carries the MCP protocol, so nothing else may be printed there
+    let stdio = env::args().skip(1).any(|arg| arg == "--stdio");
+
     // Initialize tracing
-    tracing_subscriber::fmt::init();
+    if stdio {
+        tracing_subscriber::fmt()
+            .with_writer(std::io::stderr)
+            .with_ansi(false)
+            .init();
+    } else {
+        tracing_subscriber::fmt::init();
+
+        println!("MeCP - Modular Context Protocol Server");
+        println!("=======================================\n");
+    }
 
-    println!("MeCP - Modular Context Protocol Server");
-    println!("=======================================\n");
+    // Load configuration for MySQL metrics and list pagination
+    let config = services::config::ServiceConfig::load("config.toml")
+        .unwrap_or_else(|_| {
+            if !stdio {
+                println!("⚠️  Could not load config.toml, using defaults");
+            }
+            services::config::ServiceConfig::default()
+        });
 
     // Initialize the MCP server
-    let server = Arc::new(crate::core::server::McpServer::new());
+    let server = Arc::new(crate::core::server::McpServer::with_page_size(config.server.page_size));
+    let shutdown_grace = std::time::Duration::from_secs(config.server.shutdown_grace_secs);
+    server.set_default_tool_limits(config.tools.defaults.to_limits()).await;
+    for (name, limits) in &config.tools.limits {
+        server.set_tool_limits(name, limits.to_limits()).await;
+    }
+    for api_key in &config.api_keys {
+        server.add_api_key(&api_key.key, &api_key.user).await;
+    }
+    let read_only_users: Vec<String> = config
+        .api_keys
+        .iter()
+        .filter(|api_key| api_key.read_only)
+        .map(|api_key| api_key.user.clone())
+        .collect();
+    if !read_only_users.is_empty() {
+        server
+            .add_middleware(Box::new(core::middleware::ReadOnlyPolicy::for_users(read_only_users)))
+            .await;
+    }
     
     // Register resources
-    server.register_resource(Box::new(resources::mock::MockResource::new())).await;
+    server.register_resource(Box::new(resources::mock::MockResource::new())).await?;
+    server.register_resource_template(Box::new(resources::mock::MockResourceTemplate::new())).await?;
     
     // Register tools
-    server.register_tool(Box::new(tools::mock::HelloWorldTool::new())).await;
+    server.register_tool(Box::new(tools::mock::HelloWorldTool::new())).await?;
     // Required tools for ChatGPT Connectors and deep research
-    server.register_tool(Box::new(tools::mock::SearchTool::new())).await;
-    server.register_tool(Box::new(tools::mock::FetchTool::new())).await;
+    server.register_tool(Box::new(tools::mock::SearchTool::new())).await?;
+    server.register_tool(Box::new(tools::mock::FetchTool::new())).await?;
+    // Summaries use the connected client's model through sampling
+    server.register_tool(Box::new(tools::mock::SummarizeTool)).await?;
     
     // Register prompts
-    server.register_prompt(Box::new(prompts::mock::MockPrompt::new())).await;
-    
-    println!("Server initialized successfully!");
-    println!("\nRegistered components:");
-    println!("  - Resources: {}", server.resource_count().await);
-    println!("  - Tools: {}", server.tool_count().await);
-    println!("  - Prompts: {}", server.prompt_count().await);
-    
-    // Load configuration for MySQL metrics
-    let config = services::config::ServiceConfig::load("config.toml")
-        .unwrap_or_else(|_| {
-            println!("⚠️  Could not load config.toml, using defaults");
-            services::config::ServiceConfig::default()
-        });
+    server.register_prompt(Box::new(prompts::mock::MockPrompt::new())).await?;
+
+    // WebAssembly components in ./wassette-components run as tools; the
+    // runtime is shut down with them
+    #[cfg(feature = "wassette")]
+    {
+        let redis = Some(config.redis.clone()).filter(|redis| redis.enabled);
+        match core::wassette_runtime::WassetteAppLoader::new_with_redis("./wassette-components", redis).await {
+            Ok(loader) => {
+                for tool in loader.get_all_tools().await? {
+                    if let Err(e) = server.register_tool(tool).await {
+                        eprintln!("⚠️  Skipping WebAssembly tool: {}", e);
+                    }
+                }
+            }
+            Err(e) => eprintln!("⚠️  Wassette runtime initialization failed: {}", e),
+        }
+    }
     
     // Initialize metrics collector with MySQL backend if enabled
     let metrics = if config.mysql.enabled {
-        println!("📊 Enabling MySQL metrics backend...");
+        if !stdio {
+            println!("📊 Enabling MySQL metrics backend...");
+        }
         let mysql_writer = Arc::new(crate::core::metrics::MySqlMetricsWriter::new(
             &config.mysql.host,
             config.mysql.port,
@@ -56,87 +98,37 @@ async fn main() -> Result<()> {
         ));
         Arc::new(crate::core::metrics::MetricsCollector::with_mysql_writer(mysql_writer))
     } else {
-        println!("⚠️  MySQL metrics disabled, using in-memory only");
+        if !stdio {
+            println!("⚠️  MySQL metrics disabled, using in-memory only");
+        }
         Arc::new(crate::core::metrics::MetricsCollector::new())
     };
-    
-    // Initialize app loader for marketplace FIRST (needed by connector)
-    let app_loader = if config.mysql.enabled {
-        println!("📦 Initializing Application Marketplace...");
-        match crate::core::app_loader::AppLoader::new(&config.mysql).await {
-            Ok(loader) => {
-                // Initialize tables
-                if let Err(e) = loader.initialize_tables().await {
-                    println!("⚠️  Failed to initialize app loader tables: {}", e);
-                }
-                Some(Arc::new(loader))
-            }
-            Err(e) => {
-                println!("⚠️  Failed to create app loader: {}", e);
-                None
-            }
+
+    if config.tools.cache.enabled {
+        if !stdio {
+            println!("🗄️  Caching results of cacheable tools (up to {})", config.tools.cache.capacity);
         }
-    } else {
-        println!("⚠️  Application Marketplace disabled (MySQL required)");
-        None
-    };
+        server
+            .set_tool_cache(config.tools.cache.to_cache().with_metrics(metrics.clone()))
+            .await;
+    }
     
-    // Initialize the WASM KV Store MySQL persistence if MySQL is enabled
-    if config.mysql.enabled {
-        if let Err(e) = crate::core::wasm_runtime::WasmKvStore::init_mysql_pool(&config.mysql).await {
-            println!("⚠️  WASM KV Store MySQL initialization failed: {}", e);
-            println!("   (WASM apps will use in-memory storage - data will not persist across restarts)");
+    // Desktop MCP hosts launch the server as a subprocess and talk JSON-RPC over stdin/stdout
+    if stdio {
+        let mut stdio_server = crate::core::stdio::StdioServer::with_metrics(server, metrics)
+            .with_shutdown_grace(shutdown_grace);
+        // Hosts pass the API key in the environment of the server process
+        if let Ok(key) = env::var("MECP_API_KEY") {
+            stdio_server = stdio_server.with_api_key(key);
         }
+        return stdio_server.run().await;
     }
     
-    // Create the notification broadcaster for resource updates
-    // This needs to be created before the connector so it can be shared
-    let notifications = Arc::new(crate::core::notifications::NotificationBroadcaster::new());
-    println!("📢 Notification broadcaster initialized");
-    
-    // Initialize the Cursor MCP Connector if MySQL is enabled
-    // Note: Connector needs app_loader for WASM application support
-    let connector = if config.mysql.enabled {
-        println!("🔌 Initializing Cursor MCP Connector...");
-        
-        let mut connector = if let Some(ref loader) = app_loader {
-            println!("   ✅ WASM Runtime enabled with App Loader");
-            crate::core::connector::CursorMcpConnector::with_app_loader(
-                config.mysql.clone(),
-                Arc::clone(loader),
-            )
-        } else {
-            println!("   ⚠️  WASM Runtime disabled (no App Loader)");
-            crate::core::connector::CursorMcpConnector::new(config.mysql.clone())
-        };
-        
-        // Set the notification broadcaster for resource updates
-        connector.set_notifications(Arc::clone(&notifications));
-        
-        // Initialize Wassette runtime if feature is enabled
-        #[cfg(feature = "wassette")]
-        {
-            use std::path::PathBuf;
-            let component_dir = PathBuf::from("./wassette-components");
-            println!("   🔧 Initializing Wassette runtime...");
-            println!("      Component dir: {}", component_dir.display());
-            
-            match connector.set_wassette_runtime_with_redis(component_dir, Some(config.redis.clone())).await {
-                Ok(()) => {
-                    println!("   ✅ Wassette runtime enabled for Components");
-                }
-                Err(e) => {
-                    eprintln!("   ⚠️  Wassette runtime initialization failed: {}", e);
-                    eprintln!("      Components will not be loadable");
-                }
-            }
-        }
-        
-        Some(Arc::new(connector))
-    } else {
-        println!("⚠️  Per-user MCP endpoints disabled (MySQL required)");
-        None
-    };
+    println!("Server initialized successfully!");
+    println!("\nRegistered components:");
+    println!("  - Resources: {}", server.resource_count().await);
+    println!("  - Tools: {}", server.tool_count().await);
+    println!("  - Prompts: {}", server.prompt_count().await);
     
     // Get port from environment or use config
     // Railway uses PORT, but we also support MCP_PORT for local development
@@ -153,12 +145,6 @@ async fn main() -> Result<()> {
     println!("API endpoint: http://{}:{}/mcp", host, port);
     println!("Health check: http://{}:{}/health", host, port);
     println!("Dashboard: http://{}:{}/dashboard", host, port);
-    println!("Marketplace: http://{}:{}/marketplace", host, port);
-    
-    if connector.is_some() {
-        println!("\n📍 Per-user MCP endpoints enabled:");
-        println!("   http://{}:{}/u/{{username}}/mcp", host, port);
-    }
     
     println!();
     
@@ -185,54 +171,16 @@ async fn main() -> Result<()> {
         None
     };
     
-    // Initialize Vector Database (Milvus) for similarity search
-    let vector_db = if config.milvus.enabled {
-        println!("🔍 Initializing Vector Database for similarity search...");
-        let milvus_config = core::database::MilvusConfig {
-            host: config.milvus.host.clone(),
-            port: config.milvus.port,
-            collection_name: config.milvus.collection_name.clone(),
-            dimension: config.milvus.dimension,
-            metric: config.milvus.metric.clone(),
-        };
-        let client = core::database::MilvusClient::new(milvus_config);
-        
-        // Check if Milvus is available
-        if client.check_connection().await {
-            println!("   ✅ Milvus connected at {}:{}", config.milvus.host, config.milvus.port);
-        } else {
-            println!("   ⚠️  Milvus not available, using in-memory fallback");
-        }
-        
-        Some(Arc::new(client))
-    } else {
-        println!("🔍 Initializing Vector Database (in-memory mode)...");
-        // Always initialize vector DB in memory for fuzzy search
-        let client = core::database::MilvusClient::with_defaults();
-        Some(Arc::new(client))
-    };
-    
-    // Start the HTTP server with metrics, auth, connector, app loader, and vector DB
+    // Start the HTTP server with metrics and auth
     let mut http_server = crate::core::http_server::HttpServer::with_metrics(server.clone(), metrics, host, port)
-        .with_mysql_config(config.mysql.clone())
-        .with_notifications(notifications);  // Share the same notification broadcaster
+        .with_shutdown_grace(shutdown_grace)
+        .with_session_ttl(std::time::Duration::from_secs(config.server.session_ttl_secs));
     
     if let Some(auth) = auth_service {
         http_server = http_server.with_auth(auth);
     }
     
-    if let Some(conn) = connector {
-        http_server = http_server.with_connector(conn);
-    }
-    
-    if let Some(loader) = app_loader {
-        http_server = http_server.with_app_loader(loader);
-    }
-    
-    if let Some(vdb) = vector_db {
-        http_server = http_server.with_vector_db(vdb);
-    }
-    
+    // Serves until SIGINT/SIGTERM, then drains in-flight calls and flushes metrics
     http_server.start().await?;
     
     Ok(())
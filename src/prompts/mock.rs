@@ -1,9 +1,23 @@
 use async_trait::async_trait;
 use anyhow::Result;
+use std::collections::HashMap;
 
 use crate::core::types::{PromptMetadata, PromptArgument, PromptResult, PromptMessage, JsonValue};
 use crate::prompts::Prompt;
 
+/// Topics the mock prompt suggests
+const TOPICS: &[&str] = &[
+    "astronomy",
+    "biology",
+    "chemistry",
+    "climate",
+    "history",
+    "mathematics",
+    "music",
+    "philosophy",
+    "rust",
+];
+
 /// Mock prompt implementation for testing and demonstration
 pub struct MockPrompt {
     name: String,
@@ -33,6 +47,18 @@ impl Prompt for MockPrompt {
         })
     }
 
+    async fn complete(&self, argument: &str, value: &str, _context: &HashMap<String, String>) -> Result<Vec<String>> {
+        if argument != "topic" {
+            return Ok(Vec::new());
+        }
+        let value = value.to_lowercase();
+        Ok(TOPICS
+            .iter()
+            .filter(|topic| topic.starts_with(&value))
+            .map(|topic| topic.to_string())
+            .collect())
+    }
+
     async fn generate(&self, args: JsonValue) -> Result<PromptResult> {
         let topic = args
             .get("topic")
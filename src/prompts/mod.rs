@@ -2,6 +2,7 @@ pub mod mock;
 
 use async_trait::async_trait;
 use anyhow::Result;
+use std::collections::HashMap;
 use crate::core::types::{PromptMetadata, PromptResult, JsonValue};
 
 /// Prompt trait - defines the interface for all MCP prompts
@@ -18,4 +19,22 @@ pub trait Prompt: Send + Sync {
         // Default implementation - can be overridden
         Ok(args.is_object())
     }
+
+    /// Suggest values for an argument, given its partial `value` and the
+    /// arguments already filled in
+    ///
+    /// The server caps the suggestions sent to the client.
+    async fn complete(
+        &self,
+        _argument: &str,
+        _value: &str,
+        _context: &HashMap<String, String>,
+    ) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Release what the prompt holds when the server shuts down
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
 }
@@ -1,9 +1,11 @@
 use async_trait::async_trait;
 use anyhow::Result;
 use serde_json::json;
+use std::collections::HashMap;
 
-use crate::core::types::{ResourceMetadata, ResourceContent};
-use crate::resources::Resource;
+use crate::core::protocol;
+use crate::core::types::{ResourceMetadata, ResourceContent, ResourceTemplateMetadata};
+use crate::resources::{Resource, ResourceTemplate};
 
 /// Mock resource implementation for testing and demonstration
 pub struct MockResource {
@@ -40,6 +42,7 @@ impl Resource for MockResource {
                 "timestamp": chrono::Utc::now().to_rfc3339()
             }),
             metadata: None,
+            ..Default::default()
         })
     }
 
@@ -53,3 +56,119 @@ impl Default for MockResource {
         Self::new()
     }
 }
+
+/// Mock image resource - serves a 1x1 PNG as binary contents
+pub struct MockImageResource {
+    name: String,
+    uri: String,
+}
+
+/// A transparent 1x1 PNG
+const PIXEL_PNG: &[u8] = &[
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0x00, 0x01, 0x00, 0x00,
+    0x05, 0x00, 0x01, 0x0d, 0x0a, 0x2d, 0xb4, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae,
+    0x42, 0x60, 0x82,
+];
+
+impl MockImageResource {
+    pub fn new() -> Self {
+        Self {
+            name: "mock_image".to_string(),
+            uri: "mock://images/pixel.png".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Resource for MockImageResource {
+    async fn metadata(&self) -> Result<ResourceMetadata> {
+        Ok(ResourceMetadata {
+            name: self.name.clone(),
+            description: "A mock binary resource".to_string(),
+            mime_type: Some("image/png".to_string()),
+            uri: self.uri.clone(),
+        })
+    }
+
+    async fn read(&self) -> Result<ResourceContent> {
+        Ok(ResourceContent {
+            uri: self.uri.clone(),
+            contents: vec![protocol::ResourceContent::blob(&self.uri, Some("image/png"), PIXEL_PNG)],
+            ..Default::default()
+        })
+    }
+
+    async fn uri(&self) -> String {
+        self.uri.clone()
+    }
+}
+
+impl Default for MockImageResource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Users the mock resource template suggests
+const USERS: &[&str] = &["alice", "bob", "carol", "dave"];
+
+/// Mock resource template - a profile resource for any user
+pub struct MockResourceTemplate {
+    name: String,
+    uri_template: String,
+}
+
+impl MockResourceTemplate {
+    pub fn new() -> Self {
+        Self {
+            name: "mock_user_profile".to_string(),
+            uri_template: "mock://users/{user}/profile".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl ResourceTemplate for MockResourceTemplate {
+    async fn metadata(&self) -> Result<ResourceTemplateMetadata> {
+        Ok(ResourceTemplateMetadata {
+            name: self.name.clone(),
+            description: "A mock user profile, addressed by user name".to_string(),
+            mime_type: Some("application/json".to_string()),
+            uri_template: self.uri_template.clone(),
+        })
+    }
+
+    async fn read(&self, uri: &str, variables: &HashMap<String, String>) -> Result<ResourceContent> {
+        let user = variables.get("user").cloned().unwrap_or_default();
+
+        Ok(ResourceContent {
+            uri: uri.to_string(),
+            content: json!({
+                "user": user,
+                "display_name": format!("Mock user {}", user),
+                "timestamp": chrono::Utc::now().to_rfc3339()
+            }),
+            metadata: None,
+            ..Default::default()
+        })
+    }
+
+    async fn complete(&self, variable: &str, value: &str, _context: &HashMap<String, String>) -> Result<Vec<String>> {
+        if variable != "user" {
+            return Ok(Vec::new());
+        }
+        Ok(USERS
+            .iter()
+            .filter(|user| user.starts_with(value))
+            .map(|user| user.to_string())
+            .collect())
+    }
+}
+
+impl Default for MockResourceTemplate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,8 +1,10 @@
 pub mod mock;
+pub mod template;
 
 use async_trait::async_trait;
 use anyhow::Result;
-use crate::core::types::{ResourceMetadata, ResourceContent};
+use std::collections::HashMap;
+use crate::core::types::{ResourceMetadata, ResourceContent, ResourceTemplateMetadata};
 
 /// Resource trait - defines the interface for all MCP resources
 #[async_trait]
@@ -20,4 +22,37 @@ pub trait Resource: Send + Sync {
     
     /// Get resource URI
     async fn uri(&self) -> String;
+
+    /// Release what the resource holds when the server shuts down
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Resource template trait - a family of resources sharing an RFC 6570 URI template
+#[async_trait]
+pub trait ResourceTemplate: Send + Sync {
+    /// Get template metadata, including its `uri_template`
+    async fn metadata(&self) -> Result<ResourceTemplateMetadata>;
+
+    /// Read the resource at `uri`, given the variables extracted from it
+    async fn read(&self, uri: &str, variables: &HashMap<String, String>) -> Result<ResourceContent>;
+
+    /// Suggest values for a template variable, given its partial `value` and
+    /// the variables already filled in
+    ///
+    /// The server caps the suggestions sent to the client.
+    async fn complete(
+        &self,
+        _variable: &str,
+        _value: &str,
+        _context: &HashMap<String, String>,
+    ) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Release what the template holds when the server shuts down
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
 }
@@ -0,0 +1,263 @@
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+
+use crate::core::protocol::percent_decode;
+
+/// A parsed RFC 6570 URI template
+///
+/// Supports the expressions MCP servers use in practice: simple string
+/// expansion (`{var}`), which matches a single path segment, and reserved
+/// expansion (`{+var}`), which may span several segments. Simple values are
+/// percent-decoded when matched, and a URI whose simple value has a malformed
+/// escape doesn't match.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UriTemplate {
+    template: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Variable { name: String, reserved: bool },
+}
+
+impl UriTemplate {
+    /// Parse a URI template such as `db://tables/{name}`
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            let Some(end) = rest[start..].find('}') else {
+                bail!("Unclosed expression in URI template: {}", template);
+            };
+            let expression = &rest[start + 1..start + end];
+            let (name, reserved) = match expression.strip_prefix('+') {
+                Some(name) => (name, true),
+                None => (expression, false),
+            };
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                bail!("Unsupported expression {{{}}} in URI template: {}", expression, template);
+            }
+            if matches!(parts.last(), Some(Part::Variable { .. })) {
+                bail!("Adjacent expressions in URI template: {}", template);
+            }
+            parts.push(Part::Variable {
+                name: name.to_string(),
+                reserved,
+            });
+            rest = &rest[start + end + 1..];
+        }
+        if rest.contains('}') {
+            bail!("Unmatched '}}' in URI template: {}", template);
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        Ok(Self {
+            template: template.to_string(),
+            parts,
+        })
+    }
+
+    /// Get the template string
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    /// Get the names of the template's variables, in order
+    pub fn variables(&self) -> Vec<&str> {
+        self.parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Variable { name, .. } => Some(name.as_str()),
+                Part::Literal(_) => None,
+            })
+            .collect()
+    }
+
+    /// Match a URI against the template, extracting its variables
+    pub fn match_uri(&self, uri: &str) -> Option<HashMap<String, String>> {
+        let mut variables = HashMap::new();
+        if match_parts(&self.parts, uri, &mut variables) {
+            Some(variables)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::fmt::Display for UriTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.template)
+    }
+}
+
+/// Match a URI against a template's parts, filling in `variables`
+///
+/// Each variable takes the longest value that lets the rest of the template
+/// match. Rather than backtracking, which takes time exponential in the
+/// number of `{+var}` expressions, this first works out for every part and
+/// offset whether the rest of the URI matches the rest of the template, then
+/// picks the values in one pass, so the time is linear in the URI's length.
+fn match_parts(parts: &[Part], uri: &str, variables: &mut HashMap<String, String>) -> bool {
+    let n = uri.len();
+
+    // Where each value may end at the latest: simple expansion never
+    // produces '/', so its value ends at the next segment
+    let mut segment_end = vec![n; n + 1];
+    for p in (0..n).rev() {
+        segment_end[p] = if uri.as_bytes()[p] == b'/' { p } else { segment_end[p + 1] };
+    }
+    let value_limit = |p: usize, reserved: bool| if reserved { n } else { segment_end[p] };
+
+    // matches[i][p]: whether parts[i..] match uri[p..]
+    let mut matches = vec![vec![false; n + 1]; parts.len() + 1];
+    matches[parts.len()][n] = true;
+    for (i, part) in parts.iter().enumerate().rev() {
+        let (head, tail) = matches.split_at_mut(i + 1);
+        let (current, next) = (&mut head[i], &tail[0]);
+        match part {
+            Part::Literal(literal) => {
+                for p in (0..=n).filter(|p| uri.is_char_boundary(*p)) {
+                    current[p] = uri[p..].starts_with(literal.as_str()) && next[p + literal.len()];
+                }
+            }
+            Part::Variable { reserved, .. } => {
+                let last = last_true(next);
+                for p in (0..=n).filter(|p| uri.is_char_boundary(*p)) {
+                    // A value is never empty
+                    current[p] = last[value_limit(p, *reserved)].is_some_and(|end| end > p);
+                }
+            }
+        }
+    }
+    if !matches[0][0] {
+        return false;
+    }
+
+    let mut offset = 0;
+    for (i, part) in parts.iter().enumerate() {
+        match part {
+            Part::Literal(literal) => offset += literal.len(),
+            Part::Variable { name, reserved } => {
+                // Prefer the longest value that lets the rest of the template match
+                let limit = value_limit(offset, *reserved);
+                let Some(end) = (offset + 1..=limit).rev().find(|end| matches[i + 1][*end]) else {
+                    return false;
+                };
+                let raw = &uri[offset..end];
+                let value = if *reserved {
+                    raw.to_string()
+                } else {
+                    match percent_decode(raw) {
+                        Some(value) => value,
+                        None => return false,
+                    }
+                };
+                variables.insert(name.clone(), value);
+                offset = end;
+            }
+        }
+    }
+    true
+}
+
+/// For each index, the last index at or before it that is `true`
+fn last_true(flags: &[bool]) -> Vec<Option<usize>> {
+    let mut last = None;
+    flags
+        .iter()
+        .enumerate()
+        .map(|(index, flag)| {
+            if *flag {
+                last = Some(index);
+            }
+            last
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_simple_variable() {
+        let template = UriTemplate::parse("db://tables/{name}").unwrap();
+        let variables = template.match_uri("db://tables/users").unwrap();
+
+        assert_eq!(variables["name"], "users");
+        assert!(template.match_uri("db://tables/").is_none());
+        assert!(template.match_uri("db://views/users").is_none());
+    }
+
+    #[test]
+    fn test_simple_variable_stops_at_slash() {
+        let template = UriTemplate::parse("mailbox://{user}/inbox").unwrap();
+
+        assert_eq!(template.match_uri("mailbox://alice/inbox").unwrap()["user"], "alice");
+        assert!(template.match_uri("mailbox://alice/bob/inbox").is_none());
+    }
+
+    #[test]
+    fn test_reserved_variable_spans_segments() {
+        let template = UriTemplate::parse("file:///{+path}").unwrap();
+        let variables = template.match_uri("file:///home/alice/notes.txt").unwrap();
+
+        assert_eq!(variables["path"], "home/alice/notes.txt");
+    }
+
+    #[test]
+    fn test_reserved_variables_match_longest_first() {
+        let template = UriTemplate::parse("file:///{+dir}/{+name}.txt").unwrap();
+        let variables = template.match_uri("file:///a/b/c.d.txt").unwrap();
+
+        assert_eq!(variables["dir"], "a/b");
+        assert_eq!(variables["name"], "c.d");
+    }
+
+    #[test]
+    fn test_many_reserved_expressions_match_quickly() {
+        // Trying every split in turn takes time exponential in the number of
+        // reserved expressions
+        let template = UriTemplate::parse("x://{+a}/{+b}/{+c}/{+d}/{+e}/{+f}/end").unwrap();
+        let uri = format!("x://{}", "/".repeat(100_000));
+
+        let started = std::time::Instant::now();
+        assert!(template.match_uri(&uri).is_none());
+        assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_multiple_variables() {
+        let template = UriTemplate::parse("repo://{owner}/{repo}/issues/{number}").unwrap();
+        let variables = template.match_uri("repo://gnufoo/MeCP/issues/42").unwrap();
+
+        assert_eq!(variables["owner"], "gnufoo");
+        assert_eq!(variables["repo"], "MeCP");
+        assert_eq!(variables["number"], "42");
+        assert_eq!(template.variables(), vec!["owner", "repo", "number"]);
+    }
+
+    #[test]
+    fn test_simple_variable_is_percent_decoded() {
+        let template = UriTemplate::parse("search://{query}").unwrap();
+
+        assert_eq!(template.match_uri("search://rust%20async").unwrap()["query"], "rust async");
+        assert!(template.match_uri("search://100%").is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_templates() {
+        assert!(UriTemplate::parse("db://tables/{name").is_err());
+        assert!(UriTemplate::parse("db://tables/{}").is_err());
+        assert!(UriTemplate::parse("db://{?query}").is_err());
+        assert!(UriTemplate::parse("db://{a}{b}").is_err());
+    }
+}
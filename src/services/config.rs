@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
 use anyhow::{Result, Context};
 
+use crate::core::cache::ToolCache;
+use crate::core::limits::ToolLimits;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceConfig {
     pub mysql: MySqlConfig,
@@ -12,6 +17,12 @@ pub struct ServiceConfig {
     pub services: ServicePaths,
     #[serde(default)]
     pub auth: Option<AuthConfig>,
+    #[serde(default)]
+    pub tools: ToolsConfig,
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
+    #[serde(default)]
+    pub redis: RedisConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +33,17 @@ pub struct AuthConfig {
     pub session_duration: i64,
 }
 
+/// An API key MCP clients authenticate with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyConfig {
+    pub key: String,
+    /// The user calls made with the key are attributed to
+    pub user: String,
+    /// Refuse tools not annotated as read-only to this key
+    #[serde(default)]
+    pub read_only: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MySqlConfig {
     pub enabled: bool,
@@ -35,6 +57,29 @@ pub struct MySqlConfig {
     pub connect_timeout: u64,
 }
 
+/// Redis backing the key-value storage of WebAssembly components
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub database: u32,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl Default for RedisConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 6379,
+            database: 0,
+            password: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Neo4jConfig {
     pub enabled: bool,
@@ -64,6 +109,98 @@ pub struct ServerConfig {
     pub host: String,
     pub port: u16,
     pub log_level: String,
+    /// Maximum number of items per page of tools/list, resources/list and prompts/list
+    #[serde(default = "default_page_size")]
+    pub page_size: usize,
+    /// Seconds in-flight tool calls and metrics writes get to finish on shutdown
+    #[serde(default = "default_shutdown_grace_secs")]
+    pub shutdown_grace_secs: u64,
+    /// Seconds an HTTP session may go without requests before it is closed
+    #[serde(default = "default_session_ttl_secs")]
+    pub session_ttl_secs: u64,
+}
+
+fn default_page_size() -> usize {
+    crate::core::pagination::DEFAULT_PAGE_SIZE
+}
+
+fn default_shutdown_grace_secs() -> u64 {
+    crate::core::shutdown::DEFAULT_SHUTDOWN_GRACE.as_secs()
+}
+
+fn default_session_ttl_secs() -> u64 {
+    crate::core::session::DEFAULT_SESSION_TTL.as_secs()
+}
+
+/// Limits on tool calls, with overrides per tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolsConfig {
+    /// Limits of tools without an entry in `limits`
+    #[serde(flatten)]
+    pub defaults: ToolLimitsConfig,
+    /// Overrides keyed by tool name; unset fields fall back to the defaults
+    #[serde(default)]
+    pub limits: HashMap<String, ToolLimitsConfig>,
+    #[serde(default)]
+    pub cache: ToolCacheConfig,
+}
+
+/// Result cache for tools that declare themselves cacheable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCacheConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Maximum number of results held; the least recently used are evicted
+    #[serde(default = "default_cache_capacity")]
+    pub capacity: usize,
+    /// TTLs keyed by tool name, overriding the ones tools declare; 0 turns
+    /// caching off for a tool
+    #[serde(default)]
+    pub ttl_secs: HashMap<String, u64>,
+}
+
+fn default_cache_capacity() -> usize {
+    crate::core::cache::DEFAULT_CACHE_CAPACITY
+}
+
+impl Default for ToolCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: default_cache_capacity(),
+            ttl_secs: HashMap::new(),
+        }
+    }
+}
+
+impl ToolCacheConfig {
+    /// Build the cache this section describes
+    pub fn to_cache(&self) -> ToolCache {
+        self.ttl_secs
+            .iter()
+            .fold(ToolCache::new(self.capacity), |cache, (tool, secs)| {
+                cache.with_ttl(tool, Duration::from_secs(*secs))
+            })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolLimitsConfig {
+    /// Seconds a call may take before it fails with a timeout
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    /// Maximum number of calls of the tool running at once
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<usize>,
+}
+
+impl ToolLimitsConfig {
+    pub fn to_limits(&self) -> ToolLimits {
+        ToolLimits {
+            timeout: self.timeout_secs.map(Duration::from_secs),
+            max_concurrency: self.max_concurrency,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -144,6 +281,9 @@ impl Default for ServiceConfig {
                 host: "0.0.0.0".to_string(),
                 port: 8080,
                 log_level: "info".to_string(),
+                page_size: default_page_size(),
+                shutdown_grace_secs: default_shutdown_grace_secs(),
+                session_ttl_secs: default_session_ttl_secs(),
             },
             services: ServicePaths {
                 mysql_service: "mysql".to_string(),
@@ -154,6 +294,43 @@ impl Default for ServiceConfig {
                 backup_dir: "./backups".to_string(),
             },
             auth: None,
+            tools: ToolsConfig::default(),
+            api_keys: Vec::new(),
+            redis: RedisConfig::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_limits_from_toml() {
+        let tools: ToolsConfig = toml::from_str(
+            r#"
+            timeout_secs = 60
+
+            [limits.render_chart]
+            max_concurrency = 4
+
+            [cache]
+            enabled = true
+
+            [cache.ttl_secs]
+            fetch = 0
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(tools.defaults.to_limits().timeout, Some(Duration::from_secs(60)));
+        let chart = tools.limits["render_chart"].to_limits();
+        assert_eq!(chart.timeout, None);
+        assert_eq!(chart.max_concurrency, Some(4));
+
+        assert!(tools.cache.enabled);
+        assert_eq!(tools.cache.capacity, crate::core::cache::DEFAULT_CACHE_CAPACITY);
+        let cache = tools.cache.to_cache();
+        assert_eq!(cache.ttl("fetch", Some(Duration::from_secs(60))), None);
+    }
+}
@@ -15,10 +15,10 @@ impl MilvusService {
     /// Check if Milvus is installed (via Docker)
     pub fn is_installed(&self) -> bool {
         // Check if Docker is available
-        if !Command::new("docker")
+        if Command::new("docker")
             .arg("--version")
             .output()
-            .is_ok()
+            .is_err()
         {
             return false;
         }
@@ -45,10 +45,10 @@ impl MilvusService {
         println!("📦 Installing Milvus...");
         
         // Check if Docker is installed
-        if !Command::new("docker")
+        if Command::new("docker")
             .arg("--version")
             .output()
-            .is_ok()
+            .is_err()
         {
             bail!("Docker is not installed. Please install Docker first.\nSee: https://docs.docker.com/get-docker/");
         }
@@ -0,0 +1,124 @@
+//! Input schemas for tools defined with the `#[tool]` macro
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::core::types::JsonValue;
+
+/// A type that can describe itself as a JSON Schema
+///
+/// Implemented for the primitive types, `Option`, `Vec`, string-keyed maps
+/// and `JsonValue`; derive it for argument structs and unit-only enums with
+/// `#[derive(InputSchema)]`.
+pub trait InputSchema {
+    /// Get the JSON Schema of the type
+    fn schema() -> JsonValue;
+
+    /// Whether a property of this type must be present
+    fn required() -> bool {
+        true
+    }
+}
+
+macro_rules! impl_input_schema {
+    ($schema:tt => $($ty:ty),+) => {
+        $(impl InputSchema for $ty {
+            fn schema() -> JsonValue {
+                json!($schema)
+            }
+        })+
+    };
+}
+
+impl_input_schema!({"type": "string"} => String, char);
+impl_input_schema!({"type": "boolean"} => bool);
+impl_input_schema!({"type": "integer", "minimum": i8::MIN, "maximum": i8::MAX} => i8);
+impl_input_schema!({"type": "integer", "minimum": i16::MIN, "maximum": i16::MAX} => i16);
+impl_input_schema!({"type": "integer", "minimum": i32::MIN, "maximum": i32::MAX} => i32);
+impl_input_schema!({"type": "integer"} => i64, isize);
+impl_input_schema!({"type": "integer", "minimum": 0, "maximum": u8::MAX} => u8);
+impl_input_schema!({"type": "integer", "minimum": 0, "maximum": u16::MAX} => u16);
+impl_input_schema!({"type": "integer", "minimum": 0, "maximum": u32::MAX} => u32);
+impl_input_schema!({"type": "integer", "minimum": 0} => u64, usize);
+impl_input_schema!({"type": "number"} => f32, f64);
+impl_input_schema!({} => JsonValue);
+
+impl<T: InputSchema> InputSchema for Option<T> {
+    /// `T`'s schema, also allowing `null` since serde reads it as `None`
+    fn schema() -> JsonValue {
+        nullable(T::schema())
+    }
+
+    fn required() -> bool {
+        false
+    }
+}
+
+impl<T: InputSchema> InputSchema for Vec<T> {
+    fn schema() -> JsonValue {
+        json!({"type": "array", "items": T::schema()})
+    }
+}
+
+impl<T: InputSchema> InputSchema for HashMap<String, T> {
+    fn schema() -> JsonValue {
+        json!({"type": "object", "additionalProperties": T::schema()})
+    }
+}
+
+impl<T: InputSchema> InputSchema for BTreeMap<String, T> {
+    fn schema() -> JsonValue {
+        json!({"type": "object", "additionalProperties": T::schema()})
+    }
+}
+
+/// Widen a schema to also accept `null`
+///
+/// Adds `"null"` to its `type` and `enum` where it has them, so object
+/// schemas keep their properties at the top level; schemas that say
+/// nothing about the type are wrapped in an `anyOf`.
+fn nullable(mut schema: JsonValue) -> JsonValue {
+    let Some(object) = schema.as_object_mut() else {
+        return schema;
+    };
+    if object.is_empty() {
+        return schema;
+    }
+
+    match object.get_mut("type") {
+        Some(JsonValue::String(name)) => {
+            let name = std::mem::take(name);
+            object.insert("type".to_string(), json!([name, "null"]));
+        }
+        Some(JsonValue::Array(types)) if !types.contains(&json!("null")) => types.push(json!("null")),
+        Some(_) => {}
+        None => return json!({"anyOf": [schema, {"type": "null"}]}),
+    }
+    if let Some(JsonValue::Array(options)) = object.get_mut("enum") {
+        if !options.contains(&JsonValue::Null) {
+            options.push(JsonValue::Null);
+        }
+    }
+    if object.contains_key("const") {
+        return json!({"anyOf": [schema, {"type": "null"}]});
+    }
+    schema
+}
+
+/// Add a description to a schema
+#[doc(hidden)]
+pub fn describe(mut schema: JsonValue, description: Option<&str>) -> JsonValue {
+    if let (Some(description), Some(object)) = (description, schema.as_object_mut()) {
+        object.insert("description".to_string(), json!(description));
+    }
+    schema
+}
+
+/// Deserialize one named argument from a tool's parameters
+#[doc(hidden)]
+pub fn argument<T: DeserializeOwned>(params: &JsonValue, name: &str) -> Result<T> {
+    let value = params.get(name).cloned().unwrap_or(JsonValue::Null);
+    serde_json::from_value(value).with_context(|| format!("Invalid argument '{}'", name))
+}
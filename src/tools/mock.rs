@@ -1,9 +1,12 @@
 use async_trait::async_trait;
 use anyhow::Result;
 use serde_json::json;
+use std::time::Duration;
 
+use crate::core::context::RequestContext;
+use crate::core::protocol::{CreateMessageParams, SamplingMessage, ToolAnnotations, ToolContent};
 use crate::core::types::{ToolParameter, ToolResult, JsonValue};
-use crate::tools::{Tool, ToolMetadata};
+use crate::tools::{tool, Tool, ToolMetadata};
 
 /// Hello World tool - a simple mock tool implementation
 pub struct HelloWorldTool {
@@ -32,6 +35,12 @@ impl Tool for HelloWorldTool {
                     param_type: "string".to_string(),
                 },
             ],
+            annotations: Some(ToolAnnotations {
+                title: Some("Hello World".to_string()),
+                open_world_hint: Some(false),
+                ..ToolAnnotations::read_only()
+            }),
+            ..Default::default()
         })
     }
 
@@ -50,6 +59,7 @@ impl Tool for HelloWorldTool {
                 "timestamp": chrono::Utc::now().to_rfc3339()
             }),
             error: None,
+            ..Default::default()
         })
     }
 }
@@ -94,6 +104,12 @@ impl Tool for SearchTool {
                     param_type: "number".to_string(),
                 },
             ],
+            cache_ttl: Some(Duration::from_secs(300)),
+            annotations: Some(ToolAnnotations {
+                title: Some("Search".to_string()),
+                ..ToolAnnotations::read_only()
+            }),
+            ..Default::default()
         })
     }
 
@@ -154,6 +170,7 @@ impl Tool for SearchTool {
                 "timestamp": chrono::Utc::now().to_rfc3339()
             }),
             error: None,
+            ..Default::default()
         })
     }
 }
@@ -192,6 +209,12 @@ impl Tool for FetchTool {
                     param_type: "string".to_string(),
                 },
             ],
+            cache_ttl: Some(Duration::from_secs(60)),
+            annotations: Some(ToolAnnotations {
+                title: Some("Fetch URL".to_string()),
+                ..ToolAnnotations::read_only()
+            }),
+            ..Default::default()
         })
     }
 
@@ -246,6 +269,7 @@ impl Tool for FetchTool {
                 "status": "success"
             }),
             error: None,
+            ..Default::default()
         })
     }
 }
@@ -255,3 +279,241 @@ impl Default for FetchTool {
         Self::new()
     }
 }
+
+/// Long task tool - works through a number of steps, reporting progress
+pub struct LongTaskTool {
+    name: String,
+}
+
+impl LongTaskTool {
+    pub fn new() -> Self {
+        Self {
+            name: "long_task".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for LongTaskTool {
+    async fn metadata(&self) -> Result<ToolMetadata> {
+        Ok(ToolMetadata {
+            name: self.name.clone(),
+            description: "Simulates a long-running job that reports progress".to_string(),
+            parameters: vec![
+                ToolParameter {
+                    name: "steps".to_string(),
+                    description: "Number of steps to run".to_string(),
+                    required: false,
+                    param_type: "integer".to_string(),
+                },
+                ToolParameter {
+                    name: "delay_ms".to_string(),
+                    description: "Time each step takes in milliseconds".to_string(),
+                    required: false,
+                    param_type: "integer".to_string(),
+                },
+            ],
+            annotations: Some(ToolAnnotations {
+                open_world_hint: Some(false),
+                ..ToolAnnotations::read_only()
+            }),
+            ..Default::default()
+        })
+    }
+
+    async fn execute(&self, params: JsonValue) -> Result<ToolResult> {
+        self.execute_with_context(params, &RequestContext::default()).await
+    }
+
+    async fn execute_with_context(&self, params: JsonValue, ctx: &RequestContext) -> Result<ToolResult> {
+        let steps = params.get("steps").and_then(|v| v.as_u64()).unwrap_or(5);
+        let delay_ms = params.get("delay_ms").and_then(|v| v.as_u64()).unwrap_or(100);
+        let logger = ctx.logger(&self.name);
+
+        logger.info(format!("Starting {} steps", steps)).await;
+        for step in 1..=steps {
+            tokio::time::sleep(std::time::Duration::from_millis(delay_ms)).await;
+            ctx.report_progress(step as f64, Some(steps as f64), Some(&format!("Step {} of {}", step, steps)))
+                .await;
+            logger.debug(json!({"step": step, "of": steps})).await;
+        }
+
+        Ok(ToolResult {
+            success: true,
+            output: json!({
+                "steps_completed": steps
+            }),
+            error: None,
+            ..Default::default()
+        })
+    }
+}
+
+impl Default for LongTaskTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Chart tool - renders a bar chart as an SVG image with summary statistics
+pub struct ChartTool {
+    name: String,
+}
+
+impl ChartTool {
+    pub fn new() -> Self {
+        Self {
+            name: "render_chart".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Tool for ChartTool {
+    async fn metadata(&self) -> Result<ToolMetadata> {
+        Ok(ToolMetadata {
+            name: self.name.clone(),
+            description: "Renders a list of numbers as a bar chart".to_string(),
+            parameters: Vec::new(),
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "values": {
+                        "type": "array",
+                        "description": "Bar heights",
+                        "items": {"type": "number", "minimum": 0},
+                        "minItems": 1
+                    },
+                    "title": {
+                        "type": "string",
+                        "description": "Chart title",
+                        "default": "Chart"
+                    }
+                },
+                "required": ["values"],
+                "additionalProperties": false
+            })),
+            output_schema: Some(json!({
+                "type": "object",
+                "properties": {
+                    "title": {"type": "string"},
+                    "count": {"type": "integer", "minimum": 0},
+                    "min": {"type": ["number", "null"]},
+                    "max": {"type": ["number", "null"]}
+                },
+                "required": ["title", "count", "min", "max"]
+            })),
+            annotations: Some(ToolAnnotations {
+                title: Some("Bar Chart".to_string()),
+                open_world_hint: Some(false),
+                ..ToolAnnotations::read_only()
+            }),
+            ..Default::default()
+        })
+    }
+
+    async fn execute(&self, params: JsonValue) -> Result<ToolResult> {
+        let title = params.get("title").and_then(|v| v.as_str()).unwrap_or("Chart");
+        let values: Vec<f64> = params
+            .get("values")
+            .and_then(|v| v.as_array())
+            .map(|values| values.iter().filter_map(|v| v.as_f64()).collect())
+            .unwrap_or_default();
+
+        let min = values.iter().copied().reduce(f64::min);
+        let max = values.iter().copied().reduce(f64::max);
+        let scale = max.filter(|m| *m > 0.0).unwrap_or(1.0);
+
+        let bars: String = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let height = (v.max(0.0) / scale * 100.0).round();
+                format!(
+                    r#"<rect x="{}" y="{}" width="16" height="{}"/>"#,
+                    i * 20,
+                    100.0 - height,
+                    height
+                )
+            })
+            .collect();
+        let svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="100">{}</svg>"#,
+            values.len().max(1) * 20,
+            bars
+        );
+
+        Ok(ToolResult {
+            success: true,
+            output: JsonValue::Null,
+            error: None,
+            content: vec![
+                ToolContent::text(format!("{}: {} values", title, values.len())),
+                ToolContent::image(svg.as_bytes(), "image/svg+xml"),
+            ],
+            structured_content: Some(json!({
+                "title": title,
+                "count": values.len(),
+                "min": min,
+                "max": max
+            })),
+        })
+    }
+}
+
+impl Default for ChartTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Most repetitions `echo` produces, so a client can't make it allocate
+/// without bound
+pub const MAX_ECHO_TIMES: u32 = 100;
+
+/// Repeats a message a number of times
+#[tool(read_only_hint, open_world_hint = false)]
+pub async fn echo(
+    #[arg(description = "Message to repeat")] message: String,
+    #[arg(description = "How many times to repeat it, at most 100")] times: Option<u32>,
+) -> Result<String> {
+    let times = times.unwrap_or(1).min(MAX_ECHO_TIMES);
+    Ok(vec![message; times as usize].join(" "))
+}
+
+/// Summarizes text with the client's own model
+#[tool(read_only_hint)]
+pub async fn summarize(
+    #[arg(description = "Text to summarize")] text: String,
+    #[arg(description = "Longest summary, in tokens")] max_tokens: Option<u32>,
+    ctx: &RequestContext,
+) -> Result<String> {
+    let request = CreateMessageParams::new(vec![SamplingMessage::user(text)], max_tokens.unwrap_or(200))
+        .with_system_prompt("Summarize the user's text in a few sentences.");
+    let reply = ctx.create_message(request).await?;
+
+    reply
+        .text()
+        .map(String::from)
+        .ok_or_else(|| anyhow::anyhow!("{} answered without text", reply.model))
+}
+
+/// Lists the files in a directory the client has exposed as a root
+#[tool(read_only_hint, open_world_hint = false)]
+pub async fn list_files(
+    #[arg(description = "Directory to list, as a file:// URI")] uri: String,
+    ctx: &RequestContext,
+) -> Result<JsonValue> {
+    if !uri.starts_with("file://") {
+        anyhow::bail!("Only file:// URIs can be listed");
+    }
+    let path = ctx.resolve_within_roots(&uri).await?;
+
+    let mut names = Vec::new();
+    let mut entries = tokio::fs::read_dir(&path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    names.sort();
+    Ok(json!({ "uri": uri, "files": names }))
+}
@@ -1,15 +1,113 @@
+pub mod input;
 pub mod mock;
 
+pub use input::InputSchema;
+pub use mecp_macros::{tool, InputSchema};
+
 use async_trait::async_trait;
 use anyhow::Result;
+use serde_json::json;
+use std::time::Duration;
+use crate::core::context::RequestContext;
+use crate::core::protocol::{ToolAnnotations, ToolContent};
+use crate::core::schema::SchemaError;
 use crate::core::types::{ToolParameter, ToolResult, JsonValue};
 
 /// Tool metadata
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ToolMetadata {
     pub name: String,
     pub description: String,
+    /// Flat parameter list, used to build the input schema if none is declared
     pub parameters: Vec<ToolParameter>,
+    /// JSON Schema the tool's arguments must conform to
+    pub input_schema: Option<JsonValue>,
+    /// JSON Schema the tool's structured content must conform to
+    pub output_schema: Option<JsonValue>,
+    /// How long results may be reused for the same arguments, if the server
+    /// has a result cache; `None` if every call must run the tool
+    ///
+    /// Cached results are kept per user, but are otherwise shared across
+    /// sessions, so tools that depend on a session's roots or client must
+    /// leave this unset.
+    pub cache_ttl: Option<Duration>,
+    /// Behavioural hints shown to clients in `tools/list`
+    pub annotations: Option<ToolAnnotations>,
+}
+
+impl ToolMetadata {
+    /// Get the JSON Schema of the tool's arguments
+    ///
+    /// Falls back to an object schema built from `parameters`.
+    pub fn schema(&self) -> JsonValue {
+        if let Some(ref schema) = self.input_schema {
+            return schema.clone();
+        }
+
+        json!({
+            "type": "object",
+            "properties": self.parameters.iter().map(|p| {
+                (p.name.clone(), json!({
+                    "type": p.param_type,
+                    "description": p.description
+                }))
+            }).collect::<serde_json::Map<String, JsonValue>>(),
+            "required": self.parameters.iter()
+                .filter(|p| p.required)
+                .map(|p| p.name.clone())
+                .collect::<Vec<_>>()
+        })
+    }
+}
+
+/// A value a `#[tool]` function can return
+pub trait IntoToolResult {
+    fn into_tool_result(self) -> ToolResult;
+}
+
+impl IntoToolResult for ToolResult {
+    fn into_tool_result(self) -> ToolResult {
+        self
+    }
+}
+
+impl IntoToolResult for String {
+    fn into_tool_result(self) -> ToolResult {
+        ToolResult {
+            success: true,
+            content: vec![ToolContent::text(self.clone())],
+            output: JsonValue::String(self),
+            ..Default::default()
+        }
+    }
+}
+
+impl IntoToolResult for JsonValue {
+    fn into_tool_result(self) -> ToolResult {
+        ToolResult {
+            success: true,
+            output: self,
+            ..Default::default()
+        }
+    }
+}
+
+impl IntoToolResult for Vec<ToolContent> {
+    fn into_tool_result(self) -> ToolResult {
+        ToolResult {
+            success: true,
+            content: self,
+            ..Default::default()
+        }
+    }
+}
+
+/// Tool arguments rejected before the tool ran
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid arguments for tool {tool}: {error}")]
+pub struct InvalidArguments {
+    pub tool: String,
+    pub error: SchemaError,
 }
 
 /// Tool trait - defines the interface for all MCP tools
@@ -20,10 +118,26 @@ pub trait Tool: Send + Sync {
     
     /// Execute the tool with given parameters
     async fn execute(&self, params: JsonValue) -> Result<ToolResult>;
+
+    /// Execute the tool with access to the calling request
+    ///
+    /// Long-running tools override this to report progress or stop early
+    /// when the client cancels. Dropping the returned future aborts the call.
+    async fn execute_with_context(&self, params: JsonValue, _ctx: &RequestContext) -> Result<ToolResult> {
+        self.execute(params).await
+    }
     
     /// Validate tool parameters
+    ///
+    /// Called after the arguments passed the input schema, for checks a
+    /// schema can't express.
     async fn validate(&self, params: &JsonValue) -> Result<bool> {
         // Default implementation - can be overridden
         Ok(params.is_object())
     }
+
+    /// Release what the tool holds when the server shuts down
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
 }
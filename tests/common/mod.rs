@@ -22,13 +22,36 @@ lazy_static! {
                 // Register mock components
                 server
                     .register_resource(Box::new(mecp::resources::mock::MockResource::new()))
-                    .await;
+                    .await
+                    .unwrap();
+                server
+                    .register_resource(Box::new(mecp::resources::mock::MockImageResource::new()))
+                    .await
+                    .unwrap();
+                server
+                    .register_resource_template(Box::new(mecp::resources::mock::MockResourceTemplate::new()))
+                    .await
+                    .unwrap();
                 server
                     .register_tool(Box::new(mecp::tools::mock::HelloWorldTool::new()))
-                    .await;
+                    .await
+                    .unwrap();
+                server
+                    .register_tool(Box::new(mecp::tools::mock::LongTaskTool::new()))
+                    .await
+                    .unwrap();
+                server
+                    .register_tool(Box::new(mecp::tools::mock::ChartTool::new()))
+                    .await
+                    .unwrap();
+                server
+                    .register_tool(Box::new(mecp::tools::mock::SummarizeTool))
+                    .await
+                    .unwrap();
                 server
                     .register_prompt(Box::new(mecp::prompts::mock::MockPrompt::new()))
-                    .await;
+                    .await
+                    .unwrap();
                 
                 // Start HTTP server
                 let http_server = mecp::core::http_server::HttpServer::new(server, "127.0.0.1".to_string(), TEST_SERVER_PORT);
@@ -118,6 +141,86 @@ impl TestClient {
         response.json().await.expect("Failed to parse response")
     }
 
+    /// Get the underlying HTTP client
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Get the server base URL
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Build a JSON-RPC request body with a fresh ID
+    pub fn request_body(method: &str, params: Option<Value>) -> Value {
+        let id = REQUEST_ID.fetch_add(1, Ordering::SeqCst);
+
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        })
+    }
+
+    /// POST a raw body to /mcp with extra headers
+    pub async fn post_mcp(&self, body: Value, headers: &[(&str, &str)]) -> reqwest::Response {
+        let mut request = self
+            .client
+            .post(format!("{}/mcp", self.base_url))
+            .json(&body)
+            .timeout(Duration::from_secs(10));
+
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        request.send().await.expect("Failed to send request")
+    }
+
+    /// Send an HTTP request with the given method to a path
+    pub async fn request(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        headers: &[(&str, &str)],
+    ) -> reqwest::Response {
+        let mut request = self
+            .client
+            .request(method, format!("{}{}", self.base_url, path))
+            .timeout(Duration::from_secs(10));
+
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        request.send().await.expect("Failed to send request")
+    }
+
+    /// Initialize a Streamable HTTP session and return its ID
+    pub async fn initialize_session(&self) -> String {
+        let body = Self::request_body(
+            "initialize",
+            Some(json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": {},
+                "clientInfo": {
+                    "name": "test-client",
+                    "version": "1.0.0"
+                }
+            })),
+        );
+
+        let response = self.post_mcp(body, &[]).await;
+        response
+            .headers()
+            .get("mcp-session-id")
+            .expect("initialize should return a session ID")
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
     /// Health check
     pub async fn health_check(&self) -> Value {
         let response = self
@@ -192,3 +295,58 @@ impl TestClient {
         .await
     }
 }
+
+/// Incremental reader for a Server-Sent Events response
+pub struct SseReader {
+    response: reqwest::Response,
+    buffer: String,
+}
+
+impl SseReader {
+    pub fn new(response: reqwest::Response) -> Self {
+        Self {
+            response,
+            buffer: String::new(),
+        }
+    }
+
+    /// Read the next event as `(event, data)`, skipping keep-alive comments
+    pub async fn next_event(&mut self) -> Option<(String, String)> {
+        loop {
+            if let Some(end) = self.buffer.find("\n\n") {
+                let block: String = self.buffer.drain(..end + 2).collect();
+                let mut event = "message".to_string();
+                let mut data = Vec::new();
+
+                for line in block.lines() {
+                    if let Some(value) = line.strip_prefix("event:") {
+                        event = value.trim().to_string();
+                    } else if let Some(value) = line.strip_prefix("data:") {
+                        data.push(value.trim_start().to_string());
+                    }
+                }
+
+                if data.is_empty() {
+                    continue;
+                }
+                return Some((event, data.join("\n")));
+            }
+
+            let chunk = tokio::time::timeout(Duration::from_secs(10), self.response.chunk())
+                .await
+                .ok()?
+                .ok()??;
+            self.buffer.push_str(&String::from_utf8_lossy(&chunk));
+        }
+    }
+
+    /// Read the next `message` event and parse it as JSON
+    pub async fn next_message(&mut self) -> Option<Value> {
+        loop {
+            let (event, data) = self.next_event().await?;
+            if event == "message" {
+                return serde_json::from_str(&data).ok();
+            }
+        }
+    }
+}
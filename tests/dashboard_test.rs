@@ -122,7 +122,7 @@ async fn test_endpoint_metrics_multiple_methods() {
     let collector = MetricsCollector::new();
     
     // Record calls to different methods
-    let methods = vec!["initialize", "resources/list", "tools/call", "prompts/get"];
+    let methods = ["initialize", "resources/list", "tools/call", "prompts/get"];
     
     for method in methods.iter() {
         let log = ApiCallLog {
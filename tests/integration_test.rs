@@ -30,6 +30,64 @@ async fn test_initialize() {
     assert_eq!(result["serverInfo"]["name"], "MeCP");
 }
 
+#[tokio::test]
+async fn test_initialize_negotiates_protocol_version() {
+    let client = TestClient::new().await;
+
+    for (requested, expected) in [
+        ("2025-03-26", "2025-03-26"),
+        ("2099-01-01", "2025-06-18"),
+    ] {
+        let body = TestClient::request_body(
+            "initialize",
+            Some(json!({
+                "protocolVersion": requested,
+                "capabilities": {"sampling": {}, "roots": {"listChanged": true}},
+                "clientInfo": {"name": "test-client", "version": "1.0.0"}
+            })),
+        );
+        let response: serde_json::Value = client.post_mcp(body, &[]).await.json().await.unwrap();
+
+        assert_eq!(response["result"]["protocolVersion"], expected);
+    }
+}
+
+#[tokio::test]
+async fn test_initialize_advertises_registered_capabilities() {
+    let client = TestClient::new().await;
+
+    let response = client.initialize().await;
+    let capabilities = &response["result"]["capabilities"];
+
+    assert_eq!(capabilities["resources"]["subscribe"], true);
+    assert_eq!(capabilities["resources"]["listChanged"], true);
+    assert_eq!(capabilities["tools"]["listChanged"], true);
+    assert_eq!(capabilities["prompts"]["listChanged"], true);
+    assert_eq!(capabilities["completions"], json!({}));
+    assert_eq!(capabilities["logging"], json!({}));
+}
+
+#[tokio::test]
+async fn test_initialize_invalid_params() {
+    let client = TestClient::new().await;
+
+    let response = client.send_request("initialize", Some(json!({"capabilities": {}}))).await;
+
+    assert_eq!(response["error"]["code"], -32602);
+}
+
+#[tokio::test]
+async fn test_unsupported_protocol_version_header_rejected() {
+    let client = TestClient::new().await;
+
+    let body = TestClient::request_body("tools/list", None);
+    let response = client
+        .post_mcp(body, &[("MCP-Protocol-Version", "1999-01-01")])
+        .await;
+
+    assert_eq!(response.status(), 400);
+}
+
 #[tokio::test]
 async fn test_list_resources() {
     let client = TestClient::new().await;
@@ -41,7 +99,7 @@ async fn test_list_resources() {
     
     let resources = &response["result"]["resources"];
     assert!(resources.is_array());
-    assert!(resources.as_array().unwrap().len() > 0);
+    assert!(!resources.as_array().unwrap().is_empty());
     
     // Check mock resource is present
     let resource = &resources[0];
@@ -68,13 +126,116 @@ async fn test_read_resource() {
     
     let contents = &response["result"]["contents"];
     assert!(contents.is_array());
-    assert!(contents.as_array().unwrap().len() > 0);
+    assert!(!contents.as_array().unwrap().is_empty());
     
     let content = &contents[0];
     assert_eq!(content["uri"], uri);
     assert!(content["text"].is_string());
 }
 
+#[tokio::test]
+async fn test_read_resource_uses_declared_mime_type() {
+    let client = TestClient::new().await;
+
+    let response = client.read_resource("mock://example/resource").await;
+
+    let content = &response["result"]["contents"][0];
+    assert_eq!(content["mimeType"], "application/json");
+    assert!(content.get("blob").is_none());
+}
+
+#[tokio::test]
+async fn test_read_binary_resource() {
+    let client = TestClient::new().await;
+
+    let response = client.read_resource("mock://images/pixel.png").await;
+
+    let contents = response["result"]["contents"].as_array().unwrap();
+    assert_eq!(contents.len(), 1);
+    assert_eq!(contents[0]["uri"], "mock://images/pixel.png");
+    assert_eq!(contents[0]["mimeType"], "image/png");
+    assert!(contents[0]["blob"].as_str().unwrap().starts_with("iVBORw0KGgo")); // PNG signature
+    assert!(contents[0].get("text").is_none());
+}
+
+#[tokio::test]
+async fn test_list_resource_templates() {
+    let client = TestClient::new().await;
+
+    let response = client.send_request("resources/templates/list", None).await;
+
+    let templates = response["result"]["resourceTemplates"].as_array().unwrap();
+    assert!(templates
+        .iter()
+        .any(|t| t["uriTemplate"] == "mock://users/{user}/profile" && t["name"] == "mock_user_profile"));
+}
+
+#[tokio::test]
+async fn test_read_templated_resource() {
+    let client = TestClient::new().await;
+
+    let response = client.read_resource("mock://users/alice/profile").await;
+
+    let contents = &response["result"]["contents"][0];
+    assert_eq!(contents["uri"], "mock://users/alice/profile");
+    let data: serde_json::Value = serde_json::from_str(contents["text"].as_str().unwrap()).unwrap();
+    assert_eq!(data["user"], "alice");
+}
+
+#[tokio::test]
+async fn test_read_unmatched_resource() {
+    let client = TestClient::new().await;
+
+    let response = client.read_resource("mock://users/alice/settings").await;
+
+    assert_eq!(response["error"]["code"], -32002);
+    assert_eq!(response["error"]["data"]["uri"], "mock://users/alice/settings");
+}
+
+#[tokio::test]
+async fn test_subscribe_and_unsubscribe() {
+    let client = TestClient::new().await;
+    let session_id = client.initialize_session().await;
+    let headers = [("mcp-session-id", session_id.as_str())];
+
+    for uri in ["mock://example/resource", "mock://users/alice/profile"] {
+        let body = TestClient::request_body("resources/subscribe", Some(json!({"uri": uri})));
+        let response: serde_json::Value = client.post_mcp(body, &headers).await.json().await.unwrap();
+        assert_eq!(response["result"], json!({}), "{}", uri);
+    }
+
+    let body = TestClient::request_body("resources/unsubscribe", Some(json!({"uri": "mock://example/resource"})));
+    let response: serde_json::Value = client.post_mcp(body, &headers).await.json().await.unwrap();
+    assert_eq!(response["result"], json!({}));
+}
+
+#[tokio::test]
+async fn test_subscribe_unknown_resource() {
+    let client = TestClient::new().await;
+    let session_id = client.initialize_session().await;
+
+    let body = TestClient::request_body("resources/subscribe", Some(json!({"uri": "mock://nowhere"})));
+    let response: serde_json::Value = client
+        .post_mcp(body, &[("mcp-session-id", &session_id)])
+        .await
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(response["error"]["code"], -32002);
+}
+
+#[tokio::test]
+async fn test_subscribe_requires_session() {
+    let client = TestClient::new().await;
+
+    let response = client
+        .send_request("resources/subscribe", Some(json!({"uri": "mock://example/resource"})))
+        .await;
+
+    assert_eq!(response["error"]["code"], -32600);
+}
+
 #[tokio::test]
 async fn test_list_tools() {
     let client = TestClient::new().await;
@@ -86,13 +247,36 @@ async fn test_list_tools() {
     
     let tools = &response["result"]["tools"];
     assert!(tools.is_array());
-    assert!(tools.as_array().unwrap().len() > 0);
+    assert!(!tools.as_array().unwrap().is_empty());
     
     // Check hello_world tool is present
     let tool = &tools[0];
     assert_eq!(tool["name"], "hello_world");
     assert!(tool["description"].is_string());
     assert!(tool["inputSchema"].is_object());
+    assert_eq!(tool["annotations"], json!({"title": "Hello World", "readOnlyHint": true, "openWorldHint": false}));
+}
+
+#[tokio::test]
+async fn test_list_tools_single_page_has_no_cursor() {
+    let client = TestClient::new().await;
+
+    let response = client.list_tools().await;
+
+    assert!(response["result"].get("nextCursor").is_none());
+}
+
+#[tokio::test]
+async fn test_list_invalid_cursor() {
+    let client = TestClient::new().await;
+
+    for method in ["tools/list", "resources/list", "prompts/list"] {
+        let response = client
+            .send_request(method, Some(json!({"cursor": "bogus"})))
+            .await;
+
+        assert_eq!(response["error"]["code"], -32602, "{}", method);
+    }
 }
 
 #[tokio::test]
@@ -130,6 +314,72 @@ async fn test_call_tool_with_name() {
     assert!(text.contains("Hello, Rust"));
 }
 
+#[tokio::test]
+async fn test_list_tools_output_schema() {
+    let client = TestClient::new().await;
+
+    let response = client.list_tools().await;
+    let tools = response["result"]["tools"].as_array().unwrap();
+
+    let chart = tools.iter().find(|t| t["name"] == "render_chart").unwrap();
+    assert_eq!(chart["outputSchema"]["type"], "object");
+
+    let hello = tools.iter().find(|t| t["name"] == "hello_world").unwrap();
+    assert!(hello.get("outputSchema").is_none());
+}
+
+#[tokio::test]
+async fn test_call_tool_rich_content() {
+    let client = TestClient::new().await;
+
+    let response = client
+        .call_tool("render_chart", json!({"title": "Sales", "values": [3, 1, 2]}))
+        .await;
+
+    let result = &response["result"];
+    assert_eq!(result["isError"], false);
+    assert_eq!(result["content"][0]["type"], "text");
+
+    let image = &result["content"][1];
+    assert_eq!(image["type"], "image");
+    assert_eq!(image["mimeType"], "image/svg+xml");
+    assert!(image["data"].as_str().unwrap().starts_with("PHN2Zy")); // "<svg"
+
+    assert_eq!(
+        result["structuredContent"],
+        json!({"title": "Sales", "count": 3, "min": 1.0, "max": 3.0})
+    );
+}
+
+#[tokio::test]
+async fn test_call_tool_invalid_arguments() {
+    let client = TestClient::new().await;
+
+    let response = client
+        .call_tool("render_chart", json!({"values": [1, -2]}))
+        .await;
+    assert_eq!(response["error"]["code"], -32602);
+    assert_eq!(response["error"]["data"]["path"], "/values/1");
+
+    let response = client.call_tool("render_chart", json!({})).await;
+    assert_eq!(response["error"]["code"], -32602);
+    assert!(response["error"]["message"].as_str().unwrap().contains("values"));
+
+    let response = client
+        .call_tool("render_chart", json!({"values": [1], "colour": "red"}))
+        .await;
+    assert_eq!(response["error"]["data"]["path"], "/colour");
+}
+
+#[tokio::test]
+async fn test_call_tool_applies_schema_defaults() {
+    let client = TestClient::new().await;
+
+    let response = client.call_tool("render_chart", json!({"values": [1]})).await;
+
+    assert_eq!(response["result"]["structuredContent"]["title"], "Chart");
+}
+
 #[tokio::test]
 async fn test_call_nonexistent_tool() {
     let client = TestClient::new().await;
@@ -142,10 +392,20 @@ async fn test_call_nonexistent_tool() {
     assert!(response["error"].is_object());
     
     let error = &response["error"];
-    assert_eq!(error["code"], -32603);
+    assert_eq!(error["code"], -32602);
     assert!(error["message"].as_str().unwrap().contains("not found"));
 }
 
+#[tokio::test]
+async fn test_unknown_method() {
+    let client = TestClient::new().await;
+
+    let response = client.send_request("tools/launch", None).await;
+
+    assert_eq!(response["error"]["code"], -32601);
+    assert_eq!(response["error"]["message"], "Method not found: tools/launch");
+}
+
 #[tokio::test]
 async fn test_list_prompts() {
     let client = TestClient::new().await;
@@ -157,7 +417,7 @@ async fn test_list_prompts() {
     
     let prompts = &response["result"]["prompts"];
     assert!(prompts.is_array());
-    assert!(prompts.as_array().unwrap().len() > 0);
+    assert!(!prompts.as_array().unwrap().is_empty());
     
     // Check mock_prompt is present
     let prompt = &prompts[0];
@@ -179,7 +439,7 @@ async fn test_get_prompt() {
     
     let messages = &response["result"]["messages"];
     assert!(messages.is_array());
-    assert!(messages.as_array().unwrap().len() > 0);
+    assert!(!messages.as_array().unwrap().is_empty());
     
     let message = &messages[0];
     assert!(message["role"].is_string());
@@ -202,6 +462,72 @@ async fn test_get_prompt_with_topic() {
     assert!(message_text.contains("Rust programming"));
 }
 
+#[tokio::test]
+async fn test_set_log_level_requires_session() {
+    let client = TestClient::new().await;
+
+    let response = client
+        .send_request("logging/setLevel", Some(json!({"level": "debug"})))
+        .await;
+
+    assert_eq!(response["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn test_complete_prompt_argument() {
+    let client = TestClient::new().await;
+
+    let response = client
+        .send_request(
+            "completion/complete",
+            Some(json!({
+                "ref": {"type": "ref/prompt", "name": "mock_prompt"},
+                "argument": {"name": "topic", "value": "ch"}
+            })),
+        )
+        .await;
+
+    assert_eq!(
+        response["result"]["completion"],
+        json!({"values": ["chemistry"], "total": 1, "hasMore": false})
+    );
+}
+
+#[tokio::test]
+async fn test_complete_resource_template_variable() {
+    let client = TestClient::new().await;
+
+    let response = client
+        .send_request(
+            "completion/complete",
+            Some(json!({
+                "ref": {"type": "ref/resource", "uri": "mock://users/{user}/profile"},
+                "argument": {"name": "user", "value": ""},
+                "context": {"arguments": {}}
+            })),
+        )
+        .await;
+
+    let completion = &response["result"]["completion"];
+    assert_eq!(completion["values"], json!(["alice", "bob", "carol", "dave"]));
+    assert_eq!(completion["hasMore"], false);
+}
+
+#[tokio::test]
+async fn test_complete_unknown_reference() {
+    let client = TestClient::new().await;
+
+    for params in [
+        json!({"ref": {"type": "ref/prompt", "name": "missing"}, "argument": {"name": "topic", "value": ""}}),
+        json!({"ref": {"type": "ref/prompt", "name": "mock_prompt"}, "argument": {"name": "mood", "value": ""}}),
+        json!({"ref": {"type": "ref/resource", "uri": "mock://missing/{id}"}, "argument": {"name": "id", "value": ""}}),
+        json!({"ref": {"type": "ref/tool", "name": "hello_world"}, "argument": {"name": "name", "value": ""}}),
+    ] {
+        let response = client.send_request("completion/complete", Some(params.clone())).await;
+        assert_eq!(response["error"]["code"], -32602, "{}", params);
+    }
+}
+
 #[tokio::test]
 async fn test_invalid_method() {
     let client = TestClient::new().await;
@@ -259,3 +585,513 @@ async fn test_json_rpc_format() {
     assert!(response.get("result").is_some() || response.get("error").is_some());
     assert!(!(response.get("result").is_some() && response.get("error").is_some()));
 }
+
+#[tokio::test]
+async fn test_initialize_creates_session() {
+    let client = TestClient::new().await;
+
+    let session_id = client.initialize_session().await;
+    assert!(!session_id.is_empty());
+
+    // Requests within the session are accepted
+    let body = TestClient::request_body("tools/list", None);
+    let response = client.post_mcp(body, &[("Mcp-Session-Id", &session_id)]).await;
+    assert_eq!(response.status(), 200);
+
+    let response: serde_json::Value = response.json().await.unwrap();
+    assert!(response["result"]["tools"].is_array());
+}
+
+#[tokio::test]
+async fn test_failed_initialize_creates_no_session() {
+    let client = TestClient::new().await;
+
+    let body = TestClient::request_body("initialize", Some(json!({"protocolVersion": 7})));
+    let response = client.post_mcp(body, &[]).await;
+
+    assert!(response.headers().get("mcp-session-id").is_none());
+    let response: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(response["error"]["code"], -32602);
+}
+
+#[tokio::test]
+async fn test_batched_initialize_creates_session() {
+    let client = TestClient::new().await;
+
+    let initialize = TestClient::request_body(
+        "initialize",
+        Some(json!({"protocolVersion": "2025-03-26", "capabilities": {}, "clientInfo": {"name": "batch", "version": "1.0.0"}})),
+    );
+    let batch = json!([TestClient::request_body("ping", None), initialize]);
+    let response = client.post_mcp(batch, &[]).await;
+    let session_id = response.headers().get("mcp-session-id").expect("session ID").to_str().unwrap().to_string();
+
+    let body = TestClient::request_body("tools/list", None);
+    let response = client.post_mcp(body, &[("Mcp-Session-Id", &session_id)]).await;
+    assert_eq!(response.status(), 200);
+
+    // A batch whose initialize fails establishes nothing
+    let failed = TestClient::request_body("initialize", Some(json!({"protocolVersion": 7})));
+    let batch = json!([TestClient::request_body("ping", None), failed]);
+    let response = client.post_mcp(batch, &[]).await;
+    assert!(response.headers().get("mcp-session-id").is_none());
+}
+
+#[tokio::test]
+async fn test_idle_sessions_are_reaped() {
+    let sessions = mecp::core::session::SessionManager::new();
+    let idle = sessions.create().await;
+    let connected = sessions.create().await;
+    let _stream = connected.attach_stream().await;
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    let active = sessions.create().await;
+
+    // Only the session with neither recent requests nor an open stream goes
+    assert_eq!(sessions.reap_idle(std::time::Duration::from_millis(30)).await, 1);
+    assert!(sessions.get(idle.id()).await.is_none());
+    assert!(sessions.get(connected.id()).await.is_some());
+    assert!(sessions.get(active.id()).await.is_some());
+
+    // Requests keep a session alive
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    active.touch().await;
+    assert_eq!(sessions.reap_idle(std::time::Duration::from_millis(30)).await, 0);
+
+    // So does a call still running long after it was made
+    let id = serde_json::json!(7);
+    let token = active.begin_request(&id).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert_eq!(sessions.reap_idle(std::time::Duration::from_millis(30)).await, 0);
+    active.end_request(&id, &token).await;
+    assert_eq!(sessions.reap_idle(std::time::Duration::from_millis(30)).await, 0);
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert_eq!(sessions.reap_idle(std::time::Duration::from_millis(30)).await, 1);
+}
+
+#[tokio::test]
+async fn test_unknown_session_rejected() {
+    let client = TestClient::new().await;
+
+    let body = TestClient::request_body("tools/list", None);
+    let response = client.post_mcp(body, &[("Mcp-Session-Id", "no-such-session")]).await;
+
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_delete_session() {
+    let client = TestClient::new().await;
+    let session_id = client.initialize_session().await;
+
+    let response = client
+        .request(reqwest::Method::DELETE, "/mcp", &[("Mcp-Session-Id", &session_id)])
+        .await;
+    assert_eq!(response.status(), 200);
+
+    // The session is gone afterwards
+    let body = TestClient::request_body("tools/list", None);
+    let response = client.post_mcp(body, &[("Mcp-Session-Id", &session_id)]).await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_get_stream_requires_session() {
+    let client = TestClient::new().await;
+
+    let response = client
+        .request(reqwest::Method::GET, "/mcp", &[("Accept", "text/event-stream")])
+        .await;
+
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn test_get_stream_opens_sse() {
+    let client = TestClient::new().await;
+    let session_id = client.initialize_session().await;
+
+    let response = client
+        .request(
+            reqwest::Method::GET,
+            "/mcp",
+            &[("Accept", "text/event-stream"), ("Mcp-Session-Id", &session_id)],
+        )
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let content_type = response.headers()["content-type"].to_str().unwrap();
+    assert!(content_type.starts_with("text/event-stream"));
+}
+
+#[tokio::test]
+async fn test_call_tool_streamed_response() {
+    let client = TestClient::new().await;
+
+    let body = TestClient::request_body(
+        "tools/call",
+        Some(json!({"name": "hello_world", "arguments": {"name": "Stream"}})),
+    );
+    let response = client
+        .post_mcp(body, &[("Accept", "application/json, text/event-stream")])
+        .await;
+
+    assert_eq!(response.status(), 200);
+    let content_type = response.headers()["content-type"].to_str().unwrap().to_string();
+    assert!(content_type.starts_with("text/event-stream"));
+
+    // The stream carries the JSON-RPC response and then closes
+    let text = response.text().await.unwrap();
+    let data = text
+        .lines()
+        .find_map(|line| line.strip_prefix("data: "))
+        .expect("stream should contain a data event");
+    let message: serde_json::Value = serde_json::from_str(data).unwrap();
+    assert_eq!(message["jsonrpc"], "2.0");
+    assert!(message["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("Hello, Stream"));
+}
+
+#[tokio::test]
+async fn test_ping() {
+    let client = TestClient::new().await;
+
+    let response = client.send_request("ping", None).await;
+
+    assert_eq!(response["result"], json!({}));
+}
+
+#[tokio::test]
+async fn test_progress_streamed_before_response() {
+    let client = TestClient::new().await;
+
+    let body = TestClient::request_body(
+        "tools/call",
+        Some(json!({
+            "name": "long_task",
+            "arguments": {"steps": 3, "delay_ms": 10},
+            "_meta": {"progressToken": "ingest-1"}
+        })),
+    );
+    let id = body["id"].clone();
+    let response = client
+        .post_mcp(body, &[("Accept", "application/json, text/event-stream")])
+        .await;
+    let mut events = SseReader::new(response);
+
+    for step in 1..=3 {
+        let message = events.next_message().await.expect("progress notification");
+        assert_eq!(message["method"], "notifications/progress");
+        assert_eq!(message["params"]["progressToken"], "ingest-1");
+        assert_eq!(message["params"]["progress"], step as f64);
+        assert_eq!(message["params"]["total"], 3.0);
+    }
+
+    let message = events.next_message().await.expect("response");
+    assert_eq!(message["id"], id);
+    assert!(message["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("steps_completed"));
+}
+
+#[tokio::test]
+async fn test_sampling_request_over_tool_call_stream() {
+    let client = TestClient::new().await;
+    let initialize = TestClient::request_body(
+        "initialize",
+        Some(json!({
+            "protocolVersion": "2025-06-18",
+            "capabilities": {"sampling": {}},
+            "clientInfo": {"name": "sampling-client", "version": "1.0.0"}
+        })),
+    );
+    let response = client.post_mcp(initialize, &[]).await;
+    let session_id = response.headers()["mcp-session-id"].to_str().unwrap().to_string();
+
+    let body = TestClient::request_body(
+        "tools/call",
+        Some(json!({"name": "summarize", "arguments": {"text": "A long story", "max_tokens": 50}})),
+    );
+    let id = body["id"].clone();
+    let response = client
+        .post_mcp(
+            body,
+            &[("Accept", "application/json, text/event-stream"), ("Mcp-Session-Id", &session_id)],
+        )
+        .await;
+    let mut events = SseReader::new(response);
+
+    // The server's request arrives on the tool call's stream
+    let request = events.next_message().await.expect("sampling request");
+    assert_eq!(request["method"], "sampling/createMessage");
+    assert_eq!(request["params"]["maxTokens"], 50);
+    assert_eq!(
+        request["params"]["messages"],
+        json!([{"role": "user", "content": {"type": "text", "text": "A long story"}}])
+    );
+
+    let reply = json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "result": {
+            "role": "assistant",
+            "content": {"type": "text", "text": "A story, shortened."},
+            "model": "client-model",
+            "stopReason": "endTurn"
+        }
+    });
+    let accepted = client.post_mcp(reply, &[("Mcp-Session-Id", &session_id)]).await;
+    assert_eq!(accepted.status(), 202);
+
+    let message = events.next_message().await.expect("response");
+    assert_eq!(message["id"], id);
+    assert_eq!(message["result"]["content"][0]["text"], "A story, shortened.");
+}
+
+#[tokio::test]
+async fn test_cancel_in_flight_request() {
+    let client = TestClient::new().await;
+    let session_id = client.initialize_session().await;
+
+    let body = TestClient::request_body(
+        "tools/call",
+        Some(json!({"name": "long_task", "arguments": {"steps": 1000, "delay_ms": 50}})),
+    );
+    let request_id = body["id"].clone();
+
+    let started = std::time::Instant::now();
+    let call = {
+        let http = client.client().clone();
+        let url = format!("{}/mcp", client.base_url());
+        let session_id = session_id.clone();
+        tokio::spawn(async move {
+            http
+                .post(url)
+                .header("mcp-session-id", session_id)
+                .json(&body)
+                .send()
+                .await
+                .unwrap()
+        })
+    };
+
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    let cancel = json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/cancelled",
+        "params": {"requestId": request_id, "reason": "user aborted"}
+    });
+    let response = client.post_mcp(cancel, &[("mcp-session-id", &session_id)]).await;
+    assert_eq!(response.status(), 202);
+
+    // The cancelled request is never answered
+    let response = call.await.unwrap();
+    assert_eq!(response.status(), 202);
+    assert!(response.text().await.unwrap().is_empty());
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn test_duplicate_in_flight_request_id_rejected() {
+    let client = TestClient::new().await;
+    let session_id = client.initialize_session().await;
+
+    let body = TestClient::request_body(
+        "tools/call",
+        Some(json!({"name": "long_task", "arguments": {"steps": 1000, "delay_ms": 50}})),
+    );
+    let request_id = body["id"].clone();
+
+    let call = {
+        let http = client.client().clone();
+        let url = format!("{}/mcp", client.base_url());
+        let session_id = session_id.clone();
+        let body = body.clone();
+        tokio::spawn(async move {
+            http
+                .post(url)
+                .header("mcp-session-id", session_id)
+                .json(&body)
+                .send()
+                .await
+                .unwrap()
+        })
+    };
+
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    let response = client.post_mcp(body, &[("mcp-session-id", &session_id)]).await;
+    let duplicate: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(duplicate["id"], request_id);
+    assert_eq!(duplicate["error"]["code"], -32600);
+
+    // The original request can still be cancelled
+    let cancel = json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/cancelled",
+        "params": {"requestId": request_id}
+    });
+    client.post_mcp(cancel, &[("mcp-session-id", &session_id)]).await;
+    let response = call.await.unwrap();
+    assert_eq!(response.status(), 202);
+}
+
+#[tokio::test]
+async fn test_legacy_sse_transport() {
+    let client = TestClient::new().await;
+
+    let response = client
+        .request(reqwest::Method::GET, "/sse", &[("Accept", "text/event-stream")])
+        .await;
+    assert_eq!(response.status(), 200);
+    let mut events = SseReader::new(response);
+
+    // The first event names the per-connection message endpoint
+    let (event, endpoint) = events.next_event().await.expect("endpoint event");
+    assert_eq!(event, "endpoint");
+    assert!(endpoint.starts_with("/messages?sessionId="));
+
+    let body = TestClient::request_body(
+        "tools/call",
+        Some(json!({"name": "hello_world", "arguments": {"name": "Legacy"}})),
+    );
+    let request_id = body["id"].clone();
+    let response = client
+        .client()
+        .post(format!("{}{}", client.base_url(), endpoint))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 202);
+
+    // The response arrives over the SSE stream
+    let message = events.next_message().await.expect("response message");
+    assert_eq!(message["id"], request_id);
+    assert!(message["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("Hello, Legacy"));
+}
+
+#[tokio::test]
+async fn test_legacy_messages_unknown_session() {
+    let client = TestClient::new().await;
+
+    let body = TestClient::request_body("tools/list", None);
+    let response = client
+        .client()
+        .post(format!("{}/messages?sessionId=no-such-session", client.base_url()))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_batch_request() {
+    let client = TestClient::new().await;
+
+    let first = TestClient::request_body("tools/list", None);
+    let second = TestClient::request_body("prompts/list", None);
+    let batch = json!([first.clone(), second.clone()]);
+
+    let response = client.post_mcp(batch, &[]).await;
+    assert_eq!(response.status(), 200);
+
+    let responses: serde_json::Value = response.json().await.unwrap();
+    let responses = responses.as_array().expect("batch response should be an array");
+    assert_eq!(responses.len(), 2);
+
+    let tools = responses.iter().find(|r| r["id"] == first["id"]).unwrap();
+    assert!(tools["result"]["tools"].is_array());
+    let prompts = responses.iter().find(|r| r["id"] == second["id"]).unwrap();
+    assert!(prompts["result"]["prompts"].is_array());
+}
+
+#[tokio::test]
+async fn test_batch_skips_notifications() {
+    let client = TestClient::new().await;
+
+    let request = TestClient::request_body("tools/list", None);
+    let batch = json!([
+        {"jsonrpc": "2.0", "method": "notifications/initialized"},
+        request.clone()
+    ]);
+
+    let response = client.post_mcp(batch, &[]).await;
+    let responses: serde_json::Value = response.json().await.unwrap();
+    let responses = responses.as_array().unwrap();
+
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0]["id"], request["id"]);
+}
+
+#[tokio::test]
+async fn test_notification_accepted_without_body() {
+    let client = TestClient::new().await;
+    let session_id = client.initialize_session().await;
+
+    let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+    let response = client
+        .post_mcp(notification, &[("Mcp-Session-Id", &session_id)])
+        .await;
+
+    assert_eq!(response.status(), 202);
+    assert!(response.text().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_cancelled_notification_accepted() {
+    let client = TestClient::new().await;
+
+    let notification = json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/cancelled",
+        "params": {"requestId": 42, "reason": "user aborted"}
+    });
+    let response = client.post_mcp(notification, &[]).await;
+
+    assert_eq!(response.status(), 202);
+}
+
+#[tokio::test]
+async fn test_empty_batch_is_invalid() {
+    let client = TestClient::new().await;
+
+    let response = client.post_mcp(json!([]), &[]).await;
+    let response: serde_json::Value = response.json().await.unwrap();
+
+    assert_eq!(response["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn test_invalid_request_in_batch() {
+    let client = TestClient::new().await;
+
+    let response = client.post_mcp(json!([{"jsonrpc": "2.0", "id": 7}]), &[]).await;
+    let responses: serde_json::Value = response.json().await.unwrap();
+
+    assert_eq!(responses[0]["id"], 7);
+    assert_eq!(responses[0]["error"]["code"], -32600);
+}
+
+#[tokio::test]
+async fn test_parse_error() {
+    let client = TestClient::new().await;
+
+    let response = client
+        .client()
+        .post(format!("{}/mcp", client.base_url()))
+        .header("Content-Type", "application/json")
+        .body("{not json")
+        .send()
+        .await
+        .unwrap();
+    let response: serde_json::Value = response.json().await.unwrap();
+
+    assert_eq!(response["error"]["code"], -32700);
+}
@@ -0,0 +1,349 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use mecp::core::context::RequestContext;
+use mecp::core::error::McpError;
+use mecp::core::http_server::HttpServer;
+use mecp::core::middleware::{Call, CallResult, Middleware, ReadOnlyPolicy};
+use mecp::core::protocol::{methods, ResourceContent};
+use mecp::core::server::McpServer;
+use mecp::prompts::mock::MockPrompt;
+use mecp::resources::mock::MockResource;
+use mecp::tools::mock::HelloWorldTool;
+use mecp::tools::tool;
+
+const API_KEY_TEST_PORT: u16 = 13110;
+
+/// Records the hooks it runs, tagged with its name
+struct Trace {
+    name: &'static str,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl Middleware for Trace {
+    async fn before(&self, call: &mut Call) -> Result<Option<CallResult>, McpError> {
+        self.log.lock().unwrap().push(format!("{} before {} {}", self.name, call.method, call.target));
+        Ok(None)
+    }
+
+    async fn after(&self, _call: &Call, result: Result<CallResult, McpError>) -> Result<CallResult, McpError> {
+        self.log.lock().unwrap().push(format!("{} after", self.name));
+        result
+    }
+}
+
+/// Only lets users on the allow list call tools
+struct AllowList(Vec<&'static str>);
+
+#[async_trait]
+impl Middleware for AllowList {
+    async fn before(&self, call: &mut Call) -> Result<Option<CallResult>, McpError> {
+        let allowed = call
+            .caller
+            .user
+            .as_deref()
+            .is_some_and(|user| self.0.contains(&user));
+        if call.method == methods::CALL_TOOL && !allowed {
+            return Err(McpError::invalid_params(format!("Not allowed to call {}", call.target)));
+        }
+        Ok(None)
+    }
+}
+
+/// Greets everyone by the same name
+struct Anonymize;
+
+#[async_trait]
+impl Middleware for Anonymize {
+    async fn before(&self, call: &mut Call) -> Result<Option<CallResult>, McpError> {
+        if call.target == "hello_world" {
+            call.arguments["name"] = json!("friend");
+        }
+        Ok(None)
+    }
+}
+
+/// Replaces resource contents with a placeholder
+struct Redact;
+
+#[async_trait]
+impl Middleware for Redact {
+    async fn after(&self, call: &Call, result: Result<CallResult, McpError>) -> Result<CallResult, McpError> {
+        match result? {
+            CallResult::Resource(_) => Ok(CallResult::Resource(vec![ResourceContent::text(
+                &call.target,
+                Some("text/plain"),
+                "[redacted]",
+            )])),
+            other => Ok(other),
+        }
+    }
+}
+
+/// Sends calls of one tool to another
+struct Reroute {
+    from: &'static str,
+    to: &'static str,
+}
+
+#[async_trait]
+impl Middleware for Reroute {
+    async fn before(&self, call: &mut Call) -> Result<Option<CallResult>, McpError> {
+        if call.target == self.from {
+            call.target = self.to.to_string();
+        }
+        Ok(None)
+    }
+}
+
+/// Deletes a note
+#[tool]
+async fn delete_note(id: String) -> anyhow::Result<String> {
+    Ok(format!("deleted {}", id))
+}
+
+/// Archives a note, keeping it recoverable
+#[tool(destructive_hint = false)]
+async fn archive_note(id: String) -> anyhow::Result<String> {
+    Ok(format!("archived {}", id))
+}
+
+async fn server() -> McpServer {
+    let server = McpServer::new();
+    server.register_tool(Box::new(HelloWorldTool::new())).await.unwrap();
+    server.register_resource(Box::new(MockResource::new())).await.unwrap();
+    server.register_prompt(Box::new(MockPrompt::new())).await.unwrap();
+    server
+}
+
+#[tokio::test]
+async fn test_middleware_order() {
+    let server = server().await;
+    let log = Arc::new(Mutex::new(Vec::new()));
+    for name in ["outer", "inner"] {
+        server
+            .add_middleware(Box::new(Trace { name, log: Arc::clone(&log) }))
+            .await;
+    }
+
+    server.get_prompt("mock_prompt", json!({})).await.unwrap();
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec![
+            "outer before prompts/get mock_prompt",
+            "inner before prompts/get mock_prompt",
+            "inner after",
+            "outer after",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_middleware_rewrites_arguments() {
+    let server = server().await;
+    server.add_middleware(Box::new(Anonymize)).await;
+
+    let result = server.call_tool("hello_world", json!({"name": "Alice"})).await.unwrap();
+    assert_eq!(result.output["message"], "Hello, friend! Welcome to MeCP.");
+}
+
+#[tokio::test]
+async fn test_middleware_short_circuits_on_caller_identity() {
+    let server = server().await;
+    let log = Arc::new(Mutex::new(Vec::new()));
+    server
+        .add_middleware(Box::new(Trace { name: "audit", log: Arc::clone(&log) }))
+        .await;
+    server.add_middleware(Box::new(AllowList(vec!["alice"]))).await;
+    server
+        .add_middleware(Box::new(Trace { name: "inner", log: Arc::clone(&log) }))
+        .await;
+
+    // Anonymous callers are turned away before the tool runs
+    let err = server.call_tool("hello_world", json!({})).await.unwrap_err();
+    assert!(matches!(err, McpError::InvalidParams { .. }));
+    assert_eq!(
+        *log.lock().unwrap(),
+        vec!["audit before tools/call hello_world", "audit after"]
+    );
+
+    let session = server.sessions().create().await;
+    session.set_user("alice").await;
+    let ctx = RequestContext::new(Some(json!(1)), None, Some(session), None, CancellationToken::new());
+    let result = server
+        .call_tool_with_context("hello_world", json!({"name": "Alice"}), &ctx)
+        .await
+        .unwrap();
+    assert_eq!(result.output["message"], "Hello, Alice! Welcome to MeCP.");
+}
+
+#[tokio::test]
+async fn test_middleware_rewrites_results() {
+    let server = server().await;
+    server.add_middleware(Box::new(Redact)).await;
+
+    let contents = server.read_resource_contents("mock://example/resource").await.unwrap();
+    assert_eq!(
+        contents,
+        vec![ResourceContent::text("mock://example/resource", Some("text/plain"), "[redacted]")]
+    );
+
+    // Errors pass through untouched
+    let err = server.read_resource_contents("mock://missing").await.unwrap_err();
+    assert!(matches!(err, McpError::ResourceNotFound { .. }));
+}
+
+#[tokio::test]
+async fn test_read_only_policy() {
+    let server = server().await;
+    server.register_tool(Box::new(DeleteNoteTool)).await.unwrap();
+    server.register_tool(Box::new(ArchiveNoteTool)).await.unwrap();
+    server.add_middleware(Box::new(ReadOnlyPolicy::for_users(["viewer"]))).await;
+
+    let session = server.sessions().create().await;
+    session.set_user("viewer").await;
+    let viewer = RequestContext::new(Some(json!(1)), None, Some(session), None, CancellationToken::new());
+
+    let result = server.call_tool_with_context("hello_world", json!({}), &viewer).await.unwrap();
+    assert!(result.success);
+    for name in ["delete_note", "archive_note"] {
+        let err = server
+            .call_tool_with_context(name, json!({"id": "n1"}), &viewer)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, McpError::ToolExecution { .. }), "{}", err);
+    }
+
+    // Unrestricted callers may use any tool
+    let result = server.call_tool("delete_note", json!({"id": "n1"})).await.unwrap();
+    assert_eq!(result.output, json!("deleted n1"));
+}
+
+#[tokio::test]
+async fn test_read_only_policy_checks_rewritten_target() {
+    let server = server().await;
+    server.register_tool(Box::new(DeleteNoteTool)).await.unwrap();
+    server.add_middleware(Box::new(Reroute { from: "hello_world", to: "delete_note" })).await;
+    server.add_middleware(Box::new(ReadOnlyPolicy::new(|_| true))).await;
+
+    // hello_world is read-only, but the call now goes to delete_note
+    let err = server.call_tool("hello_world", json!({"id": "n1"})).await.unwrap_err();
+    assert!(matches!(err, McpError::ToolExecution { .. }), "{}", err);
+}
+
+#[tokio::test]
+async fn test_read_only_policy_allowing_non_destructive_tools() {
+    let server = server().await;
+    server.register_tool(Box::new(DeleteNoteTool)).await.unwrap();
+    server.register_tool(Box::new(ArchiveNoteTool)).await.unwrap();
+    server
+        .add_middleware(Box::new(ReadOnlyPolicy::new(|caller| caller.user.is_none()).allow_non_destructive()))
+        .await;
+
+    let result = server.call_tool("archive_note", json!({"id": "n1"})).await.unwrap();
+    assert_eq!(result.output, json!("archived n1"));
+    assert!(server.call_tool("delete_note", json!({"id": "n1"})).await.is_err());
+}
+
+/// POST a JSON-RPC message to `/mcp` with the given API key and session
+async fn post_mcp(
+    client: &reqwest::Client,
+    key: &str,
+    session_id: Option<&str>,
+    message: Value,
+) -> reqwest::Response {
+    let mut request = client
+        .post(format!("http://127.0.0.1:{}/mcp", API_KEY_TEST_PORT))
+        .bearer_auth(key)
+        .json(&message);
+    if let Some(session_id) = session_id {
+        request = request.header("Mcp-Session-Id", session_id);
+    }
+    request.send().await.unwrap()
+}
+
+/// Open a session authenticated with `key`, returning its ID
+async fn initialize(client: &reqwest::Client, key: &str) -> String {
+    let response = post_mcp(client, key, None, json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {},
+            "clientInfo": {"name": "agent", "version": "1.0.0"}
+        }
+    }))
+    .await;
+    assert_eq!(response.status(), 200);
+    response.headers()["mcp-session-id"].to_str().unwrap().to_string()
+}
+
+async fn call_tool(client: &reqwest::Client, key: &str, session_id: &str, name: &str) -> Value {
+    post_mcp(client, key, Some(session_id), json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": {"name": name, "arguments": {"id": "n1"}}
+    }))
+    .await
+    .json()
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_read_only_api_key_over_http() {
+    let server = Arc::new(server().await);
+    server.register_tool(Box::new(DeleteNoteTool)).await.unwrap();
+    server.add_api_key("viewer-key", "viewer").await;
+    server.add_api_key("admin-key", "admin").await;
+    server.add_middleware(Box::new(ReadOnlyPolicy::for_users(["viewer"]))).await;
+    let http_server = HttpServer::new(Arc::clone(&server), "127.0.0.1".to_string(), API_KEY_TEST_PORT);
+    tokio::spawn(http_server.start());
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let client = reqwest::Client::new();
+
+    // The read-only key may look things up but not delete them
+    let viewer = initialize(&client, "viewer-key").await;
+    let response = call_tool(&client, "viewer-key", &viewer, "hello_world").await;
+    assert_eq!(response["result"]["isError"], false);
+    let response = call_tool(&client, "viewer-key", &viewer, "delete_note").await;
+    assert_eq!(response["result"]["isError"], true);
+
+    let admin = initialize(&client, "admin-key").await;
+    let response = call_tool(&client, "admin-key", &admin, "delete_note").await;
+    assert_eq!(response["result"]["isError"], false);
+    assert_eq!(response["result"]["content"][0]["text"], "deleted n1");
+
+    // Keys can't be swapped within a session, and unknown keys are refused
+    let message = json!({"jsonrpc": "2.0", "id": 3, "method": "tools/list"});
+    let response = post_mcp(&client, "admin-key", Some(&viewer), message.clone()).await;
+    assert_eq!(response.status(), 403);
+    let response = post_mcp(&client, "stolen-key", None, message.clone()).await;
+    assert_eq!(response.status(), 401);
+
+    // Dropping the key doesn't get around the policy, in a session or outside one
+    let delete = json!({
+        "jsonrpc": "2.0",
+        "id": 4,
+        "method": "tools/call",
+        "params": {"name": "delete_note", "arguments": {"id": "n1"}}
+    });
+    let url = format!("http://127.0.0.1:{}/mcp", API_KEY_TEST_PORT);
+    let response = client
+        .post(&url)
+        .header("Mcp-Session-Id", &viewer)
+        .json(&delete)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    let response = client.post(&url).json(&delete).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+}
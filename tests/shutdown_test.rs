@@ -0,0 +1,164 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use mecp::core::error::McpError;
+use mecp::core::http_server::HttpServer;
+use mecp::core::metrics::{ApiCallLog, MetricsCollector};
+use mecp::core::server::McpServer;
+use mecp::core::types::{JsonValue, ToolResult};
+use mecp::tools::mock::LongTaskTool;
+use mecp::tools::{Tool, ToolMetadata};
+
+const SHUTDOWN_TEST_PORT: u16 = 13100;
+
+/// Records whether its shutdown hook ran
+struct PooledTool {
+    closed: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl Tool for PooledTool {
+    async fn metadata(&self) -> Result<ToolMetadata> {
+        Ok(ToolMetadata {
+            name: "pooled".to_string(),
+            description: "Holds a connection pool".to_string(),
+            ..Default::default()
+        })
+    }
+
+    async fn execute(&self, _params: JsonValue) -> Result<ToolResult> {
+        Ok(ToolResult::default())
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        self.closed.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_shutdown_drains_calls_and_runs_hooks() {
+    let server = Arc::new(McpServer::new());
+    let closed = Arc::new(AtomicBool::new(false));
+    server.register_tool(Box::new(LongTaskTool::new())).await.unwrap();
+    server
+        .register_tool(Box::new(PooledTool { closed: Arc::clone(&closed) }))
+        .await
+        .unwrap();
+    let session = server.sessions().create().await;
+
+    let call = tokio::spawn({
+        let server = Arc::clone(&server);
+        async move { server.call_tool("long_task", json!({"steps": 3, "delay_ms": 50})).await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let shutdown = tokio::spawn({
+        let server = Arc::clone(&server);
+        async move { server.shutdown(Duration::from_secs(5)).await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    // Calls arriving during shutdown are refused
+    assert!(server.is_shutting_down());
+    let err = server.call_tool("pooled", json!({})).await.unwrap_err();
+    assert!(matches!(err, McpError::Internal { .. }));
+
+    // The running call is allowed to finish
+    assert!(call.await.unwrap().unwrap().success);
+    shutdown.await.unwrap();
+
+    assert!(closed.load(Ordering::SeqCst));
+    assert!(server.sessions().get(session.id()).await.is_none());
+}
+
+#[tokio::test]
+async fn test_shutdown_gives_up_after_grace() {
+    let server = Arc::new(McpServer::new());
+    server.register_tool(Box::new(LongTaskTool::new())).await.unwrap();
+
+    tokio::spawn({
+        let server = Arc::clone(&server);
+        async move { server.call_tool("long_task", json!({"steps": 1, "delay_ms": 10_000})).await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let started = Instant::now();
+    server.shutdown(Duration::from_millis(100)).await;
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_metrics_flush() {
+    let metrics = Arc::new(MetricsCollector::new());
+    for i in 0..3 {
+        metrics.record_call_detached(ApiCallLog {
+            id: None,
+            method: "tools/call".to_string(),
+            endpoint: "/mcp".to_string(),
+            request_params: None,
+            response_data: None,
+            response_status: "success".to_string(),
+            error_message: None,
+            duration_ms: i,
+            timestamp: chrono::Utc::now(),
+            client_info: None,
+        });
+    }
+
+    assert!(metrics.flush(Duration::from_secs(5)).await);
+    assert_eq!(metrics.get_recent_logs(10).await.len(), 3);
+}
+
+#[tokio::test]
+async fn test_http_server_graceful_shutdown() {
+    let server = Arc::new(McpServer::new());
+    server.register_tool(Box::new(LongTaskTool::new())).await.unwrap();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let http_server = HttpServer::new(Arc::clone(&server), "127.0.0.1".to_string(), SHUTDOWN_TEST_PORT)
+        .with_shutdown_grace(Duration::from_secs(5));
+    let serving = tokio::spawn(http_server.start_with_shutdown(async {
+        let _ = stopped.await;
+    }));
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let client = reqwest::Client::new();
+    let url = format!("http://127.0.0.1:{}/mcp", SHUTDOWN_TEST_PORT);
+    let call = tokio::spawn({
+        let client = client.clone();
+        let url = url.clone();
+        async move {
+            let response = client
+                .post(&url)
+                .json(&json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "tools/call",
+                    "params": {"name": "long_task", "arguments": {"steps": 2, "delay_ms": 100}}
+                }))
+                .send()
+                .await
+                .unwrap();
+            response.json::<Value>().await.unwrap()
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    stop.send(()).unwrap();
+
+    // The call that was running when the signal arrived still gets its answer
+    let response = call.await.unwrap();
+    assert!(response.get("error").is_none());
+    assert!(response["result"]["content"].is_array());
+
+    tokio::time::timeout(Duration::from_secs(10), serving)
+        .await
+        .expect("server should stop after the signal")
+        .unwrap()
+        .unwrap();
+    assert!(client.post(&url).json(&json!({})).send().await.is_err());
+}
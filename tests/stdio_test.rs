@@ -0,0 +1,699 @@
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use mecp::core::server::McpServer;
+use mecp::core::stdio::StdioServer;
+use mecp::core::types::ToolResult;
+use mecp::tools::tool;
+
+/// Fails without producing any content
+#[tool]
+async fn reject(reason: String) -> anyhow::Result<ToolResult> {
+    Ok(ToolResult {
+        success: false,
+        error: Some(reason),
+        ..Default::default()
+    })
+}
+
+/// Start a stdio server over in-memory pipes, returning the client ends
+async fn start_stdio_server() -> (tokio::io::DuplexStream, BufReader<tokio::io::DuplexStream>) {
+    let server = Arc::new(McpServer::new());
+    server
+        .register_tool(Box::new(mecp::tools::mock::HelloWorldTool::new()))
+        .await
+        .unwrap();
+
+    start_stdio_server_with(server).await
+}
+
+/// Start a stdio server for an already configured `McpServer`
+async fn start_stdio_server_with(
+    server: Arc<McpServer>,
+) -> (tokio::io::DuplexStream, BufReader<tokio::io::DuplexStream>) {
+    let (client_in, server_in) = tokio::io::duplex(64 * 1024);
+    let (server_out, client_out) = tokio::io::duplex(64 * 1024);
+
+    tokio::spawn(async move {
+        StdioServer::new(server).serve(server_in, server_out).await.unwrap();
+    });
+
+    (client_in, BufReader::new(client_out))
+}
+
+async fn read_message(reader: &mut BufReader<tokio::io::DuplexStream>) -> Value {
+    let mut line = String::new();
+    reader.read_line(&mut line).await.unwrap();
+    serde_json::from_str(&line).expect("stdout should only carry JSON-RPC messages")
+}
+
+#[tokio::test]
+async fn test_stdio_initialize_and_call_tool() {
+    let (mut input, mut output) = start_stdio_server().await;
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "desktop-host", "version": "1.0.0"}
+        }
+    });
+    input.write_all(format!("{}\n", initialize).as_bytes()).await.unwrap();
+
+    let response = read_message(&mut output).await;
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["serverInfo"]["name"], "MeCP");
+
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": {"name": "hello_world", "arguments": {"name": "Desktop"}}
+    });
+    input.write_all(format!("{}\n", call).as_bytes()).await.unwrap();
+
+    let response = read_message(&mut output).await;
+    assert_eq!(response["id"], 2);
+    assert!(response["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("Hello, Desktop"));
+}
+
+#[tokio::test]
+async fn test_stdio_failed_tool_reports_its_error() {
+    let server = Arc::new(McpServer::new());
+    server.register_tool(Box::new(RejectTool)).await.unwrap();
+    let (mut input, mut output) = start_stdio_server_with(server).await;
+
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {"name": "reject", "arguments": {"reason": "Quota exceeded"}}
+    });
+    input.write_all(format!("{}\n", call).as_bytes()).await.unwrap();
+
+    let response = read_message(&mut output).await;
+    assert_eq!(response["result"]["isError"], true);
+    assert_eq!(response["result"]["content"], json!([{"type": "text", "text": "Quota exceeded"}]));
+}
+
+#[tokio::test]
+async fn test_stdio_parse_error() {
+    let (mut input, mut output) = start_stdio_server().await;
+
+    input.write_all(b"{not json}\n").await.unwrap();
+
+    let response = read_message(&mut output).await;
+    assert_eq!(response["jsonrpc"], "2.0");
+    assert_eq!(response["error"]["code"], -32700);
+}
+
+#[tokio::test]
+async fn test_stdio_notification_gets_no_response() {
+    let (mut input, mut output) = start_stdio_server().await;
+
+    let notification = json!({"jsonrpc": "2.0", "method": "notifications/initialized"});
+    input.write_all(format!("{}\n", notification).as_bytes()).await.unwrap();
+
+    let list = json!({"jsonrpc": "2.0", "id": 3, "method": "tools/list"});
+    input.write_all(format!("{}\n", list).as_bytes()).await.unwrap();
+
+    // The first line written is the tools/list response, not a reply to the notification
+    let response = read_message(&mut output).await;
+    assert_eq!(response["id"], 3);
+    assert!(response["result"]["tools"].is_array());
+}
+
+#[tokio::test]
+async fn test_stdio_initialize_records_client() {
+    let server = Arc::new(McpServer::new());
+    server
+        .register_tool(Box::new(mecp::tools::mock::HelloWorldTool::new()))
+        .await
+        .unwrap();
+    let (mut input, mut output) = start_stdio_server_with(Arc::clone(&server)).await;
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-03-26",
+            "capabilities": {"roots": {"listChanged": true}},
+            "clientInfo": {"name": "desktop-host", "version": "2.1.0"}
+        }
+    });
+    input.write_all(format!("{}\n", initialize).as_bytes()).await.unwrap();
+
+    let response = read_message(&mut output).await;
+    let result = &response["result"];
+    assert_eq!(result["protocolVersion"], "2025-03-26");
+    // Only tools are registered, so only tools are advertised
+    assert!(result["capabilities"]["tools"].is_object());
+    assert!(result["capabilities"].get("resources").is_none());
+    assert!(result["capabilities"].get("prompts").is_none());
+
+    let session = server.sessions().all().await.pop().unwrap();
+    let client = session.client().await.unwrap();
+    assert_eq!(client.protocol_version, "2025-03-26");
+    assert_eq!(client.info.name, "desktop-host");
+    assert!(session.client_capabilities().await.roots.unwrap().list_changed);
+    assert!(session.client_capabilities().await.sampling.is_none());
+}
+
+#[tokio::test]
+async fn test_stdio_cancel_and_progress() {
+    let server = Arc::new(McpServer::new());
+    server
+        .register_tool(Box::new(mecp::tools::mock::LongTaskTool::new()))
+        .await
+        .unwrap();
+    let (mut input, mut output) = start_stdio_server_with(server).await;
+
+    // Progress is written to stdout ahead of the response
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {
+            "name": "long_task",
+            "arguments": {"steps": 2, "delay_ms": 10},
+            "_meta": {"progressToken": 7}
+        }
+    });
+    input.write_all(format!("{}\n", call).as_bytes()).await.unwrap();
+
+    for step in 1..=2 {
+        let message = read_message(&mut output).await;
+        assert_eq!(message["method"], "notifications/progress");
+        assert_eq!(message["params"]["progressToken"], 7);
+        assert_eq!(message["params"]["progress"], step as f64);
+    }
+    assert_eq!(read_message(&mut output).await["id"], 1);
+
+    // A cancelled request is never answered
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": {"name": "long_task", "arguments": {"steps": 1000, "delay_ms": 50}}
+    });
+    let cancel = json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/cancelled",
+        "params": {"requestId": 2}
+    });
+    let ping = json!({"jsonrpc": "2.0", "id": 3, "method": "ping"});
+    input.write_all(format!("{}\n", call).as_bytes()).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    input.write_all(format!("{}\n{}\n", cancel, ping).as_bytes()).await.unwrap();
+
+    let response = read_message(&mut output).await;
+    assert_eq!(response["id"], 3);
+    assert_eq!(response["result"], json!({}));
+
+    // Closing stdin must not wait for the cancelled call
+    drop(input);
+    let mut rest = String::new();
+    tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        tokio::io::AsyncReadExt::read_to_string(&mut output, &mut rest),
+    )
+    .await
+    .expect("server should shut down promptly")
+    .unwrap();
+    assert!(rest.is_empty());
+}
+
+#[tokio::test]
+async fn test_stdio_paginated_tools_list() {
+    let server = Arc::new(McpServer::with_page_size(2));
+    server
+        .register_tool(Box::new(mecp::tools::mock::SearchTool::new()))
+        .await
+        .unwrap();
+    server
+        .register_tool(Box::new(mecp::tools::mock::HelloWorldTool::new()))
+        .await
+        .unwrap();
+    server
+        .register_tool(Box::new(mecp::tools::mock::FetchTool::new()))
+        .await
+        .unwrap();
+    let (mut input, mut output) = start_stdio_server_with(server).await;
+
+    let list = json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"});
+    input.write_all(format!("{}\n", list).as_bytes()).await.unwrap();
+    let first = read_message(&mut output).await;
+    let names: Vec<&str> = first["result"]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["fetch", "hello_world"]);
+    let cursor = first["result"]["nextCursor"].as_str().expect("more pages");
+
+    let list = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list", "params": {"cursor": cursor}});
+    input.write_all(format!("{}\n", list).as_bytes()).await.unwrap();
+    let second = read_message(&mut output).await;
+    assert_eq!(second["result"]["tools"].as_array().unwrap().len(), 1);
+    assert_eq!(second["result"]["tools"][0]["name"], "search");
+    assert!(second["result"].get("nextCursor").is_none());
+}
+
+#[tokio::test]
+async fn test_stdio_resource_updated_notification() {
+    let server = Arc::new(McpServer::new());
+    server
+        .register_resource(Box::new(mecp::resources::mock::MockResource::new()))
+        .await
+        .unwrap();
+    let (mut input, mut output) = start_stdio_server_with(Arc::clone(&server)).await;
+
+    let subscribe = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "resources/subscribe",
+        "params": {"uri": "mock://example/resource"}
+    });
+    input.write_all(format!("{}\n", subscribe).as_bytes()).await.unwrap();
+    assert_eq!(read_message(&mut output).await["result"], json!({}));
+
+    // Only subscribed resources are announced
+    assert_eq!(server.notify_resource_updated("mock://other").await, 0);
+    assert_eq!(server.notify_resource_updated("mock://example/resource").await, 1);
+
+    let notification = read_message(&mut output).await;
+    assert_eq!(notification["method"], "notifications/resources/updated");
+    assert_eq!(notification["params"]["uri"], "mock://example/resource");
+    assert!(notification.get("id").is_none());
+
+    let unsubscribe = json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "resources/unsubscribe",
+        "params": {"uri": "mock://example/resource"}
+    });
+    input.write_all(format!("{}\n", unsubscribe).as_bytes()).await.unwrap();
+    assert_eq!(read_message(&mut output).await["id"], 2);
+    assert_eq!(server.notify_resource_updated("mock://example/resource").await, 0);
+}
+
+#[tokio::test]
+async fn test_stdio_dynamic_registration_notifies_client() {
+    let server = Arc::new(McpServer::new());
+    server
+        .register_tool(Box::new(mecp::tools::mock::HelloWorldTool::new()))
+        .await
+        .unwrap();
+    let (mut input, mut output) = start_stdio_server_with(Arc::clone(&server)).await;
+
+    // Make sure the session exists before changing the registry
+    let ping = json!({"jsonrpc": "2.0", "id": 1, "method": "ping"});
+    input.write_all(format!("{}\n", ping).as_bytes()).await.unwrap();
+    assert_eq!(read_message(&mut output).await["id"], 1);
+
+    server
+        .register_tool(Box::new(mecp::tools::mock::SearchTool::new()))
+        .await
+        .unwrap();
+    assert_eq!(read_message(&mut output).await["method"], "notifications/tools/list_changed");
+
+    assert!(server.set_tool_enabled("hello_world", false).await);
+    assert_eq!(read_message(&mut output).await["method"], "notifications/tools/list_changed");
+    // Disabling twice is not a change
+    assert!(server.set_tool_enabled("hello_world", false).await);
+
+    let list = json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+    input.write_all(format!("{}\n", list).as_bytes()).await.unwrap();
+    let response = read_message(&mut output).await;
+    assert_eq!(response["id"], 2);
+    let tools = response["result"]["tools"].as_array().unwrap();
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0]["name"], "search");
+    assert!(server.call_tool("hello_world", json!({})).await.is_err());
+
+    assert!(server.replace_tool(Box::new(mecp::tools::mock::SearchTool::new())).await.unwrap());
+    // Registering a taken name is rejected rather than shadowing the tool
+    assert!(server
+        .register_tool(Box::new(mecp::tools::mock::SearchTool::new()))
+        .await
+        .is_err());
+    assert_eq!(read_message(&mut output).await["method"], "notifications/tools/list_changed");
+
+    assert!(server.unregister_tool("search").await);
+    assert_eq!(read_message(&mut output).await["method"], "notifications/tools/list_changed");
+    assert!(!server.unregister_tool("search").await);
+
+    server
+        .register_prompt(Box::new(mecp::prompts::mock::MockPrompt::new()))
+        .await
+        .unwrap();
+    assert_eq!(read_message(&mut output).await["method"], "notifications/prompts/list_changed");
+
+    assert!(!server.unregister_resource("mock://missing").await);
+    server
+        .register_resource(Box::new(mecp::resources::mock::MockResource::new()))
+        .await
+        .unwrap();
+    assert_eq!(read_message(&mut output).await["method"], "notifications/resources/list_changed");
+}
+
+/// Always fails, like a tool whose upstream service is down
+#[mecp::tools::tool]
+async fn flaky_lookup() -> anyhow::Result<String> {
+    anyhow::bail!("upstream timed out")
+}
+
+#[tokio::test]
+async fn test_stdio_tool_failure_is_error_result() {
+    let server = Arc::new(McpServer::new());
+    server.register_tool(Box::new(FlakyLookupTool)).await.unwrap();
+    let (mut input, mut output) = start_stdio_server_with(server).await;
+
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {"name": "flaky_lookup"}
+    });
+    input.write_all(format!("{}\n", call).as_bytes()).await.unwrap();
+
+    // A failing tool is a result the model can read, not a protocol error
+    let response = read_message(&mut output).await;
+    assert!(response.get("error").is_none());
+    assert_eq!(response["result"]["isError"], true);
+    assert_eq!(response["result"]["content"][0]["text"], "upstream timed out");
+}
+
+#[tokio::test]
+async fn test_stdio_log_messages() {
+    let server = Arc::new(McpServer::new());
+    server
+        .register_tool(Box::new(mecp::tools::mock::LongTaskTool::new()))
+        .await
+        .unwrap();
+    let (mut input, mut output) = start_stdio_server_with(server).await;
+
+    let set_level = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "logging/setLevel",
+        "params": {"level": "debug"}
+    });
+    input.write_all(format!("{}\n", set_level).as_bytes()).await.unwrap();
+    assert_eq!(read_message(&mut output).await["result"], json!({}));
+
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": {"name": "long_task", "arguments": {"steps": 2, "delay_ms": 10}}
+    });
+    input.write_all(format!("{}\n", call).as_bytes()).await.unwrap();
+
+    let message = read_message(&mut output).await;
+    assert_eq!(message["method"], "notifications/message");
+    assert_eq!(
+        message["params"],
+        json!({"level": "info", "logger": "long_task", "data": "Starting 2 steps"})
+    );
+    for step in 1..=2 {
+        let message = read_message(&mut output).await;
+        assert_eq!(message["params"]["level"], "debug");
+        assert_eq!(message["params"]["data"], json!({"step": step, "of": 2}));
+    }
+    assert_eq!(read_message(&mut output).await["id"], 2);
+
+    // Less severe messages than the level set are dropped
+    let set_level = json!({
+        "jsonrpc": "2.0",
+        "id": 3,
+        "method": "logging/setLevel",
+        "params": {"level": "warning"}
+    });
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 4,
+        "method": "tools/call",
+        "params": {"name": "long_task", "arguments": {"steps": 1, "delay_ms": 10}}
+    });
+    input.write_all(format!("{}\n", set_level).as_bytes()).await.unwrap();
+    assert_eq!(read_message(&mut output).await["id"], 3);
+    input.write_all(format!("{}\n", call).as_bytes()).await.unwrap();
+    assert_eq!(read_message(&mut output).await["id"], 4);
+
+    let set_level = json!({
+        "jsonrpc": "2.0",
+        "id": 5,
+        "method": "logging/setLevel",
+        "params": {"level": "verbose"}
+    });
+    input.write_all(format!("{}\n", set_level).as_bytes()).await.unwrap();
+    assert_eq!(read_message(&mut output).await["error"]["code"], -32602);
+}
+
+#[tokio::test]
+async fn test_stdio_sampling() {
+    let server = Arc::new(McpServer::new());
+    server
+        .register_tool(Box::new(mecp::tools::mock::SummarizeTool))
+        .await
+        .unwrap();
+    let (mut input, mut output) = start_stdio_server_with(server).await;
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "tools/call",
+        "params": {"name": "summarize", "arguments": {"text": "A long story"}}
+    });
+
+    // Clients that don't offer sampling are never asked
+    input.write_all(format!("{}\n", call).as_bytes()).await.unwrap();
+    let response = read_message(&mut output).await;
+    assert_eq!(response["result"]["isError"], true);
+    assert!(response["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("does not support sampling"));
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-06-18",
+            "capabilities": {"sampling": {}},
+            "clientInfo": {"name": "desktop-host", "version": "1.0.0"}
+        }
+    });
+    input.write_all(format!("{}\n", initialize).as_bytes()).await.unwrap();
+    assert_eq!(read_message(&mut output).await["id"], 2);
+
+    // A client that declines is reported as a tool failure
+    input.write_all(format!("{}\n", call).as_bytes()).await.unwrap();
+    let request = read_message(&mut output).await;
+    assert_eq!(request["method"], "sampling/createMessage");
+    assert_eq!(request["params"]["maxTokens"], 200);
+    let decline = json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "error": {"code": -1, "message": "User rejected sampling request"}
+    });
+    input.write_all(format!("{}\n", decline).as_bytes()).await.unwrap();
+
+    let response = read_message(&mut output).await;
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["result"]["isError"], true);
+    assert!(response["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .contains("User rejected sampling request"));
+}
+
+#[tokio::test]
+async fn test_stdio_roots() {
+    let dir = std::env::temp_dir().join(format!("mecp-roots-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(dir.join("granted")).unwrap();
+    std::fs::write(dir.join("granted/notes.txt"), "hi").unwrap();
+    std::os::unix::fs::symlink(&dir, dir.join("granted/up")).unwrap();
+    let granted = format!("file://{}/granted", dir.display());
+    let other = format!("file://{}", dir.display());
+
+    let server = Arc::new(McpServer::new());
+    server
+        .register_tool(Box::new(mecp::tools::mock::ListFilesTool))
+        .await
+        .unwrap();
+    let (mut input, mut output) = start_stdio_server_with(server).await;
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-06-18",
+            "capabilities": {"roots": {"listChanged": true}},
+            "clientInfo": {"name": "desktop-host", "version": "1.0.0"}
+        }
+    });
+    input.write_all(format!("{}\n", initialize).as_bytes()).await.unwrap();
+    assert_eq!(read_message(&mut output).await["id"], 1);
+
+    // The first call asks the client for its roots
+    let call = |id: u64, uri: &str| {
+        json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": {"name": "list_files", "arguments": {"uri": uri}}
+        })
+    };
+    input.write_all(format!("{}\n", call(2, &granted)).as_bytes()).await.unwrap();
+    let request = read_message(&mut output).await;
+    assert_eq!(request["method"], "roots/list");
+    let roots = json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "result": {"roots": [{"uri": granted, "name": "granted"}]}
+    });
+    input.write_all(format!("{}\n", roots).as_bytes()).await.unwrap();
+
+    let response = read_message(&mut output).await;
+    assert_eq!(response["id"], 2);
+    let listing: Value = serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(listing["files"], json!(["notes.txt", "up"]));
+
+    // Later calls use the roots already listed, and stay inside them, even
+    // through escapes and symlinks
+    let escape = format!("{}/..", granted);
+    let encoded_escape = format!("{}/%2E%2E", granted);
+    let symlink_escape = format!("{}/up", granted);
+    for (id, uri) in [(3, &other), (4, &escape), (6, &encoded_escape), (7, &symlink_escape)] {
+        input.write_all(format!("{}\n", call(id, uri)).as_bytes()).await.unwrap();
+        let response = read_message(&mut output).await;
+        assert_eq!(response["id"], id);
+        assert_eq!(response["result"]["isError"], true, "{}", uri);
+    }
+
+    std::fs::create_dir(dir.join("granted/my notes")).unwrap();
+    input
+        .write_all(format!("{}\n", call(8, &format!("{}/my%20notes", granted))).as_bytes())
+        .await
+        .unwrap();
+    let response = read_message(&mut output).await;
+    assert_eq!(response["result"]["isError"], false);
+    let listing: Value = serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(listing["files"], json!([]));
+
+    // A change to the roots makes the server ask again
+    let changed = json!({"jsonrpc": "2.0", "method": "notifications/roots/list_changed"});
+    input.write_all(format!("{}\n", changed).as_bytes()).await.unwrap();
+    input.write_all(format!("{}\n", call(5, &other)).as_bytes()).await.unwrap();
+    let request = read_message(&mut output).await;
+    assert_eq!(request["method"], "roots/list");
+    let roots = json!({
+        "jsonrpc": "2.0",
+        "id": request["id"],
+        "result": {"roots": [{"uri": other}]}
+    });
+    input.write_all(format!("{}\n", roots).as_bytes()).await.unwrap();
+
+    let response = read_message(&mut output).await;
+    assert_eq!(response["id"], 5);
+    let listing: Value = serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(listing["files"], json!(["granted"]));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_stdio_stale_roots_reply_is_discarded() {
+    let dir = std::env::temp_dir().join(format!("mecp-roots-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(dir.join("old")).unwrap();
+    std::fs::create_dir_all(dir.join("new")).unwrap();
+    std::fs::write(dir.join("new/notes.txt"), "hi").unwrap();
+    let old = format!("file://{}/old", dir.display());
+    let new = format!("file://{}/new", dir.display());
+
+    let server = Arc::new(McpServer::new());
+    server
+        .register_tool(Box::new(mecp::tools::mock::ListFilesTool))
+        .await
+        .unwrap();
+    let (mut input, mut output) = start_stdio_server_with(server).await;
+
+    let initialize = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "initialize",
+        "params": {
+            "protocolVersion": "2025-06-18",
+            "capabilities": {"roots": {"listChanged": true}},
+            "clientInfo": {"name": "desktop-host", "version": "1.0.0"}
+        }
+    });
+    input.write_all(format!("{}\n", initialize).as_bytes()).await.unwrap();
+    assert_eq!(read_message(&mut output).await["id"], 1);
+
+    let call = json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "tools/call",
+        "params": {"name": "list_files", "arguments": {"uri": new}}
+    });
+    input.write_all(format!("{}\n", call).as_bytes()).await.unwrap();
+    let request = read_message(&mut output).await;
+    assert_eq!(request["method"], "roots/list");
+
+    // The roots change before the client answers with the old ones
+    let changed = json!({"jsonrpc": "2.0", "method": "notifications/roots/list_changed"});
+    input.write_all(format!("{}\n", changed).as_bytes()).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    let stale = json!({"jsonrpc": "2.0", "id": request["id"], "result": {"roots": [{"uri": old}]}});
+    input.write_all(format!("{}\n", stale).as_bytes()).await.unwrap();
+
+    // The stale answer is dropped and the server asks again
+    let request = read_message(&mut output).await;
+    assert_eq!(request["method"], "roots/list");
+    let fresh = json!({"jsonrpc": "2.0", "id": request["id"], "result": {"roots": [{"uri": new}]}});
+    input.write_all(format!("{}\n", fresh).as_bytes()).await.unwrap();
+
+    let response = read_message(&mut output).await;
+    assert_eq!(response["id"], 2);
+    assert_eq!(response["result"]["isError"], false);
+    let listing: Value = serde_json::from_str(response["result"]["content"][0]["text"].as_str().unwrap()).unwrap();
+    assert_eq!(listing["files"], json!(["notes.txt"]));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_stdio_requires_api_key_once_keys_are_set() {
+    let server = Arc::new(McpServer::new());
+    server.add_api_key("viewer-key", "viewer").await;
+
+    let (_input, server_in) = tokio::io::duplex(1024);
+    let (server_out, _output) = tokio::io::duplex(1024);
+    let error = StdioServer::new(Arc::clone(&server))
+        .serve(server_in, server_out)
+        .await
+        .unwrap_err();
+    assert_eq!(error.to_string(), "Missing API key");
+
+    let (_input, server_in) = tokio::io::duplex(1024);
+    let (server_out, _output) = tokio::io::duplex(1024);
+    let error = StdioServer::new(server)
+        .with_api_key("stolen-key")
+        .serve(server_in, server_out)
+        .await
+        .unwrap_err();
+    assert_eq!(error.to_string(), "Unknown API key");
+}
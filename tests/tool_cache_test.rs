@@ -0,0 +1,142 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+use mecp::core::cache::{ToolCache, NO_CACHE_META};
+use mecp::core::context::RequestContext;
+use mecp::core::error::McpError;
+use mecp::core::metrics::MetricsCollector;
+use mecp::core::middleware::{Call, CallResult, Middleware};
+use mecp::core::server::McpServer;
+use mecp::tools::{tool, Tool};
+
+static LOOKUPS: AtomicUsize = AtomicUsize::new(0);
+static ROLLS: AtomicUsize = AtomicUsize::new(0);
+static QUOTES: AtomicUsize = AtomicUsize::new(0);
+
+/// Looks up an exchange rate, slowly
+#[tool(cache_ttl_secs = 60)]
+async fn exchange_rate(from: String, to: String, date: Option<String>) -> Result<String> {
+    let calls = LOOKUPS.fetch_add(1, Ordering::SeqCst) + 1;
+    Ok(format!("{}/{} on {}: call {}", from, to, date.as_deref().unwrap_or("today"), calls))
+}
+
+/// Rolls a die
+#[tool]
+async fn roll() -> Result<String> {
+    Ok(ROLLS.fetch_add(1, Ordering::SeqCst).to_string())
+}
+
+/// Quotes a stock price
+#[tool(cache_ttl_secs = 60)]
+async fn quote(symbol: String) -> Result<String> {
+    Ok(format!("{}: call {}", symbol, QUOTES.fetch_add(1, Ordering::SeqCst) + 1))
+}
+
+/// Counts the calls that reach it
+struct Counter(Arc<AtomicUsize>);
+
+#[async_trait]
+impl Middleware for Counter {
+    async fn before(&self, _call: &mut Call) -> Result<Option<CallResult>, McpError> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(None)
+    }
+}
+
+async fn server(metrics: Arc<MetricsCollector>) -> McpServer {
+    let server = McpServer::new();
+    server.register_tool(Box::new(ExchangeRateTool)).await.unwrap();
+    server.register_tool(Box::new(RollTool)).await.unwrap();
+    server.set_tool_cache(ToolCache::new(16).with_metrics(metrics)).await;
+    server
+}
+
+#[tokio::test]
+async fn test_cacheable_tool_results_are_reused() {
+    let metrics = Arc::new(MetricsCollector::new());
+    let server = server(Arc::clone(&metrics)).await;
+
+    let first = server
+        .call_tool("exchange_rate", json!({"from": "EUR", "to": "USD"}))
+        .await
+        .unwrap();
+    let second = server
+        .call_tool("exchange_rate", json!({"to": "USD", "from": "EUR"}))
+        .await
+        .unwrap();
+    assert_eq!(first.output, second.output);
+
+    let other = server
+        .call_tool("exchange_rate", json!({"from": "EUR", "to": "GBP"}))
+        .await
+        .unwrap();
+    assert_ne!(first.output, other.output);
+
+    let stats = metrics.cache_stats();
+    assert_eq!((stats.hits, stats.misses), (1, 2));
+
+    // Tools that don't declare a TTL always run
+    let first = server.call_tool("roll", json!({})).await.unwrap();
+    let second = server.call_tool("roll", json!({})).await.unwrap();
+    assert_ne!(first.output, second.output);
+    assert_eq!(metrics.cache_stats().misses, 2);
+}
+
+#[tokio::test]
+async fn test_cache_bypass_and_invalidation() {
+    let server = server(Arc::new(MetricsCollector::new())).await;
+    let args = json!({"from": "JPY", "to": "CHF", "date": "2024-01-02"});
+
+    let cached = server.call_tool("exchange_rate", args.clone()).await.unwrap();
+
+    // The client's flag skips the cached result and refreshes it
+    let ctx = RequestContext::new(
+        Some(json!(1)),
+        Some(&json!({"_meta": {NO_CACHE_META: true}})),
+        None,
+        None,
+        CancellationToken::new(),
+    );
+    let fresh = server
+        .call_tool_with_context("exchange_rate", args.clone(), &ctx)
+        .await
+        .unwrap();
+    assert_ne!(cached.output, fresh.output);
+    assert_eq!(server.call_tool("exchange_rate", args.clone()).await.unwrap().output, fresh.output);
+
+    let cache = server.tool_cache().await.unwrap();
+    cache.invalidate("exchange_rate", &args);
+    let refetched = server.call_tool("exchange_rate", args.clone()).await.unwrap();
+    assert_ne!(refetched.output, fresh.output);
+
+    // Unregistering a tool drops its results
+    assert!(!cache.is_empty());
+    server.unregister_tool("exchange_rate").await;
+    assert!(cache.is_empty());
+}
+
+#[tokio::test]
+async fn test_declared_ttl_metadata() {
+    let metadata = ExchangeRateTool.metadata().await.unwrap();
+    assert_eq!(metadata.cache_ttl, Some(std::time::Duration::from_secs(60)));
+    assert_eq!(RollTool.metadata().await.unwrap().cache_ttl, None);
+}
+
+#[tokio::test]
+async fn test_middleware_sees_cached_calls() {
+    let server = McpServer::new();
+    server.register_tool(Box::new(QuoteTool)).await.unwrap();
+    let seen = Arc::new(AtomicUsize::new(0));
+    server.set_tool_cache(ToolCache::new(16)).await;
+    server.add_middleware(Box::new(Counter(Arc::clone(&seen)))).await;
+
+    let first = server.call_tool("quote", json!({"symbol": "ACME"})).await.unwrap();
+    let second = server.call_tool("quote", json!({"symbol": "ACME"})).await.unwrap();
+    assert_eq!(first.output, second.output);
+    assert_eq!(QUOTES.load(Ordering::SeqCst), 1);
+    assert_eq!(seen.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,106 @@
+use anyhow::Result;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use mecp::core::error::McpError;
+use mecp::core::limits::ToolLimits;
+use mecp::core::server::McpServer;
+use mecp::tools::tool;
+use serde_json::json;
+
+/// Sleeps for the given number of milliseconds
+#[tool]
+async fn nap(millis: u64) -> Result<String> {
+    tokio::time::sleep(Duration::from_millis(millis)).await;
+    Ok("rested".to_string())
+}
+
+/// Panics instead of returning an error
+#[tool]
+async fn divide(dividend: i64, divisor: i64) -> Result<String> {
+    if divisor == 0 {
+        panic!("attempt to divide by zero");
+    }
+    Ok((dividend / divisor).to_string())
+}
+
+static RUNNING: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// Records how many calls overlap
+#[tool]
+async fn render() -> Result<String> {
+    let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+    PEAK.fetch_max(running, Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    RUNNING.fetch_sub(1, Ordering::SeqCst);
+    Ok("rendered".to_string())
+}
+
+#[tokio::test]
+async fn test_tool_timeout() {
+    let server = McpServer::new();
+    server.register_tool(Box::new(NapTool)).await.unwrap();
+    server
+        .set_default_tool_limits(ToolLimits {
+            timeout: Some(Duration::from_millis(50)),
+            max_concurrency: None,
+        })
+        .await;
+
+    let result = server.call_tool("nap", json!({"millis": 1})).await.unwrap();
+    assert_eq!(result.output, json!("rested"));
+
+    let err = server.call_tool("nap", json!({"millis": 5_000})).await.unwrap_err();
+    assert!(matches!(err, McpError::ToolExecution { .. }));
+    assert!(err.to_string().contains("timed out"));
+}
+
+#[tokio::test]
+async fn test_tool_panic_is_execution_error() {
+    let server = McpServer::new();
+    server.register_tool(Box::new(DivideTool)).await.unwrap();
+
+    let err = server
+        .call_tool("divide", json!({"dividend": 1, "divisor": 0}))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, McpError::ToolExecution { .. }));
+    assert!(err.to_string().contains("attempt to divide by zero"));
+
+    // The server keeps serving after the panic
+    let result = server
+        .call_tool("divide", json!({"dividend": 6, "divisor": 3}))
+        .await
+        .unwrap();
+    assert_eq!(result.output, json!("2"));
+}
+
+#[tokio::test]
+async fn test_tool_concurrency_limit() {
+    let server = Arc::new(McpServer::new());
+    server.register_tool(Box::new(RenderTool)).await.unwrap();
+    server
+        .set_tool_limits(
+            "render",
+            ToolLimits {
+                timeout: None,
+                max_concurrency: Some(2),
+            },
+        )
+        .await;
+    assert_eq!(server.tool_limits("render").await.max_concurrency, Some(2));
+
+    let calls: Vec<_> = (0..6)
+        .map(|_| {
+            let server = Arc::clone(&server);
+            tokio::spawn(async move { server.call_tool("render", json!({})).await })
+        })
+        .collect();
+    for call in calls {
+        call.await.unwrap().unwrap();
+    }
+
+    assert_eq!(PEAK.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,265 @@
+use anyhow::Result;
+use mecp::core::context::RequestContext;
+use mecp::core::error::McpError;
+use mecp::core::server::McpServer;
+use mecp::core::types::{JsonValue, ToolResult};
+use mecp::tools::mock::{EchoTool, MAX_ECHO_TIMES};
+use mecp::tools::{tool, InputSchema, Tool, ToolMetadata};
+use serde::Deserialize;
+use serde_json::json;
+
+/// How results are ordered
+#[derive(Debug, Deserialize, InputSchema)]
+#[serde(rename_all = "lowercase")]
+enum Order {
+    #[serde(rename = "asc")]
+    Ascending,
+    Descending,
+}
+
+/// Filters applied to a search
+#[derive(Debug, Deserialize, InputSchema)]
+#[serde(deny_unknown_fields)]
+struct Filters {
+    /// Only include items with these tags
+    tags: Vec<String>,
+    /// Include archived items
+    #[serde(default)]
+    archived: bool,
+    #[serde(rename = "minScore")]
+    min_score: Option<f64>,
+}
+
+/// Where a page of results starts
+#[derive(Debug, Deserialize, InputSchema)]
+struct Paging {
+    offset: u32,
+    limit: Option<u32>,
+}
+
+/// Asks for a report
+#[derive(Debug, Deserialize, InputSchema)]
+#[serde(rename_all = "UPPERCASE")]
+struct ReportRequest {
+    min_score: f64,
+    #[serde(skip)]
+    cache_key: String,
+    #[serde(skip_deserializing)]
+    computed: bool,
+    #[serde(default)]
+    verbose: bool,
+    #[serde(flatten)]
+    paging: Paging,
+}
+
+/// How much a report says
+#[derive(Debug, Default, Deserialize, InputSchema)]
+#[serde(default)]
+struct Verbosity {
+    level: u8,
+    quiet: bool,
+}
+
+/// Searches the catalogue
+#[tool]
+async fn catalogue_search(
+    #[arg(description = "Search terms")] query: String,
+    filters: Option<Filters>,
+    order: Option<Order>,
+) -> Result<JsonValue> {
+    let filters = filters.unwrap_or(Filters {
+        tags: Vec::new(),
+        archived: false,
+        min_score: None,
+    });
+    Ok(json!({
+        "query": query,
+        "tags": filters.tags,
+        "archived": filters.archived,
+        "min_score": filters.min_score,
+        "descending": matches!(order, Some(Order::Descending)),
+    }))
+}
+
+#[tool(name = "whoami", description = "Reports the calling request")]
+async fn request_info(ctx: &RequestContext) -> Result<String> {
+    Ok(format!("request {}", ctx.request_id().map(|id| id.to_string()).unwrap_or_default()))
+}
+
+/// Sets the speaker volume
+#[tool]
+async fn set_volume(level: u8) -> Result<String> {
+    Ok(format!("volume {}", level))
+}
+
+/// Removes an item from the catalogue
+#[tool(title = "Remove Item", destructive_hint = false, idempotent_hint)]
+async fn catalogue_remove(id: String) -> Result<String> {
+    Ok(format!("removed {}", id))
+}
+
+#[tokio::test]
+async fn test_tool_metadata_from_function() {
+    let metadata = CatalogueSearchTool.metadata().await.unwrap();
+
+    assert_eq!(metadata.name, "catalogue_search");
+    assert_eq!(metadata.description, "Searches the catalogue");
+
+    let schema = metadata.input_schema.unwrap();
+    assert_eq!(schema["required"], json!(["query"]));
+    assert_eq!(
+        schema["properties"]["query"],
+        json!({"type": "string", "description": "Search terms"})
+    );
+    assert_eq!(
+        schema["properties"]["order"],
+        json!({"type": ["string", "null"], "enum": ["asc", "descending", null], "description": "How results are ordered"})
+    );
+
+    let filters = &schema["properties"]["filters"];
+    assert_eq!(filters["description"], "Filters applied to a search");
+    assert_eq!(filters["required"], json!(["tags"]));
+    assert_eq!(filters["additionalProperties"], false);
+    assert_eq!(filters["properties"]["tags"]["items"], json!({"type": "string"}));
+    assert_eq!(filters["properties"]["tags"]["description"], "Only include items with these tags");
+    assert_eq!(filters["type"], json!(["object", "null"]));
+    assert_eq!(filters["properties"]["minScore"], json!({"type": ["number", "null"]}));
+}
+
+#[tokio::test]
+async fn test_tool_name_and_description_overrides() {
+    let metadata = RequestInfoTool.metadata().await.unwrap();
+
+    assert_eq!(metadata.name, "whoami");
+    assert_eq!(metadata.description, "Reports the calling request");
+    assert_eq!(metadata.input_schema.unwrap()["properties"], json!({}));
+}
+
+#[test]
+fn test_schema_follows_serde_attributes() {
+    let schema = ReportRequest::schema();
+    let mut properties: Vec<&str> = schema["properties"].as_object().unwrap().keys().map(String::as_str).collect();
+    properties.sort();
+    assert_eq!(properties, ["MIN_SCORE", "VERBOSE", "limit", "offset"]);
+    assert_eq!(schema["required"], json!(["MIN_SCORE", "offset"]));
+
+    // The schema describes exactly what serde reads
+    let request: ReportRequest = serde_json::from_value(json!({"MIN_SCORE": 0.5, "offset": 20})).unwrap();
+    assert_eq!(request.min_score, 0.5);
+    assert!(request.cache_key.is_empty() && !request.computed && !request.verbose);
+    assert_eq!((request.paging.offset, request.paging.limit), (20, None));
+
+    assert_eq!(Verbosity::schema()["required"], json!([]));
+    let verbosity: Verbosity = serde_json::from_value(json!({})).unwrap();
+    assert_eq!((verbosity.level, verbosity.quiet), (0, false));
+}
+
+#[tokio::test]
+async fn test_tool_annotations() {
+    let annotations = CatalogueRemoveTool.metadata().await.unwrap().annotations.unwrap();
+    assert_eq!(annotations.title.as_deref(), Some("Remove Item"));
+    assert_eq!(annotations.read_only_hint, None);
+    assert_eq!(annotations.destructive_hint, Some(false));
+    assert_eq!(annotations.idempotent_hint, Some(true));
+
+    let annotations = EchoTool.metadata().await.unwrap().annotations.unwrap();
+    assert!(annotations.is_read_only());
+    assert!(!annotations.is_open_world());
+
+    assert!(CatalogueSearchTool.metadata().await.unwrap().annotations.is_none());
+}
+
+#[tokio::test]
+async fn test_generated_tool_runs_through_server() {
+    let server = McpServer::new();
+    server.register_tool(Box::new(CatalogueSearchTool)).await.unwrap();
+    server.register_tool(Box::new(EchoTool)).await.unwrap();
+
+    let result = server
+        .call_tool(
+            "catalogue_search",
+            json!({"query": "lamp", "filters": {"tags": ["desk"]}, "order": "descending"}),
+        )
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(
+        result.output,
+        json!({"query": "lamp", "tags": ["desk"], "archived": false, "min_score": null, "descending": true})
+    );
+
+    // serde reads null as None, so the schema lets it through
+    let result = server
+        .call_tool("catalogue_search", json!({"query": "lamp", "filters": null, "order": null}))
+        .await
+        .unwrap();
+    assert_eq!(result.output["descending"], false);
+
+    let result = server
+        .call_tool("echo", json!({"message": "hi", "times": 3}))
+        .await
+        .unwrap();
+    assert_eq!(result.output, json!("hi hi hi"));
+
+    let result = server
+        .call_tool("echo", json!({"message": "hi", "times": u32::MAX}))
+        .await
+        .unwrap();
+    assert_eq!(result.output.as_str().unwrap().split(' ').count(), MAX_ECHO_TIMES as usize);
+}
+
+/// Declares a schema keyword arguments can't be checked against
+struct TaggedTool;
+
+#[async_trait::async_trait]
+impl Tool for TaggedTool {
+    async fn metadata(&self) -> Result<ToolMetadata> {
+        Ok(ToolMetadata {
+            name: "tagged".to_string(),
+            input_schema: Some(json!({
+                "type": "object",
+                "properties": {"tags": {"type": "array", "contains": {"const": "urgent"}}}
+            })),
+            ..Default::default()
+        })
+    }
+
+    async fn execute(&self, _params: JsonValue) -> Result<ToolResult> {
+        Ok(ToolResult {
+            success: true,
+            output: json!("tagged"),
+            ..Default::default()
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_unsupported_schema_keywords_refused_at_registration() {
+    let server = McpServer::new();
+
+    let err = server.register_tool(Box::new(TaggedTool)).await.unwrap_err();
+    assert!(err.to_string().contains("/properties/tags/contains"), "{}", err);
+    assert_eq!(server.tool_count().await, 0);
+}
+
+#[tokio::test]
+async fn test_generated_schema_rejects_bad_arguments() {
+    let server = McpServer::new();
+    server.register_tool(Box::new(CatalogueSearchTool)).await.unwrap();
+    server.register_tool(Box::new(SetVolumeTool)).await.unwrap();
+
+    // Values that don't fit the argument's integer type are invalid params,
+    // not a failed call
+    let err = server.call_tool("set_volume", json!({"level": 300})).await.unwrap_err();
+    assert!(matches!(err, McpError::InvalidParams { .. }), "{}", err);
+    assert_eq!(server.call_tool("set_volume", json!({"level": 255})).await.unwrap().output, json!("volume 255"));
+
+    for params in [
+        json!({}),
+        json!({"query": "lamp", "order": "sideways"}),
+        json!({"query": "lamp", "filters": {"tags": [], "colour": "red"}}),
+    ] {
+        let err = server.call_tool("catalogue_search", params).await.unwrap_err();
+        assert!(matches!(err, McpError::InvalidParams { .. }), "{}", err);
+    }
+}